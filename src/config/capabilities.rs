@@ -0,0 +1,305 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::types::Config;
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::provider_trait::{ChatRequest, MessageContent, MessagePart, ProviderTransformer};
+
+/// What a model can accept: context size, output limit and optional features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    pub context_window: u32,
+    pub max_output_tokens: u32,
+    pub tools: bool,
+    pub vision: bool,
+    pub thinking: bool,
+    pub streaming: bool,
+    /// Document (PDF) input
+    pub documents: bool,
+    pub document_fallback: DocumentFallback,
+}
+
+/// What happens to document parts sent to a model that cannot read them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFallback {
+    /// Inline text documents; reject binary ones (PDFs, URLs), whose text is not extracted
+    #[default]
+    Auto,
+    /// Replace each document with its text, or a placeholder for binary files
+    Text,
+    /// Fail the request with an `invalid_request_error`
+    Reject,
+}
+
+/// Per-model overrides from `Provider.capabilities`; unset fields keep the built-in value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilityOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vision: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streaming: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_fallback: Option<DocumentFallback>,
+}
+
+/// What a routed request needs from its target model. Thinking is not listed: it can be
+/// dropped without breaking the request, whereas tools and images cannot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapabilityNeeds {
+    pub input_tokens: usize,
+    pub tools: bool,
+    pub vision: bool,
+}
+
+const fn caps(context_window: u32, max_output_tokens: u32, tools: bool, vision: bool, thinking: bool) -> ModelCapabilities {
+    ModelCapabilities {
+        context_window,
+        max_output_tokens,
+        tools,
+        vision,
+        thinking,
+        streaming: true,
+        documents: false,
+        document_fallback: DocumentFallback::Auto,
+    }
+}
+
+/// Built-in defaults keyed by model-name prefix, most specific first. Names are matched
+/// after normalisation (see `CapabilityRegistry::normalize_model`).
+const BUILTIN_CAPABILITIES: &[(&str, ModelCapabilities)] = &[
+    ("claude-opus-4", caps(200_000, 32_000, true, true, true).with_documents()),
+    ("claude-sonnet-4", caps(200_000, 64_000, true, true, true).with_documents()),
+    ("claude-haiku-4", caps(200_000, 64_000, true, true, true).with_documents()),
+    ("claude-3-7-sonnet", caps(200_000, 64_000, true, true, true).with_documents()),
+    ("claude-3-5-sonnet", caps(200_000, 8_192, true, true, false).with_documents()),
+    ("claude-3-5-haiku", caps(200_000, 8_192, true, true, false).with_documents()),
+    ("claude-3-opus", caps(200_000, 4_096, true, true, false)),
+    ("claude-3-sonnet", caps(200_000, 4_096, true, true, false)),
+    ("claude-3-haiku", caps(200_000, 4_096, true, true, false)),
+    ("gpt-4-1", caps(1_047_576, 32_768, true, true, false)),
+    ("gpt-4o", caps(128_000, 16_384, true, true, false)),
+    ("gpt-3-5-turbo", caps(16_385, 4_096, true, false, false)),
+    ("o4-mini", caps(200_000, 100_000, true, true, true)),
+    ("o3-mini", caps(200_000, 100_000, true, false, true)),
+    ("o3", caps(200_000, 100_000, true, true, true)),
+    ("o1", caps(200_000, 100_000, true, true, true)),
+    ("gemini-2-5-pro", caps(1_048_576, 65_536, true, true, true).with_documents()),
+    ("gemini-2-5-flash", caps(1_048_576, 65_536, true, true, true).with_documents()),
+    ("gemini-2-0-flash", caps(1_048_576, 8_192, true, true, false).with_documents()),
+    ("gemini-1-5-pro", caps(2_097_152, 8_192, true, true, false).with_documents()),
+    ("gemini-1-5-flash", caps(1_048_576, 8_192, true, true, false).with_documents()),
+    ("deepseek-chat", caps(128_000, 8_192, true, false, false)),
+    ("deepseek-reasoner", caps(128_000, 65_536, false, false, true)),
+];
+
+impl ModelCapabilities {
+    /// Used for models the registry knows nothing about, so nothing is clamped or dropped.
+    pub const fn unrestricted() -> Self {
+        Self {
+            context_window: u32::MAX,
+            max_output_tokens: u32::MAX,
+            tools: true,
+            vision: true,
+            thinking: true,
+            streaming: true,
+            documents: true,
+            document_fallback: DocumentFallback::Auto,
+        }
+    }
+
+    const fn with_documents(mut self) -> Self {
+        self.documents = true;
+        self
+    }
+
+    /// Narrows these capabilities to what `transformer` can express: documents only pass
+    /// natively to formats that have a document block.
+    pub fn for_transformer(mut self, transformer: &dyn ProviderTransformer) -> Self {
+        self.documents &= transformer.supports_documents();
+        self
+    }
+
+    pub fn with_overrides(mut self, overrides: &ModelCapabilityOverrides) -> Self {
+        if let Some(context_window) = overrides.context_window {
+            self.context_window = context_window;
+        }
+        if let Some(max_output_tokens) = overrides.max_output_tokens {
+            self.max_output_tokens = max_output_tokens;
+        }
+        if let Some(tools) = overrides.tools {
+            self.tools = tools;
+        }
+        if let Some(vision) = overrides.vision {
+            self.vision = vision;
+        }
+        if let Some(thinking) = overrides.thinking {
+            self.thinking = thinking;
+        }
+        if let Some(streaming) = overrides.streaming {
+            self.streaming = streaming;
+        }
+        if let Some(documents) = overrides.documents {
+            self.documents = documents;
+        }
+        if let Some(document_fallback) = overrides.document_fallback {
+            self.document_fallback = document_fallback;
+        }
+        self
+    }
+
+    pub fn can_serve(&self, needs: &CapabilityNeeds) -> bool {
+        needs.input_tokens <= self.context_window as usize
+            && (!needs.tools || self.tools)
+            && (!needs.vision || self.vision)
+    }
+
+    /// Clamps `max_tokens` and strips features the model cannot accept, so the upstream
+    /// does not reject the whole request. Documents are the exception when
+    /// `document_fallback` rejects them: dropping them silently would change the answer.
+    pub fn apply_to_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        if let Some(max_tokens) = request.max_tokens {
+            request.max_tokens = Some(max_tokens.min(self.max_output_tokens));
+        }
+        if !self.tools {
+            request.tools = None;
+            request.tool_choice = None;
+        }
+        if !self.thinking {
+            request.reasoning = None;
+        }
+        if !self.streaming {
+            request.stream = false;
+        }
+        if !self.vision {
+            for message in &mut request.messages {
+                if let MessageContent::Parts(parts) = &mut message.content {
+                    for part in parts.iter_mut().filter(|part| part.image_url.is_some()) {
+                        *part = MessagePart {
+                            part_type: "text".to_string(),
+                            text: Some("[image omitted: model does not support image input]".to_string()),
+                            tool_use_id: None,
+                            tool_name: None,
+                            tool_input: None,
+                            image_url: None,
+                            document: None,
+                            cache_control: part.cache_control.take(),
+                        };
+                    }
+                }
+            }
+        }
+        if !self.documents {
+            for message in &mut request.messages {
+                if let MessageContent::Parts(parts) = &mut message.content {
+                    for part in parts.iter_mut() {
+                        let Some(document) = part.document.take() else {
+                            continue;
+                        };
+                        let reject = match self.document_fallback {
+                            DocumentFallback::Auto => !document.has_text(),
+                            DocumentFallback::Text => false,
+                            DocumentFallback::Reject => true,
+                        };
+                        if reject {
+                            return Err(TransformerError::MessageConversion(format!(
+                                "Model {} does not support document input; remove the document or set \
+                                 `documents` / `document_fallback` in the provider's capabilities",
+                                request.model
+                            )));
+                        }
+                        *part = MessagePart {
+                            part_type: "text".to_string(),
+                            text: Some(document.fallback_text()),
+                            tool_use_id: None,
+                            tool_name: None,
+                            tool_input: None,
+                            image_url: None,
+                            document: None,
+                            cache_control: part.cache_control.take(),
+                        };
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CapabilityNeeds {
+    pub fn for_request(request: &ChatRequest, input_tokens: usize) -> Self {
+        let vision = request.messages.iter().any(|message| match &message.content {
+            MessageContent::Parts(parts) => parts.iter().any(|part| part.image_url.is_some()),
+            MessageContent::Text(_) => false,
+        });
+        Self {
+            input_tokens,
+            tools: request.tools.as_ref().is_some_and(|tools| !tools.is_empty()),
+            vision,
+        }
+    }
+}
+
+/// Built-in capabilities merged with the per-provider overrides from config.
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityRegistry {
+    overrides: HashMap<(String, String), ModelCapabilityOverrides>,
+}
+
+impl CapabilityRegistry {
+    pub fn from_config(config: &Config) -> Self {
+        let mut overrides = HashMap::new();
+        for provider in &config.providers {
+            for (model, model_overrides) in &provider.capabilities {
+                overrides.insert((provider.name.clone(), model.clone()), model_overrides.clone());
+            }
+        }
+        Self { overrides }
+    }
+
+    /// Capabilities from the built-in table, if the model is known.
+    pub fn builtin(model: &str) -> Option<ModelCapabilities> {
+        let normalized = Self::normalize_model(model);
+        BUILTIN_CAPABILITIES
+            .iter()
+            .find(|(prefix, _)| normalized.starts_with(prefix))
+            .map(|(_, capabilities)| *capabilities)
+    }
+
+    pub fn lookup(&self, provider: &str, model: &str) -> ModelCapabilities {
+        let capabilities = Self::builtin(model).unwrap_or(ModelCapabilities::unrestricted());
+        match self.overrides.get(&(provider.to_string(), model.to_string())) {
+            Some(overrides) => capabilities.with_overrides(overrides),
+            None => capabilities,
+        }
+    }
+
+    /// Looks up a `provider,model` route string as used in `Router` config.
+    pub fn lookup_route(&self, route: &str) -> ModelCapabilities {
+        match route.split_once(',') {
+            Some((provider, model)) => self.lookup(provider.trim(), model.trim()),
+            None => ModelCapabilities::unrestricted(),
+        }
+    }
+
+    /// Strips vendor prefixes (`anthropic/`, `us.anthropic.`) and turns version dots into
+    /// dashes so `anthropic/claude-3.5-sonnet` and `claude-3-5-sonnet-20241022` both match.
+    fn normalize_model(model: &str) -> String {
+        let base = model.rsplit('/').next().unwrap_or(model);
+        let base = match base.find("claude") {
+            Some(index) => &base[index..],
+            None => base,
+        };
+        base.to_ascii_lowercase().replace('.', "-")
+    }
+}
@@ -177,12 +177,15 @@ impl ConfigManager {
             non_interactive_mode: Some(false),
             api_timeout_ms: Some(600000),
             custom_router_path: None,
+            response_model: None,
+            project_roots: vec![],
             providers: vec![crate::config::types::Provider {
                 name: name.clone(),
                 api_base_url: base_url,
                 api_key: api_key,
                 models: vec![model.clone()],
                 transformer: None,
+                capabilities: std::collections::HashMap::new(),
             }],
             router: crate::config::types::RouterConfig {
                 default: format!("{},{}", name, model),
@@ -191,6 +194,7 @@ impl ConfigManager {
                 long_context: None,
                 long_context_threshold: Some(60000),
                 web_search: None,
+                rules: vec![],
             },
             transformers: None,
             extra: std::collections::HashMap::new(),
@@ -3,6 +3,8 @@ use std::env;
 
 pub const APP_NAME: &str = "code_routic";
 pub const CONFIG_DIR_NAME: &str = ".code-routic";
+/// 项目级配置文件名，放在项目目录树中
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".code-routic.json";
 
 pub fn get_home_dir() -> PathBuf {
     dirs::home_dir().expect("Failed to get home directory")
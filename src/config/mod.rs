@@ -1,3 +1,5 @@
+pub mod capabilities;
 pub mod config_manager;
 pub mod constants;
+pub mod project;
 pub mod types;
\ No newline at end of file
@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::config::constants::PROJECT_CONFIG_FILE_NAME;
+use crate::config::types::{Config, Provider, RouteRule};
+
+/// 请求头：项目目录，用于查找项目配置；由客户端设置，如 `ANTHROPIC_CUSTOM_HEADERS="x-ccr-project: $PWD"`
+pub const PROJECT_HEADER: &str = "x-ccr-project";
+
+/// 项目目录树中 `.code-routic.json` 的内容，按请求覆盖全局配置
+///
+/// 只能修改路由与新增提供商；项目文件不展开环境变量，避免仓库中的配置把本机密钥发往别处。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(rename = "Router", default)]
+    pub router: RouterOverrides,
+    /// 新增的提供商；与全局提供商同名的会被忽略，项目配置不能改写全局提供商的地址与密钥
+    #[serde(rename = "Providers", default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<Provider>,
+}
+
+/// `Router` 的覆盖项，未设置的字段沿用全局配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouterOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub think: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long_context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long_context_threshold: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_search: Option<String>,
+    /// 排在全局规则之前匹配
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RouteRule>,
+}
+
+impl ProjectConfig {
+    /// 从 `dir` 向上查找最近的 `.code-routic.json`
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|ancestor| ancestor.join(PROJECT_CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// 与 `find` 相同，但 `dir` 须位于某个 `PROJECT_ROOTS` 之内，且向上查找不越过该根目录；
+    /// 路径先规范化，`..` 与符号链接无法绕过限制
+    pub fn find_allowed(config: &Config, dir: &Path) -> Option<PathBuf> {
+        let dir = fs::canonicalize(dir).ok()?;
+        let root = config
+            .project_roots
+            .iter()
+            .filter_map(|root| fs::canonicalize(root).ok())
+            .find(|root| dir.starts_with(root))?;
+        dir.ancestors()
+            .take_while(|ancestor| ancestor.starts_with(&root))
+            .map(|ancestor| ancestor.join(PROJECT_CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read project config file: {:?}", path))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse project config file: {:?}", path))
+    }
+
+    /// 在全局配置上应用覆盖，返回生效的配置
+    pub fn apply(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        let router = &mut config.router;
+        let overrides = &self.router;
+        if let Some(default) = &overrides.default {
+            router.default = default.clone();
+        }
+        for (slot, value) in [
+            (&mut router.background, &overrides.background),
+            (&mut router.think, &overrides.think),
+            (&mut router.long_context, &overrides.long_context),
+            (&mut router.web_search, &overrides.web_search),
+        ] {
+            if value.is_some() {
+                *slot = value.clone();
+            }
+        }
+        if overrides.long_context_threshold.is_some() {
+            router.long_context_threshold = overrides.long_context_threshold;
+        }
+        router.rules.splice(0..0, overrides.rules.iter().cloned());
+
+        for provider in &self.providers {
+            if config.providers.iter().any(|existing| existing.name == provider.name) {
+                eprintln!("Ignoring project provider '{}': a provider with that name is already configured", provider.name);
+                continue;
+            }
+            config.providers.push(provider.clone());
+        }
+        config
+    }
+
+    /// 按 `x-ccr-project` 指定的目录解析本次请求生效的配置；目录不在 `PROJECT_ROOTS` 之内、
+    /// 没有项目配置或加载失败时使用全局配置
+    pub fn resolve<'a>(config: &'a Config, project_dir: Option<&str>) -> Cow<'a, Config> {
+        Self::resolve_with(config, project_dir, Self::load)
+    }
+
+    fn resolve_with<'a, P: Borrow<Self>>(
+        config: &'a Config,
+        project_dir: Option<&str>,
+        load: impl FnOnce(&Path) -> Result<P>,
+    ) -> Cow<'a, Config> {
+        let Some(path) = project_dir.filter(|dir| !dir.is_empty()).and_then(|dir| Self::find_allowed(config, Path::new(dir))) else {
+            return Cow::Borrowed(config);
+        };
+        match load(&path) {
+            Ok(project) => Cow::Owned(project.borrow().apply(config)),
+            Err(e) => {
+                eprintln!("Ignoring project config: {:#}", e);
+                Cow::Borrowed(config)
+            }
+        }
+    }
+}
+
+/// 按路径缓存已解析的项目配置，文件修改时间不变时不再读取和解析
+#[derive(Debug, Default)]
+pub struct ProjectConfigCache {
+    entries: Mutex<HashMap<PathBuf, (SystemTime, Arc<ProjectConfig>)>>,
+}
+
+impl ProjectConfigCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 与 `ProjectConfig::resolve` 相同，但项目配置取自缓存
+    pub fn resolve<'a>(&self, config: &'a Config, project_dir: Option<&str>) -> Cow<'a, Config> {
+        ProjectConfig::resolve_with(config, project_dir, |path| self.load(path))
+    }
+
+    pub fn load(&self, path: &Path) -> Result<Arc<ProjectConfig>> {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| format!("Failed to read project config file: {:?}", path))?;
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((cached, project)) = entries.get(path)
+            && *cached == modified
+        {
+            return Ok(project.clone());
+        }
+        // 加载失败时移除旧条目，与未缓存时的行为一致
+        entries.remove(path);
+        let project = Arc::new(ProjectConfig::load(path)?);
+        entries.insert(path.to_path_buf(), (modified, project.clone()));
+        Ok(project)
+    }
+}
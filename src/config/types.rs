@@ -1,6 +1,9 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::config::capabilities::ModelCapabilityOverrides;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(rename = "APIKEY")]
@@ -30,6 +33,14 @@ pub struct Config {
     #[serde(rename = "CUSTOM_ROUTER_PATH")]
     pub custom_router_path: Option<String>,
     
+    /// 响应中报告的模型名：回显客户端请求的模型（默认）或实际调用的上游模型
+    #[serde(rename = "RESPONSE_MODEL", skip_serializing_if = "Option::is_none")]
+    pub response_model: Option<ResponseModel>,
+    
+    /// 允许 `x-ccr-project` 指定的项目目录（含子目录）；为空时不加载项目配置
+    #[serde(rename = "PROJECT_ROOTS", default, skip_serializing_if = "Vec::is_empty")]
+    pub project_roots: Vec<String>,
+    
     #[serde(rename = "Providers")]
     pub providers: Vec<Provider>,
     
@@ -43,6 +54,17 @@ pub struct Config {
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// 响应体中 `model` 字段的取值策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseModel {
+    /// 回显客户端请求的模型名
+    #[default]
+    Requested,
+    /// 报告实际调用的上游模型名
+    Upstream,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Provider {
     pub name: String,
@@ -52,6 +74,9 @@ pub struct Provider {
     pub api_key: String,
     pub models: Vec<String>,
     pub transformer: Option<Transformer>,
+    /// 按模型覆盖内置的能力表（上下文窗口、最大输出、工具、视觉、思考、流式）
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub capabilities: HashMap<String, ModelCapabilityOverrides>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,7 +93,7 @@ pub struct ModelTransformer {
     pub use_transformers: Vec<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RouterConfig {
     pub default: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -81,8 +106,148 @@ pub struct RouterConfig {
     pub long_context_threshold: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub web_search: Option<String>,
+    /// 按顺序匹配的路由规则，先于由上面各字段生成的默认规则
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RouteRule>,
+}
+
+/// 声明式路由规则：所有已设置的条件都满足时路由到 `target`，未设置的条件不参与匹配
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteRule {
+    /// 规则名称，仅用于日志
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 请求模型名的通配符（`*`、`?`），如 `claude-3-5-haiku*`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<GlobPattern>,
+    /// 估算的 token 数大于该值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_above: Option<usize>,
+    /// 估算的 token 数小于该值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_below: Option<usize>,
+    /// 同一会话上一次请求的输入 token 数大于该值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_input_tokens_above: Option<usize>,
+    /// 是否带有工具定义
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<bool>,
+    /// 任一工具的类型匹配该通配符，如 `web_search*`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_type: Option<GlobPattern>,
+    /// 消息中是否带有图片
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub images: Option<bool>,
+    /// 是否启用了思考模式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<bool>,
+    /// 任一 system 块匹配该正则；`target` 中可用 `$1` / `${name}` 引用捕获组
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<RegexPattern>,
+    /// 只用第几个 system 块（从 0 开始）匹配 `system`；未设置时任一块均可
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_block: Option<usize>,
+    /// 请求头名称（不区分大小写）到取值通配符，全部匹配才算满足
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, GlobPattern>,
+    /// `metadata.user_id` 的通配符
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<GlobPattern>,
+    /// 会话 id 的通配符
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<GlobPattern>,
+    /// 目标 "provider,model"
+    pub target: String,
+}
+
+/// 通配符：`*` 匹配任意字符串，`?` 匹配单个字符，其余字符按字面匹配。
+/// 加载配置时编译一次，按原字符串序列化与比较
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct GlobPattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl GlobPattern {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let regex = regex::escape(&pattern).replace(r"\*", ".*").replace(r"\?", ".");
+        // 转义后只剩字面字符与 `.*`、`.`，总能编译
+        let regex = Regex::new(&format!("^{}$", regex)).expect("escaped glob is a valid regex");
+        Self { pattern, regex }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl From<String> for GlobPattern {
+    fn from(pattern: String) -> Self {
+        Self::new(pattern)
+    }
+}
+
+impl From<GlobPattern> for String {
+    fn from(pattern: GlobPattern) -> Self {
+        pattern.pattern
+    }
 }
 
+impl PartialEq for GlobPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Eq for GlobPattern {}
+
+/// 正则表达式；加载配置时编译，无效的正则使配置加载失败
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RegexPattern(Regex);
+
+impl RegexPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn regex(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for RegexPattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Self::new(&pattern)
+    }
+}
+
+impl From<RegexPattern> for String {
+    fn from(pattern: RegexPattern) -> Self {
+        pattern.0.as_str().to_string()
+    }
+}
+
+impl PartialEq for RegexPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for RegexPattern {}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformerConfig {
     pub path: String,
@@ -101,6 +266,8 @@ impl Default for Config {
             non_interactive_mode: Some(false),
             api_timeout_ms: Some(600000),
             custom_router_path: None,
+            response_model: None,
+            project_roots: vec![],
             providers: vec![],
             router: RouterConfig {
                 default: "openrouter,anthropic/claude-sonnet-4".to_string(),
@@ -109,6 +276,7 @@ impl Default for Config {
                 long_context: None,
                 long_context_threshold: Some(60000),
                 web_search: None,
+                rules: vec![],
             },
             transformers: None,
             extra: HashMap::new(),
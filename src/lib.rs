@@ -1,4 +1,5 @@
 pub mod config;
+pub mod plugins;
 pub mod router;
 pub mod server;
 pub mod transformers;
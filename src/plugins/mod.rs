@@ -0,0 +1,4 @@
+pub mod wasi;
+pub mod wasm;
+
+pub use wasm::{load_plugins_dir, LoadedPlugins, WasmLimits, WasmPlugin, WasmRouter, WasmTransformer};
@@ -0,0 +1,145 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+use wasmi::{Caller, Error, Extern, ExternType, Linker, Memory, Module, Val, ValType};
+
+use crate::plugins::wasm::{check_guest_range, HostState};
+
+/// Import module name of WASI preview 1.
+pub const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+const ERRNO_SUCCESS: i32 = 0;
+const ERRNO_BADF: i32 = 8;
+const ERRNO_FAULT: i32 = 21;
+const ERRNO_INVAL: i32 = 28;
+/// Returned by every filesystem, socket and process call: plugins get no capabilities.
+const ERRNO_NOTCAPABLE: i32 = 76;
+
+/// WASI calls that have a real (if minimal) implementation. Everything else a module imports
+/// from `wasi_snapshot_preview1` is linked to a stub returning `ENOTCAPABLE`.
+const IMPLEMENTED: &[&str] = &[
+    "fd_write",
+    "proc_exit",
+    "args_sizes_get",
+    "args_get",
+    "environ_sizes_get",
+    "environ_get",
+    "clock_time_get",
+    "random_get",
+    "sched_yield",
+];
+
+/// Links a capability-free WASI preview 1 host: no arguments or environment, stdout and
+/// stderr are discarded, clocks and randomness work, and all filesystem and network access
+/// is refused.
+pub fn link_wasi(linker: &mut Linker<HostState>, module: &Module) -> Result<(), Error> {
+    linker.func_wrap(WASI_MODULE, "fd_write", fd_write)?;
+    linker.func_wrap(WASI_MODULE, "proc_exit", |_: Caller<'_, HostState>, code: i32| -> Result<(), Error> {
+        Err(Error::i32_exit(code))
+    })?;
+    linker.func_wrap(WASI_MODULE, "args_sizes_get", write_zero_sizes)?;
+    linker.func_wrap(WASI_MODULE, "args_get", |_: Caller<'_, HostState>, _: i32, _: i32| ERRNO_SUCCESS)?;
+    linker.func_wrap(WASI_MODULE, "environ_sizes_get", write_zero_sizes)?;
+    linker.func_wrap(WASI_MODULE, "environ_get", |_: Caller<'_, HostState>, _: i32, _: i32| ERRNO_SUCCESS)?;
+    linker.func_wrap(WASI_MODULE, "clock_time_get", clock_time_get)?;
+    linker.func_wrap(WASI_MODULE, "random_get", random_get)?;
+    linker.func_wrap(WASI_MODULE, "sched_yield", |_: Caller<'_, HostState>| ERRNO_SUCCESS)?;
+
+    for import in module.imports() {
+        if import.module() != WASI_MODULE || IMPLEMENTED.contains(&import.name()) {
+            continue;
+        }
+        let ExternType::Func(ty) = import.ty() else {
+            continue;
+        };
+        let returns_errno = ty.results() == [ValType::I32];
+        let name = import.name().to_string();
+        linker.func_new(WASI_MODULE, import.name(), ty.clone(), move |_, _, results| {
+            if returns_errno {
+                results[0] = Val::I32(ERRNO_NOTCAPABLE);
+                Ok(())
+            } else {
+                Err(Error::new(format!("WASI call {} is not available to plugins", name)))
+            }
+        })?;
+    }
+    Ok(())
+}
+
+fn memory(caller: &Caller<'_, HostState>) -> Option<Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory)
+}
+
+fn read_u32(caller: &Caller<'_, HostState>, memory: &Memory, offset: usize) -> Option<u32> {
+    let mut bytes = [0u8; 4];
+    memory.read(caller, offset, &mut bytes).ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+fn write_bytes(caller: &mut Caller<'_, HostState>, offset: i32, bytes: &[u8]) -> i32 {
+    match memory(caller) {
+        Some(memory) if memory.write(caller, offset as u32 as usize, bytes).is_ok() => ERRNO_SUCCESS,
+        _ => ERRNO_FAULT,
+    }
+}
+
+/// Accepts writes to stdout and stderr and drops them.
+fn fd_write(mut caller: Caller<'_, HostState>, fd: i32, iovs: i32, iovs_len: i32, nwritten: i32) -> i32 {
+    if fd != 1 && fd != 2 {
+        return ERRNO_BADF;
+    }
+    if iovs_len < 0 {
+        return ERRNO_INVAL;
+    }
+    let Some(memory) = memory(&caller) else {
+        return ERRNO_FAULT;
+    };
+
+    // Each iovec is `buf: u32, buf_len: u32`; only the lengths are read
+    let mut total: u32 = 0;
+    for index in 0..iovs_len as usize {
+        let Some(offset) = (iovs as u32 as usize).checked_add(index * 8 + 4) else {
+            return ERRNO_FAULT;
+        };
+        let Some(len) = read_u32(&caller, &memory, offset) else {
+            return ERRNO_FAULT;
+        };
+        total = total.saturating_add(len);
+    }
+    write_bytes(&mut caller, nwritten, &total.to_le_bytes())
+}
+
+fn write_zero_sizes(mut caller: Caller<'_, HostState>, count: i32, size: i32) -> i32 {
+    match write_bytes(&mut caller, count, &0u32.to_le_bytes()) {
+        ERRNO_SUCCESS => write_bytes(&mut caller, size, &0u32.to_le_bytes()),
+        errno => errno,
+    }
+}
+
+fn clock_time_get(mut caller: Caller<'_, HostState>, _clock_id: i32, _precision: i64, time: i32) -> i32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0);
+    write_bytes(&mut caller, time, &nanos.to_le_bytes())
+}
+
+fn random_get(mut caller: Caller<'_, HostState>, buf: i32, len: i32) -> i32 {
+    let len = len as u32 as usize;
+    match memory(&caller) {
+        Some(memory) if check_guest_range(&caller, &memory, buf as u32 as usize, len).is_ok() => {}
+        _ => return ERRNO_FAULT,
+    }
+
+    let state = RandomState::new();
+    let mut bytes = Vec::with_capacity(len);
+    let mut counter: u64 = 0;
+    while bytes.len() < len {
+        let mut hasher = state.build_hasher();
+        hasher.write_u64(counter);
+        bytes.extend_from_slice(&hasher.finish().to_le_bytes());
+        counter += 1;
+    }
+    bytes.truncate(len);
+    write_bytes(&mut caller, buf, &bytes)
+}
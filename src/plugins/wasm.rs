@@ -0,0 +1,337 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wasmi::{AsContext, Config as EngineConfig, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::config::types::Config;
+use crate::plugins::wasi::link_wasi;
+use crate::router::custom_router::CustomRouter;
+use crate::router::route_logic::{RouteRequest, Usage};
+use crate::transformers::chain::{HookFactory, TransformerHook};
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::provider_trait::*;
+
+const ALLOC_FN: &str = "alloc";
+const REQUEST_FN: &str = "transform_request";
+const RESPONSE_FN: &str = "transform_response";
+const STREAM_CHUNK_FN: &str = "transform_stream_chunk";
+const ROUTE_FN: &str = "route";
+
+/// Per-call resource limits. Every call runs in a fresh instance with a full fuel tank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmLimits {
+    /// Instructions a single call may execute (roughly one unit of fuel per instruction)
+    pub fuel: u64,
+    pub max_memory_bytes: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 50_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Store data for one plugin call.
+pub struct HostState {
+    limits: StoreLimits,
+    max_memory_bytes: usize,
+}
+
+/// Checks a guest buffer before the host allocates `len` bytes for it: the range must lie
+/// inside `memory`, and `len` may not exceed the store's memory limit.
+pub(crate) fn check_guest_range(ctx: impl AsContext<Data = HostState>, memory: &Memory, ptr: usize, len: usize) -> Result<(), wasmi::Error> {
+    let ctx = ctx.as_context();
+    if len > ctx.data().max_memory_bytes {
+        return Err(wasmi::Error::new(format!("buffer of {} bytes exceeds the memory limit", len)));
+    }
+    match ptr.checked_add(len) {
+        Some(end) if end <= memory.data_size(ctx) => Ok(()),
+        _ => Err(wasmi::Error::new(format!("buffer of {} bytes at {} is outside memory", len, ptr))),
+    }
+}
+
+/// A compiled WebAssembly plugin.
+///
+/// The ABI: the module exports `memory` and `alloc(len: i32) -> i32`, plus any of
+/// `transform_request`, `transform_response`, `transform_stream_chunk` and `route`, each
+/// `(ptr: i32, len: i32) -> i64`. The host writes UTF-8 JSON input into a buffer from
+/// `alloc`; the export returns `(out_ptr << 32) | out_len` pointing at its JSON output, or
+/// `0` for "no change" / "no decision".
+///
+/// - Transforms receive `{"body": <universal body>, "options": <options>}` and return the body.
+/// - `route` receives `{"request": ..., "token_count": n, "last_usage": {...} | null,
+///   "router": <Router config>}` and returns a `"provider,model"` string or `null`.
+///
+/// Modules may import WASI preview 1; see [`crate::plugins::wasi`] for what is available.
+pub struct WasmPlugin {
+    name: String,
+    path: PathBuf,
+    engine: Engine,
+    module: Module,
+    linker: Linker<HostState>,
+    limits: WasmLimits,
+}
+
+impl WasmPlugin {
+    pub fn load(path: impl AsRef<Path>, limits: WasmLimits) -> TransformerResult<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| {
+            TransformerError::Configuration(format!("Failed to read plugin {}: {}", path.display(), e))
+        })?;
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("plugin")
+            .to_string();
+        Self::from_bytes(name, path.to_path_buf(), &bytes, limits)
+    }
+
+    pub fn from_bytes(name: impl Into<String>, path: PathBuf, bytes: &[u8], limits: WasmLimits) -> TransformerResult<Self> {
+        let config_error = |e: &dyn std::fmt::Display| {
+            TransformerError::Configuration(format!("Invalid plugin {}: {}", path.display(), e))
+        };
+
+        let mut engine_config = EngineConfig::default();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config);
+        let module = Module::new(&engine, bytes).map_err(|e| config_error(&e))?;
+
+        let mut linker = Linker::new(&engine);
+        link_wasi(&mut linker, &module).map_err(|e| config_error(&e))?;
+
+        let plugin = Self {
+            name: name.into(),
+            path: path.clone(),
+            engine,
+            module,
+            linker,
+            limits,
+        };
+
+        for required in ["memory", ALLOC_FN] {
+            if !plugin.exports(required) {
+                return Err(config_error(&format!("missing required export '{}'", required)));
+            }
+        }
+        if !plugin.is_transformer() && !plugin.is_router() {
+            return Err(config_error(&format!(
+                "exports none of {}, {}, {} or {}",
+                REQUEST_FN, RESPONSE_FN, STREAM_CHUNK_FN, ROUTE_FN
+            )));
+        }
+        Ok(plugin)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn exports(&self, name: &str) -> bool {
+        self.module.exports().any(|export| export.name() == name)
+    }
+
+    pub fn is_transformer(&self) -> bool {
+        [REQUEST_FN, RESPONSE_FN, STREAM_CHUNK_FN].iter().any(|name| self.exports(name))
+    }
+
+    pub fn is_router(&self) -> bool {
+        self.exports(ROUTE_FN)
+    }
+
+    fn error(&self, function: &str, error: impl std::fmt::Display) -> TransformerError {
+        TransformerError::Plugin(format!("{} ({}): {}: {}", self.name, self.path.display(), function, error))
+    }
+
+    /// Calls `function` with `input` as JSON in a fresh instance. Returns `None` when the
+    /// plugin does not export it or returned `0`.
+    pub fn call_json<T: DeserializeOwned>(&self, function: &str, input: &Value) -> TransformerResult<Option<T>> {
+        if !self.exports(function) {
+            return Ok(None);
+        }
+
+        let mut store = Store::new(
+            &self.engine,
+            HostState {
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(self.limits.max_memory_bytes)
+                    .instances(1)
+                    .build(),
+                max_memory_bytes: self.limits.max_memory_bytes,
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.limits.fuel).map_err(|e| self.error(function, e))?;
+
+        let instance = self
+            .linker
+            .instantiate_and_start(&mut store, &self.module)
+            .map_err(|e| self.error(function, e))?;
+        let output = self.invoke(&mut store, instance, function, input).map_err(|e| self.error(function, e))?;
+
+        match output {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| self.error(function, format!("invalid JSON returned: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    fn invoke(&self, store: &mut Store<HostState>, instance: Instance, function: &str, input: &Value) -> Result<Option<Vec<u8>>, wasmi::Error> {
+        let input = serde_json::to_vec(input).map_err(|e| wasmi::Error::new(e.to_string()))?;
+        let input_len = i32::try_from(input.len()).map_err(|_| wasmi::Error::new("input too large"))?;
+
+        let memory = instance
+            .get_memory(&*store, "memory")
+            .ok_or_else(|| wasmi::Error::new("missing memory export"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&*store, ALLOC_FN)?;
+        let entry = instance.get_typed_func::<(i32, i32), i64>(&*store, function)?;
+
+        let input_ptr = alloc.call(&mut *store, input_len)?;
+        memory
+            .write(&mut *store, input_ptr as u32 as usize, &input)
+            .map_err(|e| wasmi::Error::new(e.to_string()))?;
+
+        let packed = entry.call(&mut *store, (input_ptr, input_len))? as u64;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let (output_ptr, output_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        check_guest_range(&*store, &memory, output_ptr, output_len)?;
+        let mut output = vec![0u8; output_len];
+        memory
+            .read(&*store, output_ptr, &mut output)
+            .map_err(|e| wasmi::Error::new(e.to_string()))?;
+        Ok(Some(output))
+    }
+}
+
+/// A plugin used as a hook in a provider's `use` chain.
+pub struct WasmTransformer {
+    plugin: Arc<WasmPlugin>,
+    options: Value,
+}
+
+impl WasmTransformer {
+    pub fn new(plugin: Arc<WasmPlugin>, options: Option<&Value>) -> Self {
+        Self {
+            plugin,
+            options: options.cloned().unwrap_or_else(|| json!({})),
+        }
+    }
+
+    pub fn factory(plugin: Arc<WasmPlugin>) -> HookFactory {
+        Box::new(move |options| Ok(Box::new(Self::new(plugin.clone(), options))))
+    }
+
+    fn call<T: Serialize + DeserializeOwned>(&self, function: &str, body: &mut T) -> TransformerResult<()> {
+        let input = json!({
+            "body": serde_json::to_value(&*body).map_err(|e| TransformerError::Serialization(e.to_string()))?,
+            "options": self.options,
+        });
+        if let Some(output) = self.plugin.call_json(function, &input)? {
+            *body = output;
+        }
+        Ok(())
+    }
+}
+
+impl TransformerHook for WasmTransformer {
+    fn name(&self) -> &str {
+        self.plugin.name()
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        self.call(REQUEST_FN, request)
+    }
+
+    fn transform_response(&self, response: &mut ChatResponse) -> TransformerResult<()> {
+        self.call(RESPONSE_FN, response)
+    }
+
+    fn transform_stream_chunk(&self, chunk: &mut ChatStreamChunk) -> TransformerResult<()> {
+        self.call(STREAM_CHUNK_FN, chunk)
+    }
+}
+
+/// A plugin consulted by the router before the built-in checks.
+pub struct WasmRouter {
+    plugin: Arc<WasmPlugin>,
+}
+
+impl WasmRouter {
+    pub fn new(plugin: Arc<WasmPlugin>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl CustomRouter for WasmRouter {
+    fn name(&self) -> &str {
+        self.plugin.name()
+    }
+
+    fn route(
+        &self,
+        req: &RouteRequest,
+        token_count: usize,
+        last_usage: Option<&Usage>,
+        config: &Config,
+    ) -> anyhow::Result<Option<String>> {
+        let input = json!({
+            "request": req,
+            "token_count": token_count,
+            "last_usage": last_usage,
+            "router": config.router,
+        });
+        let decision: Option<Option<String>> = self.plugin.call_json(ROUTE_FN, &input)?;
+        Ok(decision.flatten().filter(|route| !route.is_empty()))
+    }
+}
+
+/// Plugins found in a directory, split by role. A module exporting both transform and
+/// `route` functions appears in both lists.
+#[derive(Default)]
+pub struct LoadedPlugins {
+    pub transformers: Vec<Arc<WasmPlugin>>,
+    pub routers: Vec<Arc<WasmPlugin>>,
+    pub errors: Vec<TransformerError>,
+}
+
+/// Loads every `.wasm` file in `dir`, in file-name order. Modules that fail to load are
+/// reported in `errors` and skipped. A missing directory yields no plugins.
+pub fn load_plugins_dir(dir: &Path, limits: WasmLimits) -> LoadedPlugins {
+    let mut loaded = LoadedPlugins::default();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return loaded;
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wasm"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        match WasmPlugin::load(&path, limits) {
+            Ok(plugin) => {
+                let plugin = Arc::new(plugin);
+                if plugin.is_transformer() {
+                    loaded.transformers.push(plugin.clone());
+                }
+                if plugin.is_router() {
+                    loaded.routers.push(plugin);
+                }
+            }
+            Err(e) => loaded.errors.push(e),
+        }
+    }
+    loaded
+}
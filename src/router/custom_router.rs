@@ -0,0 +1,16 @@
+use crate::config::types::Config;
+use crate::router::route_logic::{RouteRequest, Usage};
+
+/// 自定义路由器（WASM 插件等），在内置检查之前决定目标 "provider,model"
+pub trait CustomRouter: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// 返回 `Ok(None)` 表示不做决定，交给后续的路由逻辑；`last_usage` 为同一会话上一次的用量
+    fn route(
+        &self,
+        req: &RouteRequest,
+        token_count: usize,
+        last_usage: Option<&Usage>,
+        config: &Config,
+    ) -> anyhow::Result<Option<String>>;
+}
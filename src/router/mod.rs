@@ -1,3 +1,5 @@
 pub mod route_handler;
 pub mod route_logic;
-pub mod plugin;
\ No newline at end of file
+pub mod rules;
+pub mod custom_router;
+pub mod script_router;
\ No newline at end of file
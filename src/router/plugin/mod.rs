@@ -1,11 +0,0 @@
-pub mod long_context_check;
-pub mod subagent_model_check;
-pub mod background_model_check;
-pub mod think_model_check;
-pub mod web_search_check;
-
-pub use long_context_check::check_long_context;
-pub use subagent_model_check::check_subagent_model;
-pub use background_model_check::check_background_model;
-pub use think_model_check::check_think_model;
-pub use web_search_check::check_web_search;
\ No newline at end of file
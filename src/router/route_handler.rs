@@ -1,6 +1,8 @@
 use crate::config::types::Config;
-use crate::router::route_logic::{RouteLogic, RouteRequest, Usage};
+use crate::router::custom_router::CustomRouter;
+use crate::router::route_logic::{RouteDecision, RouteLogic, RouteRequest, Usage};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct RouteHandler;
 
@@ -10,6 +12,26 @@ impl RouteHandler {
         config: &Config,
         session_usage_cache: &HashMap<String, Usage>,
     ) -> String {
+        Self::handle_route_with_routers(req, config, session_usage_cache, &[])
+    }
+
+    /// 带自定义路由器（如 WASM 插件）的路由
+    pub fn handle_route_with_routers(
+        req: &mut RouteRequest,
+        config: &Config,
+        session_usage_cache: &HashMap<String, Usage>,
+        routers: &[Arc<dyn CustomRouter>],
+    ) -> String {
+        Self::handle_route_decision(req, config, session_usage_cache, routers).target
+    }
+
+    /// 与 `handle_route_with_routers` 相同，但返回完整的决定记录
+    pub fn handle_route_decision(
+        req: &mut RouteRequest,
+        config: &Config,
+        session_usage_cache: &HashMap<String, Usage>,
+        routers: &[Arc<dyn CustomRouter>],
+    ) -> RouteDecision {
         // 解析sessionId从metadata.user_id
         let session_id = req
             .body
@@ -24,8 +46,12 @@ impl RouteHandler {
                 }
             });
 
-        // 获取上一次的使用情况
-        let last_usage = session_id
+        // 记录会话 id 供路由规则匹配，并获取上一次的使用情况
+        if req.session_id.is_none() {
+            req.session_id = session_id;
+        }
+        let last_usage = req
+            .session_id
             .as_ref()
             .and_then(|id| session_usage_cache.get(id));
 
@@ -33,7 +59,7 @@ impl RouteHandler {
         let token_count = Self::calculate_token_count(req);
 
         // 使用路由逻辑获取应该使用的模型
-        RouteLogic::get_use_model(req, token_count, config, last_usage)
+        RouteLogic::decide(req, token_count, config, last_usage, routers)
     }
 
     fn calculate_token_count(req: &RouteRequest) -> usize {
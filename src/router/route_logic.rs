@@ -1,8 +1,10 @@
+use crate::config::capabilities::{CapabilityNeeds, CapabilityRegistry};
 use crate::config::types::Config;
-use crate::router::plugin::{
-    check_long_context, check_subagent_model, check_background_model,
-    check_think_model, check_web_search
-};
+use crate::router::custom_router::CustomRouter;
+use crate::router::rules::{default_rules, long_context_threshold};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 pub struct RouteLogic;
 
@@ -13,58 +15,257 @@ impl RouteLogic {
         config: &Config,
         last_usage: Option<&Usage>,
     ) -> String {
-        // 定义路由检查函数列表
-        let checkers: Vec<fn(&RouteRequest, usize, &Config, Option<&Usage>) -> Option<String>> = vec![
-            check_long_context,
-            check_subagent_model,
-            check_background_model,
-            check_think_model,
-            check_web_search,
+        Self::get_use_model_with_routers(req, token_count, config, last_usage, &[])
+    }
+
+    /// 与 `get_use_model` 相同，但先依次询问自定义路由器；出错的路由器会被跳过
+    pub fn get_use_model_with_routers(
+        req: &RouteRequest,
+        token_count: usize,
+        config: &Config,
+        last_usage: Option<&Usage>,
+        routers: &[Arc<dyn CustomRouter>],
+    ) -> String {
+        Self::decide(req, token_count, config, last_usage, routers).target
+    }
+
+    /// 路由并返回决定记录：选中的目标、命中的自定义路由器或规则，以及当时的 token 数与阈值
+    pub fn decide(
+        req: &RouteRequest,
+        token_count: usize,
+        config: &Config,
+        last_usage: Option<&Usage>,
+        routers: &[Arc<dyn CustomRouter>],
+    ) -> RouteDecision {
+        let mut decision = RouteDecision {
+            target: config.router.default.clone(),
+            source: RouteSource::Default,
+            matched: None,
+            token_count,
+            last_input_tokens: last_usage.map(|usage| usage.input_tokens),
+            thresholds: RouteThresholds {
+                long_context: long_context_threshold(&config.router),
+                ..RouteThresholds::default()
+            },
+            skipped: Vec::new(),
+        };
+
+        // 请求中已显式指定 "provider,model" 时直接使用
+        if let Some(model) = &req.body.model
+            && model.contains(',')
+        {
+            decision.target = model.clone();
+            decision.source = RouteSource::Explicit;
+            return decision;
+        }
+
+        // 自定义路由器的决定优先于内置检查
+        for router in routers {
+            match router.route(req, token_count, last_usage, config) {
+                Ok(Some(model)) => {
+                    decision.target = model;
+                    decision.source = RouteSource::CustomRouter;
+                    decision.matched = Some(router.name().to_string());
+                    return decision;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Custom router {} failed: {}", router.name(), e),
+            }
+        }
+
+        let registry = CapabilityRegistry::from_config(config);
+        let needs = Self::capability_needs(req, token_count);
+
+        // 先匹配配置的规则，再匹配由 Router 字段生成的默认规则，跳过无法满足请求能力要求的目标
+        let defaults = default_rules(&config.router);
+        for rule in config.router.rules.iter().chain(&defaults) {
+            let Some(model) = rule.evaluate(req, token_count, last_usage) else {
+                continue;
+            };
+            if !registry.lookup_route(&model).can_serve(&needs) {
+                decision.skipped.push(SkippedRule { rule: rule.label().to_string(), target: model });
+                continue;
+            }
+            decision.target = model;
+            decision.source = RouteSource::Rule;
+            decision.matched = Some(rule.label().to_string());
+            decision.thresholds.tokens_above = rule.tokens_above;
+            decision.thresholds.tokens_below = rule.tokens_below;
+            decision.thresholds.last_input_tokens_above = rule.last_input_tokens_above;
+            return decision;
+        }
+
+        // 如果所有规则都不匹配，返回默认模型
+        decision
+    }
+
+    fn capability_needs(req: &RouteRequest, token_count: usize) -> CapabilityNeeds {
+        CapabilityNeeds {
+            input_tokens: token_count,
+            tools: req.body.tools.as_ref().is_some_and(|tools| !tools.is_empty()),
+            vision: req.body.has_images,
+        }
+    }
+}
+
+/// 响应头：本次路由的来源与 token 数，见 `RouteDecision::reason`
+pub const ROUTE_REASON_HEADER: &str = "x-ccr-route-reason";
+
+/// 一次路由的决定记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RouteDecision {
+    /// 选中的 "provider,model"
+    pub target: String,
+    pub source: RouteSource,
+    /// 命中的自定义路由器或规则名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matched: Option<String>,
+    pub token_count: usize,
+    /// 同一会话上一次请求的输入 token 数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_input_tokens: Option<usize>,
+    pub thresholds: RouteThresholds,
+    /// 条件满足、但目标无法满足请求能力要求而被跳过的规则
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteSource {
+    /// 请求模型名已是 "provider,model"
+    Explicit,
+    CustomRouter,
+    Rule,
+    /// 没有命中任何路由器或规则，使用 `Router.default`
+    Default,
+}
+
+/// 做决定时生效的阈值：长上下文阈值，以及命中规则的 token 条件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RouteThresholds {
+    pub long_context: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens_above: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens_below: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_input_tokens_above: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedRule {
+    pub rule: String,
+    pub target: String,
+}
+
+impl RouteDecision {
+    /// `x-ccr-route-reason` 的取值，如 `rule:long_context; tokens=70000; tokens_above=60000`
+    pub fn reason(&self) -> String {
+        let mut reason = match (self.source, &self.matched) {
+            (RouteSource::Explicit, _) => "explicit".to_string(),
+            (RouteSource::CustomRouter, Some(name)) => format!("custom_router:{}", name),
+            (RouteSource::Rule, Some(name)) => format!("rule:{}", name),
+            _ => "default".to_string(),
+        };
+        reason.push_str(&format!("; tokens={}", self.token_count));
+        let thresholds = [
+            ("tokens_above", self.thresholds.tokens_above),
+            ("tokens_below", self.thresholds.tokens_below),
+            ("last_input_tokens_above", self.thresholds.last_input_tokens_above),
         ];
-        
-        // 按顺序执行检查函数
-        for checker in checkers {
-            if let Some(model) = checker(req, token_count, config, last_usage) {
-                return model;
+        for (name, value) in thresholds {
+            if let Some(value) = value {
+                reason.push_str(&format!("; {}={}", name, value));
             }
         }
-        
-        // 如果所有检查都失败，返回默认模型
-        config.router.default.clone()
+        reason
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RouteRequest {
     pub body: RequestBody,
     pub session_id: Option<String>,
+    /// 请求头（名称小写，不含认证头），供路由规则与自定义路由器匹配
+    pub headers: HashMap<String, String>,
+}
+
+impl RouteRequest {
+    /// 从 Anthropic Messages 请求体构造路由请求
+    pub fn from_anthropic_body(body: &serde_json::Value, headers: HashMap<String, String>) -> Self {
+        let text = |value: &serde_json::Value| value.get("text").and_then(|text| text.as_str()).map(str::to_string);
+        let system = match body.get("system") {
+            Some(serde_json::Value::String(text)) => Some(vec![SystemMessage { text: Some(text.clone()) }]),
+            Some(serde_json::Value::Array(blocks)) => {
+                Some(blocks.iter().map(|block| SystemMessage { text: text(block) }).collect())
+            }
+            _ => None,
+        };
+        let thinking = body
+            .get("thinking")
+            .map(|thinking| thinking.get("type").and_then(|t| t.as_str()) != Some("disabled"));
+        let tools = body.get("tools").and_then(|tools| tools.as_array()).map(|tools| {
+            tools
+                .iter()
+                .map(|tool| Tool { tool_type: tool.get("type").and_then(|t| t.as_str()).map(str::to_string) })
+                .collect()
+        });
+        let metadata = body.get("metadata").map(|metadata| Metadata {
+            user_id: metadata.get("user_id").and_then(|id| id.as_str()).map(str::to_string),
+        });
+        let has_images = body
+            .get("messages")
+            .and_then(|messages| messages.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|message| message.get("content").and_then(|content| content.as_array()))
+            .flatten()
+            .any(|block| block.get("type").and_then(|t| t.as_str()) == Some("image"));
+
+        Self {
+            body: RequestBody {
+                model: body.get("model").and_then(|model| model.as_str()).map(str::to_string),
+                system,
+                thinking,
+                tools,
+                metadata,
+                has_images,
+            },
+            session_id: None,
+            headers,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RequestBody {
     pub model: Option<String>,
     pub system: Option<Vec<SystemMessage>>,
     pub thinking: Option<bool>,
     pub tools: Option<Vec<Tool>>,
     pub metadata: Option<Metadata>,
+    /// 消息中是否带有图片
+    pub has_images: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SystemMessage {
     pub text: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Tool {
+    #[serde(rename = "type")]
     pub tool_type: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Metadata {
     pub user_id: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Usage {
     pub input_tokens: usize,
 }
\ No newline at end of file
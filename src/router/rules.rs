@@ -0,0 +1,153 @@
+use crate::config::types::{GlobPattern, RegexPattern, RouteRule, RouterConfig};
+use crate::router::route_logic::{RouteRequest, Usage};
+
+/// 子代理在第二个 system 块（紧随客户端自身的系统提示）开头用该标记指定目标模型
+const SUBAGENT_MODEL_PATTERN: &str = r"^<CCR-SUBAGENT-MODEL>(.+?)</CCR-SUBAGENT-MODEL>";
+/// 未配置 `long_context_threshold` 时的长上下文阈值
+const DEFAULT_LONG_CONTEXT_THRESHOLD: u32 = 60000;
+/// 按上一次用量切换长上下文模型时，本次请求至少需要的 token 数
+const LONG_CONTEXT_MIN_TOKENS: usize = 20000;
+
+/// 由 `Router` 的 `long_context`、`background`、`think`、`web_search` 字段生成的默认规则，
+/// 顺序与原先的内置检查一致
+pub fn default_rules(router: &RouterConfig) -> Vec<RouteRule> {
+    let mut rules = Vec::new();
+    if let Some(target) = &router.long_context {
+        let threshold = long_context_threshold(router);
+        rules.push(RouteRule {
+            name: Some("long_context".to_string()),
+            tokens_above: Some(threshold),
+            target: target.clone(),
+            ..RouteRule::default()
+        });
+        rules.push(RouteRule {
+            name: Some("long_context".to_string()),
+            tokens_above: Some(LONG_CONTEXT_MIN_TOKENS),
+            last_input_tokens_above: Some(threshold),
+            target: target.clone(),
+            ..RouteRule::default()
+        });
+    }
+    rules.push(RouteRule {
+        name: Some("subagent".to_string()),
+        system: Some(RegexPattern::new(SUBAGENT_MODEL_PATTERN).expect("subagent pattern is a valid regex")),
+        system_block: Some(1),
+        target: "$1".to_string(),
+        ..RouteRule::default()
+    });
+    if let Some(target) = &router.background {
+        rules.push(RouteRule {
+            name: Some("background".to_string()),
+            model: Some(GlobPattern::new("claude-3-5-haiku*")),
+            target: target.clone(),
+            ..RouteRule::default()
+        });
+    }
+    if let Some(target) = &router.think {
+        rules.push(RouteRule {
+            name: Some("think".to_string()),
+            thinking: Some(true),
+            target: target.clone(),
+            ..RouteRule::default()
+        });
+    }
+    if let Some(target) = &router.web_search {
+        rules.push(RouteRule {
+            name: Some("web_search".to_string()),
+            tool_type: Some(GlobPattern::new("web_search*")),
+            target: target.clone(),
+            ..RouteRule::default()
+        });
+    }
+    rules
+}
+
+/// 生效的长上下文阈值
+pub fn long_context_threshold(router: &RouterConfig) -> usize {
+    router.long_context_threshold.unwrap_or(DEFAULT_LONG_CONTEXT_THRESHOLD) as usize
+}
+
+impl RouteRule {
+    /// 所有已设置的条件都满足时返回目标（已展开 system 正则的捕获组），否则返回 `None`
+    pub fn evaluate(&self, req: &RouteRequest, token_count: usize, last_usage: Option<&Usage>) -> Option<String> {
+        let body = &req.body;
+        if let Some(pattern) = &self.model
+            && !pattern.is_match(body.model.as_deref().unwrap_or_default())
+        {
+            return None;
+        }
+        if self.tokens_above.is_some_and(|tokens| token_count <= tokens)
+            || self.tokens_below.is_some_and(|tokens| token_count >= tokens)
+        {
+            return None;
+        }
+        if let Some(tokens) = self.last_input_tokens_above
+            && last_usage.is_none_or(|usage| usage.input_tokens <= tokens)
+        {
+            return None;
+        }
+        let tools = body.tools.as_deref().unwrap_or_default();
+        let has_tools = !tools.is_empty();
+        if self.tools.is_some_and(|expected| expected != has_tools) {
+            return None;
+        }
+        if let Some(pattern) = &self.tool_type
+            && !tools
+                .iter()
+                .any(|tool| tool.tool_type.as_deref().is_some_and(|tool_type| pattern.is_match(tool_type)))
+        {
+            return None;
+        }
+        if self.images.is_some_and(|expected| expected != body.has_images)
+            || self.thinking.is_some_and(|expected| expected != body.thinking.unwrap_or(false))
+        {
+            return None;
+        }
+        for (name, pattern) in &self.headers {
+            let value = req
+                .headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str());
+            if !value.is_some_and(|value| pattern.is_match(value)) {
+                return None;
+            }
+        }
+        let user_id = body.metadata.as_ref().and_then(|metadata| metadata.user_id.as_deref());
+        if let Some(pattern) = &self.user_id
+            && !user_id.is_some_and(|user_id| pattern.is_match(user_id))
+        {
+            return None;
+        }
+        if let Some(pattern) = &self.session_id
+            && !req.session_id.as_deref().is_some_and(|session_id| pattern.is_match(session_id))
+        {
+            return None;
+        }
+
+        let Some(pattern) = &self.system else {
+            return Some(self.target.clone());
+        };
+        let captures = body
+            .system
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(index, _)| self.system_block.is_none_or(|block| block == *index))
+            .filter_map(|(_, message)| message.text.as_deref())
+            .find_map(|text| pattern.regex().captures(text))?;
+        let mut target = String::new();
+        captures.expand(&self.target, &mut target);
+        (!target.is_empty()).then_some(target)
+    }
+
+    /// 日志中使用的规则名称
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.target)
+    }
+}
+
+/// 通配符匹配，见 `GlobPattern`；规则中的通配符在加载配置时已编译，这里用于一次性匹配
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    GlobPattern::new(pattern).is_match(text)
+}
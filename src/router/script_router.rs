@@ -0,0 +1,194 @@
+use crate::config::constants::get_config_dir;
+use crate::config::types::{Config, RouterConfig};
+use crate::router::custom_router::CustomRouter;
+use crate::router::route_logic::{RouteRequest, Usage};
+use crate::transformers::script::{ScriptLimits, ScriptModule};
+use anyhow::{anyhow, Context};
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+const ROUTE_FN: &str = "route";
+
+/// `CUSTOM_ROUTER_PATH` 指向的 Rhai 路由脚本
+///
+/// 脚本定义 `route(request, token_count, last_usage, config)`，返回 "provider,model"；
+/// 返回空值或空字符串表示不做决定，交给内置路由逻辑。脚本在启动时加载，文件修改后在下一次
+/// 路由时重新加载。加载或运行出错只记录日志、不影响请求，重新加载失败时继续使用上一个可用版本。
+///
+/// `config` 只包含 `Router` 与各提供商的名称、模型，不含 API 密钥等其他配置。
+pub struct ScriptRouter {
+    path: PathBuf,
+    engine: Engine,
+    state: RwLock<ScriptState>,
+    /// 最近一次转换给脚本的配置视图；配置不变时复用，避免每次路由都重新序列化
+    config: RwLock<Option<(ScriptConfig, Dynamic)>>,
+}
+
+/// 交给脚本的配置视图
+#[derive(Debug, Clone, Serialize)]
+struct ScriptConfig {
+    #[serde(rename = "Router")]
+    router: RouterConfig,
+    #[serde(rename = "Providers")]
+    providers: Vec<ScriptProvider>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ScriptProvider {
+    name: String,
+    models: Vec<String>,
+}
+
+impl ScriptConfig {
+    fn new(config: &Config) -> Self {
+        Self {
+            router: config.router.clone(),
+            providers: config
+                .providers
+                .iter()
+                .map(|provider| ScriptProvider { name: provider.name.clone(), models: provider.models.clone() })
+                .collect(),
+        }
+    }
+
+    fn describes(&self, config: &Config) -> bool {
+        self.router == config.router
+            && self.providers.len() == config.providers.len()
+            && self
+                .providers
+                .iter()
+                .zip(&config.providers)
+                .all(|(view, provider)| view.name == provider.name && view.models == provider.models)
+    }
+}
+
+#[derive(Default)]
+struct ScriptState {
+    ast: Option<Arc<AST>>,
+    /// 最近一次加载时文件的修改时间；文件不存在时为 `None`
+    modified: Option<SystemTime>,
+}
+
+impl ScriptRouter {
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        Self::load_with_limits(path, ScriptLimits::default())
+    }
+
+    pub fn load_with_limits(path: impl Into<PathBuf>, limits: ScriptLimits) -> Self {
+        let router = Self {
+            path: path.into(),
+            engine: ScriptModule::sandboxed_engine(&limits),
+            state: RwLock::new(ScriptState::default()),
+            config: RwLock::new(None),
+        };
+        if router.modified().is_none() {
+            eprintln!("Custom router script {} not found", router.path.display());
+        }
+        router.current();
+        router
+    }
+
+    /// 按配置中的 `CUSTOM_ROUTER_PATH` 创建路由器，相对路径相对于配置目录
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .custom_router_path
+            .as_deref()
+            .filter(|path| !path.is_empty())
+            .map(|path| Self::load(get_config_dir().join(path)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// 返回当前可用的脚本，文件修改时间变化时先重新编译
+    fn current(&self) -> Option<Arc<AST>> {
+        let modified = self.modified();
+        {
+            let state = self.state.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if state.modified == modified {
+                return state.ast.clone();
+            }
+        }
+
+        let mut state = self.state.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if state.modified != modified {
+            state.modified = modified;
+            match self.compile() {
+                Ok(ast) => state.ast = Some(Arc::new(ast)),
+                Err(e) => eprintln!("Failed to load custom router script {}: {:#}", self.path.display(), e),
+            }
+        }
+        state.ast.clone()
+    }
+
+    /// 本次路由交给脚本的 `config`
+    fn config_view(&self, config: &Config) -> anyhow::Result<Dynamic> {
+        {
+            let cached = self.config.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some((view, value)) = cached.as_ref()
+                && view.describes(config)
+            {
+                return Ok(value.clone());
+            }
+        }
+
+        let view = ScriptConfig::new(config);
+        let value = rhai::serde::to_dynamic(&view)?;
+        *self.config.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some((view, value.clone()));
+        Ok(value)
+    }
+
+    fn compile(&self) -> anyhow::Result<AST> {
+        let source = std::fs::read_to_string(&self.path).context("failed to read script")?;
+        let ast = self.engine.compile(&source)?;
+        if !ast.iter_functions().any(|f| f.name == ROUTE_FN && f.params.len() == 4) {
+            return Err(anyhow!("script does not define {}(request, token_count, last_usage, config)", ROUTE_FN));
+        }
+        Ok(ast)
+    }
+}
+
+impl CustomRouter for ScriptRouter {
+    fn name(&self) -> &str {
+        "custom_router"
+    }
+
+    fn route(
+        &self,
+        req: &RouteRequest,
+        token_count: usize,
+        last_usage: Option<&Usage>,
+        config: &Config,
+    ) -> anyhow::Result<Option<String>> {
+        let Some(ast) = self.current() else {
+            return Ok(None);
+        };
+
+        let args = (
+            rhai::serde::to_dynamic(req)?,
+            Dynamic::from_int(token_count as rhai::INT),
+            rhai::serde::to_dynamic(last_usage)?,
+            self.config_view(config)?,
+        );
+        let output: Dynamic = self
+            .engine
+            .call_fn_with_options(CallFnOptions::new().eval_ast(false), &mut Scope::new(), &ast, ROUTE_FN, args)
+            .map_err(|e| anyhow!("{}: {}", ROUTE_FN, e))?;
+
+        if output.is_unit() {
+            return Ok(None);
+        }
+        let route = output
+            .into_immutable_string()
+            .map_err(|type_name| anyhow!("{} returned {} instead of a string", ROUTE_FN, type_name))?;
+        Ok(Some(route.to_string()).filter(|route| !route.is_empty()))
+    }
+}
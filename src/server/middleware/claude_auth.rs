@@ -4,16 +4,16 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use crate::config::types::Config;
+use crate::server::state::AppState;
+use crate::transformers::{GatewayError, GatewayErrorKind};
 
 pub async fn claude_auth_with_state(
-    State(state): State<Arc<RwLock<Config>>>,
+    State(state): State<Arc<AppState>>,
     req: Request,
     next: Next,
 ) -> Response {
     // 从配置中读取API密钥
-    let config = state.read().await;
+    let config = state.config.read().await;
     let expected_api_key = config.api_key.as_ref();
     
     match expected_api_key {
@@ -33,18 +33,12 @@ pub async fn claude_auth_with_state(
                     }
                 }
                 None => {
-                    return (
-                        axum::http::StatusCode::UNAUTHORIZED,
-                        "API key is required".to_string(),
-                    ).into_response();
+                    return GatewayError::new(GatewayErrorKind::AuthenticationError, "API key is required").into_response();
                 }
             };
             
             if auth_token != *expected_key {
-                return (
-                    axum::http::StatusCode::UNAUTHORIZED,
-                    "Invalid API key".to_string(),
-                ).into_response();
+                return GatewayError::new(GatewayErrorKind::AuthenticationError, "Invalid API key").into_response();
             }
         }
         None => {
@@ -66,9 +60,9 @@ pub async fn claude_auth_with_state(
             let is_local = allowed_hosts.iter().any(|allowed| host.starts_with(allowed));
             
             if !is_local {
-                return (
-                    axum::http::StatusCode::FORBIDDEN,
-                    "Access denied: Only localhost access is allowed when no API key is configured".to_string(),
+                return GatewayError::new(
+                    GatewayErrorKind::PermissionError,
+                    "Access denied: Only localhost access is allowed when no API key is configured",
                 ).into_response();
             }
         }
@@ -1,2 +1,4 @@
 pub mod middleware;
-pub mod server;
\ No newline at end of file
+pub mod response;
+pub mod server;
+pub mod state;
\ No newline at end of file
@@ -0,0 +1,59 @@
+use crate::router::route_logic::{RouteDecision, ROUTE_REASON_HEADER};
+use crate::transformers::identity::ResponseIdentity;
+use crate::transformers::GatewayError;
+use axum::{
+    body::Body,
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// 在响应头中写入实际使用的提供商与模型（`x-ccr-provider` / `x-ccr-model`）
+pub fn with_identity_headers(mut response: Response, identity: &ResponseIdentity) -> Response {
+    for (name, value) in identity.headers() {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+/// 在响应头中写入本次路由的原因（`x-ccr-route-reason`）；无法作为请求头取值的原因会被忽略
+pub fn with_route_reason(mut response: Response, decision: &RouteDecision) -> Response {
+    if let Ok(value) = HeaderValue::from_str(&decision.reason()) {
+        response.headers_mut().insert(ROUTE_REASON_HEADER, value);
+    }
+    response
+}
+
+/// 非流式 JSON 响应
+pub fn json_response(status: StatusCode, body: &serde_json::Value, identity: &ResponseIdentity) -> Response {
+    let response = Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    with_identity_headers(response, identity)
+}
+
+/// SSE 流式响应，`body` 由调用方按事件编码（见 `encode_sse_event`）
+pub fn sse_response(body: Body, identity: &ResponseIdentity) -> Response {
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .body(body)
+        .unwrap();
+    with_identity_headers(response, identity)
+}
+
+/// 以 Anthropic 错误格式返回网关错误，HTTP 状态码与错误类型一致（如 429、529）
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(Body::from(self.to_anthropic().to_string()))
+            .unwrap()
+    }
+}
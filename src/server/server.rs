@@ -1,20 +1,79 @@
+use crate::config::capabilities::CapabilityRegistry;
+use crate::config::constants::get_plugins_dir;
+use crate::config::project::PROJECT_HEADER;
 use crate::config::types::Config;
+use crate::plugins::{load_plugins_dir, WasmLimits, WasmRouter, WasmTransformer};
+use crate::router::custom_router::CustomRouter;
+use crate::router::route_handler::RouteHandler;
+use crate::router::route_logic::{RouteDecision, RouteRequest};
+use crate::router::script_router::ScriptRouter;
 use crate::server::middleware::claude_auth;
+use crate::server::response::{json_response, with_route_reason};
+use crate::server::state::AppState;
+use crate::transformers::identity::ResponseIdentity;
+use crate::transformers::upstream::UpstreamRequest;
+use crate::transformers::{TransformerError, TransformerManager, TransformerResult};
 use axum::{
+    extract::State,
+    http::HeaderMap,
     middleware,
     response::Response,
     routing::{get, post},
     Router,
 };
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
 pub struct ServerSetup;
 
 impl ServerSetup {
     pub async fn create_server(config: Config) -> Router {
+        // 注册内置转换器，并加载配置中的脚本转换器（加载失败的脚本会被跳过）
+        let plugins_dir = get_plugins_dir();
+        let mut transformers = TransformerManager::new();
+        if let Some(configs) = &config.transformers {
+            for error in transformers.load_scripts(configs, &plugins_dir) {
+                eprintln!("Failed to load transformer: {}", error);
+            }
+        }
+
+        // 加载插件目录中的 WASM 插件：转换插件注册为 hook，路由插件交给路由逻辑
+        let plugins = load_plugins_dir(&plugins_dir, WasmLimits::default());
+        for error in plugins.errors {
+            eprintln!("Failed to load plugin: {}", error);
+        }
+        for plugin in plugins.transformers {
+            let (name, path) = (plugin.name().to_string(), plugin.path().to_path_buf());
+            if let Err(error) = transformers.register_plugin(name, WasmTransformer::factory(plugin), &path) {
+                eprintln!("Failed to load plugin: {}", error);
+            }
+        }
+        // CUSTOM_ROUTER_PATH 指定的脚本路由器排在 WASM 路由插件之前
+        let script_router = ScriptRouter::from_config(&config).map(|router| Arc::new(router) as Arc<dyn CustomRouter>);
+        let routers = script_router
+            .into_iter()
+            .chain(
+                plugins
+                    .routers
+                    .into_iter()
+                    .map(|plugin| Arc::new(WasmRouter::new(plugin)) as Arc<dyn CustomRouter>),
+            )
+            .collect();
+
+        let app_state = AppState::with_transformers(config, transformers).with_routers(routers);
+        Self::create_server_with_state(app_state).await
+    }
+
+    /// 使用调用方注册好的转换器（内置 + 插件）创建服务器
+    pub async fn create_server_with_transformers(config: Config, transformers: TransformerManager) -> Router {
+        Self::create_server_with_state(AppState::with_transformers(config, transformers)).await
+    }
+
+    /// 使用完整的应用状态（转换器与自定义路由器）创建服务器
+    pub async fn create_server_with_state(app_state: AppState) -> Router {
         // 创建应用状态
-        let app_state = Arc::new(RwLock::new(config));
+        let app_state = Arc::new(app_state);
         
         // 创建路由
         let app = Router::new()
@@ -24,6 +83,7 @@ impl ServerSetup {
             .route("/api/restart", post(Self::restart_service))
             .route("/api/update/check", get(Self::check_update))
             .route("/api/update/perform", post(Self::perform_update))
+            .route("/api/route/preview", post(Self::preview_route))
             // Claude API endpoints - requires authentication
             .route("/v1/messages", post(Self::claude_messages))
             .route_layer(middleware::from_fn_with_state(
@@ -45,9 +105,10 @@ impl ServerSetup {
         r#"{"success": true, "message": "Config saved successfully"}"#.to_string()
     }
     
-    async fn get_transformers() -> String {
-        // TODO: 实现获取转换器的逻辑
-        r#"{"transformers": []}"#.to_string()
+    async fn get_transformers(State(state): State<Arc<AppState>>) -> axum::Json<serde_json::Value> {
+        axum::Json(serde_json::json!({
+            "transformers": state.transformers.list_transformers()
+        }))
     }
     
     async fn restart_service() -> String {
@@ -66,14 +127,22 @@ impl ServerSetup {
     }
     
     async fn claude_messages(
-        _state: axum::extract::State<Arc<RwLock<Config>>>,
-        _payload: axum::Json<serde_json::Value>,
+        State(state): State<Arc<AppState>>,
+        headers: HeaderMap,
+        payload: axum::Json<serde_json::Value>,
     ) -> Response {
         // TODO: 实现Claude消息处理逻辑
-        // 这里暂时返回一个简单的响应
-        
+        // 这里暂时返回一个简单的响应，但 id、模型名与响应头已按真实路由结果生成
+
+        let config = state.config.read().await;
+        let config = Self::effective_config(&state, &config, &headers);
+        let requested_model = payload.get("model").and_then(|m| m.as_str()).unwrap_or_default();
+        let mut route_request = RouteRequest::from_anthropic_body(&payload, Self::route_headers(&headers));
+        let decision = RouteHandler::handle_route_decision(&mut route_request, &config, &HashMap::new(), &state.routers);
+        let identity = ResponseIdentity::for_route(requested_model, &decision.target, config.response_model.unwrap_or_default());
+
         let response = serde_json::json!({
-            "id": "msg_123456789",
+            "id": identity.id(),
             "type": "message",
             "role": "assistant",
             "content": [
@@ -82,7 +151,7 @@ impl ServerSetup {
                     "text": "Hello! I'm your AI assistant. This is a placeholder response from the CodeRoutic server."
                 }
             ],
-            "model": "claude-3-sonnet-20240229",
+            "model": identity.model(),
             "stop_reason": "end_turn",
             "stop_sequence": null,
             "usage": {
@@ -90,11 +159,69 @@ impl ServerSetup {
                 "output_tokens": 18
             }
         });
-        
-        Response::builder()
+
+        with_route_reason(json_response(axum::http::StatusCode::OK, &response, &identity), &decision)
+    }
+
+    /// 路由预演：返回对该 Messages 请求的路由决定，以及将发往上游的请求（认证头已隐去），不实际发送
+    async fn preview_route(
+        State(state): State<Arc<AppState>>,
+        headers: HeaderMap,
+        payload: axum::Json<serde_json::Value>,
+    ) -> Response {
+        let config = state.config.read().await;
+        let config = Self::effective_config(&state, &config, &headers);
+        let mut route_request = RouteRequest::from_anthropic_body(&payload, Self::route_headers(&headers));
+        let decision = RouteHandler::handle_route_decision(&mut route_request, &config, &HashMap::new(), &state.routers);
+
+        // 决定本身总会返回；目标无法构造上游请求时附带错误信息
+        let body = match Self::upstream_request(&state.transformers, &config, &decision, &payload, &route_request.headers) {
+            Ok(upstream) => serde_json::json!({"decision": decision, "upstream": upstream.redacted()}),
+            Err(e) => serde_json::json!({"decision": decision, "upstream": null, "error": e.to_string()}),
+        };
+        let response = Response::builder()
             .status(axum::http::StatusCode::OK)
             .header("content-type", "application/json")
-            .body(axum::body::Body::from(serde_json::to_string(&response).unwrap()))
-            .unwrap()
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap();
+        with_route_reason(response, &decision)
+    }
+
+    /// 按路由目标查找提供商并构造上游请求；目标同为 Anthropic 格式时保留客户端的 `anthropic-beta` 等请求头
+    fn upstream_request(
+        transformers: &TransformerManager,
+        config: &Config,
+        decision: &RouteDecision,
+        payload: &serde_json::Value,
+        headers: &HashMap<String, String>,
+    ) -> TransformerResult<UpstreamRequest> {
+        let (provider_name, model) = decision.target.split_once(',').ok_or_else(|| {
+            TransformerError::Configuration(format!("Route '{}' is not in provider,model form", decision.target))
+        })?;
+        let provider = config
+            .providers
+            .iter()
+            .find(|provider| provider.name == provider_name)
+            .ok_or_else(|| TransformerError::Configuration(format!("Provider '{}' is not configured", provider_name)))?;
+        let chain = transformers.resolve_chain(provider, model)?;
+        let capabilities = CapabilityRegistry::from_config(config).lookup(provider_name, model);
+        let (upstream, _) =
+            transformers.build_upstream_request("anthropic", &chain, provider, model, payload, headers, &capabilities)?;
+        Ok(upstream)
+    }
+
+    /// 本次请求生效的配置：全局配置叠加 `x-ccr-project` 所在项目的 `.code-routic.json`
+    fn effective_config<'a>(state: &AppState, config: &'a Config, headers: &HeaderMap) -> Cow<'a, Config> {
+        let project_dir = headers.get(PROJECT_HEADER).and_then(|value| value.to_str().ok());
+        state.project_configs.resolve(config, project_dir)
+    }
+
+    /// 供路由规则匹配的请求头；认证头不参与路由，也不会交给自定义路由器
+    fn route_headers(headers: &HeaderMap) -> HashMap<String, String> {
+        headers
+            .iter()
+            .filter(|(name, _)| !matches!(name.as_str(), "authorization" | "x-api-key"))
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect()
     }
 }
\ No newline at end of file
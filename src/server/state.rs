@@ -0,0 +1,36 @@
+use crate::config::project::ProjectConfigCache;
+use crate::config::types::Config;
+use crate::router::custom_router::CustomRouter;
+use crate::transformers::TransformerManager;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 服务器共享状态：可热更新的配置、启动时注册好的转换器与自定义路由器，以及项目配置缓存
+pub struct AppState {
+    pub config: RwLock<Config>,
+    pub transformers: TransformerManager,
+    pub routers: Vec<Arc<dyn CustomRouter>>,
+    pub project_configs: ProjectConfigCache,
+}
+
+impl AppState {
+    pub fn new(config: Config) -> Self {
+        Self::with_transformers(config, TransformerManager::new())
+    }
+
+    /// 使用已注册插件的转换器注册表创建状态
+    pub fn with_transformers(config: Config, transformers: TransformerManager) -> Self {
+        Self {
+            config: RwLock::new(config),
+            transformers,
+            routers: Vec::new(),
+            project_configs: ProjectConfigCache::new(),
+        }
+    }
+
+    /// 设置在内置路由检查之前调用的自定义路由器
+    pub fn with_routers(mut self, routers: Vec<Arc<dyn CustomRouter>>) -> Self {
+        self.routers = routers;
+        self
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::transformers::error::{TransformerError, TransformerResult};
+
+pub const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const JWT_BEARER_GRANT: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+const ASSERTION_LIFETIME_SECS: i64 = 3600;
+/// Tokens are refreshed this long before they expire so in-flight requests do not race expiry.
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+/// The subset of a Google service-account JSON key file needed to mint access tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceAccountKey {
+    pub client_email: String,
+    pub private_key: String,
+    pub private_key_id: Option<String>,
+    pub project_id: Option<String>,
+    pub token_uri: Option<String>,
+}
+
+impl ServiceAccountKey {
+    pub fn from_file(path: impl AsRef<Path>) -> TransformerResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            TransformerError::Configuration(format!(
+                "Failed to read service account file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            TransformerError::Configuration(format!(
+                "Invalid service account file {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServiceAccountClaims {
+    pub iss: String,
+    pub scope: String,
+    pub aud: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Exchanges a signed service-account JWT for an OAuth access token and caches it until
+/// shortly before expiry.
+pub struct ServiceAccountTokenProvider {
+    key: ServiceAccountKey,
+    token_uri: String,
+    scope: String,
+    cache: Mutex<Option<CachedToken>>,
+}
+
+impl ServiceAccountTokenProvider {
+    pub fn new(key: ServiceAccountKey) -> Self {
+        let token_uri = key
+            .token_uri
+            .clone()
+            .unwrap_or_else(|| DEFAULT_TOKEN_URI.to_string());
+        Self {
+            key,
+            token_uri,
+            scope: CLOUD_PLATFORM_SCOPE.to_string(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Overrides the token endpoint from the key file, e.g. to point at a local stand-in.
+    pub fn with_token_uri(mut self, token_uri: impl Into<String>) -> Self {
+        self.token_uri = token_uri.into();
+        self
+    }
+
+    pub fn key(&self) -> &ServiceAccountKey {
+        &self.key
+    }
+
+    pub fn token_uri(&self) -> &str {
+        &self.token_uri
+    }
+
+    /// Builds the RS256 assertion sent to the token endpoint.
+    pub fn signed_jwt(&self, now: DateTime<Utc>) -> TransformerResult<String> {
+        let claims = ServiceAccountClaims {
+            iss: self.key.client_email.clone(),
+            scope: self.scope.clone(),
+            aud: self.token_uri.clone(),
+            iat: now.timestamp(),
+            exp: now.timestamp() + ASSERTION_LIFETIME_SECS,
+        };
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = self.key.private_key_id.clone();
+
+        let encoding_key = EncodingKey::from_rsa_pem(self.key.private_key.as_bytes())
+            .map_err(|e| TransformerError::Configuration(format!("Invalid service account private key: {}", e)))?;
+        jsonwebtoken::encode(&header, &claims, &encoding_key)
+            .map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    /// Returns the cached token if it is still valid at `now`.
+    pub fn cached_token(&self, now: DateTime<Utc>) -> Option<String> {
+        let cache = self.cache.lock().ok()?;
+        cache
+            .as_ref()
+            .filter(|token| token.expires_at - Duration::seconds(REFRESH_MARGIN_SECS) > now)
+            .map(|token| token.access_token.clone())
+    }
+
+    pub async fn access_token(&self, client: &reqwest::Client) -> TransformerResult<String> {
+        let now = Utc::now();
+        if let Some(token) = self.cached_token(now) {
+            return Ok(token);
+        }
+
+        let assertion = self.signed_jwt(now)?;
+        let response = client
+            .post(&self.token_uri)
+            .form(&[("grant_type", JWT_BEARER_GRANT), ("assertion", assertion.as_str())])
+            .send()
+            .await
+            .map_err(|e| TransformerError::ProviderError(format!("Token request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(TransformerError::ProviderError(format!(
+                "Token endpoint returned {}: {}",
+                status, body
+            )));
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+        let expires_at = now + Duration::seconds(token.expires_in.unwrap_or(ASSERTION_LIFETIME_SECS));
+
+        if let Ok(mut cache) = self.cache.lock() {
+            *cache = Some(CachedToken {
+                access_token: token.access_token.clone(),
+                expires_at,
+            });
+        }
+        Ok(token.access_token)
+    }
+}
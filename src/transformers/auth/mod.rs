@@ -0,0 +1,5 @@
+pub mod sigv4;
+pub mod google;
+
+pub use sigv4::{AwsCredentials, SigV4Signer};
+pub use google::{ServiceAccountKey, ServiceAccountTokenProvider};
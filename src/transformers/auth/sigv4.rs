@@ -0,0 +1,203 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::env;
+
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::upstream::UpstreamRequest;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn new(
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+        session_token: Option<String>,
+    ) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token,
+        }
+    }
+
+    /// Reads the standard `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` / `AWS_SESSION_TOKEN` variables.
+    pub fn from_env() -> Option<Self> {
+        let access_key_id = env::var("AWS_ACCESS_KEY_ID").ok()?;
+        let secret_access_key = env::var("AWS_SECRET_ACCESS_KEY").ok()?;
+        let session_token = env::var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty());
+        Some(Self::new(access_key_id, secret_access_key, session_token))
+    }
+}
+
+/// AWS Signature Version 4 request signer.
+pub struct SigV4Signer<'a> {
+    credentials: &'a AwsCredentials,
+    region: &'a str,
+    service: &'a str,
+}
+
+impl<'a> SigV4Signer<'a> {
+    pub fn new(credentials: &'a AwsCredentials, region: &'a str, service: &'a str) -> Self {
+        Self {
+            credentials,
+            region,
+            service,
+        }
+    }
+
+    pub fn sign(&self, request: &mut UpstreamRequest, now: DateTime<Utc>) -> TransformerResult<()> {
+        let body = request.body_bytes()?;
+        self.sign_parts(&request.method, &request.url, &mut request.headers, &body, now)
+    }
+
+    /// Adds `x-amz-date`, the session token when present, and the `authorization` header.
+    ///
+    /// Every header already in `headers` is signed; `host` is derived from the URL unless
+    /// it was set explicitly.
+    pub fn sign_parts(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &mut Vec<(String, String)>,
+        body: &[u8],
+        now: DateTime<Utc>,
+    ) -> TransformerResult<()> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| TransformerError::InvalidFormat(format!("Invalid URL {}: {}", url, e)))?;
+
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        Self::replace_header(headers, "x-amz-date", &amz_date);
+        if let Some(token) = &self.credentials.session_token {
+            Self::replace_header(headers, "x-amz-security-token", token);
+        }
+
+        let mut canonical_headers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, value) in headers.iter() {
+            canonical_headers
+                .entry(name.to_ascii_lowercase())
+                .or_default()
+                .push(Self::normalize_header_value(value));
+        }
+        if !canonical_headers.contains_key("host") {
+            let host = url
+                .host_str()
+                .ok_or_else(|| TransformerError::InvalidFormat("URL has no host".to_string()))?;
+            let host = match url.port() {
+                Some(port) => format!("{}:{}", host, port),
+                None => host.to_string(),
+            };
+            canonical_headers.insert("host".to_string(), vec![host]);
+        }
+
+        let signed_headers = canonical_headers.keys().cloned().collect::<Vec<_>>().join(";");
+        let canonical_headers_block: String = canonical_headers
+            .iter()
+            .map(|(name, values)| format!("{}:{}\n", name, values.join(",")))
+            .collect();
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method.to_ascii_uppercase(),
+            Self::canonical_uri(url.path()),
+            Self::canonical_query(&url),
+            canonical_headers_block,
+            signed_headers,
+            hex::encode(Sha256::digest(body)),
+        );
+
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let signing_key = self.signing_key(&date)?;
+        let signature = hex::encode(Self::hmac(&signing_key, string_to_sign.as_bytes())?);
+
+        Self::replace_header(
+            headers,
+            "authorization",
+            &format!(
+                "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                ALGORITHM, self.credentials.access_key_id, scope, signed_headers, signature
+            ),
+        );
+        Ok(())
+    }
+
+    fn signing_key(&self, date: &str) -> TransformerResult<Vec<u8>> {
+        let secret = format!("AWS4{}", self.credentials.secret_access_key);
+        let k_date = Self::hmac(secret.as_bytes(), date.as_bytes())?;
+        let k_region = Self::hmac(&k_date, self.region.as_bytes())?;
+        let k_service = Self::hmac(&k_region, self.service.as_bytes())?;
+        Self::hmac(&k_service, b"aws4_request")
+    }
+
+    fn hmac(key: &[u8], data: &[u8]) -> TransformerResult<Vec<u8>> {
+        let mut mac = HmacSha256::new_from_slice(key)
+            .map_err(|e| TransformerError::Configuration(e.to_string()))?;
+        mac.update(data);
+        Ok(mac.finalize().into_bytes().to_vec())
+    }
+
+    fn replace_header(headers: &mut Vec<(String, String)>, name: &str, value: &str) {
+        headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        headers.push((name.to_string(), value.to_string()));
+    }
+
+    fn normalize_header_value(value: &str) -> String {
+        value.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Non-S3 services encode each path segment a second time, so an already escaped
+    /// `%3A` in a Bedrock model id is signed as `%253A`.
+    fn canonical_uri(path: &str) -> String {
+        if path.is_empty() {
+            return "/".to_string();
+        }
+        path.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+    }
+
+    fn canonical_query(url: &reqwest::Url) -> String {
+        let mut pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| (uri_encode(&key), uri_encode(&value)))
+            .collect();
+        pairs.sort();
+        pairs
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+/// RFC 3986 encoding as required by SigV4: everything except unreserved characters is escaped.
+pub fn uri_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
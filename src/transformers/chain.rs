@@ -0,0 +1,210 @@
+use serde_json::Value;
+use std::sync::Mutex;
+
+use crate::config::types::Transformer;
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::identity::ResponseIdentity;
+use crate::transformers::json_repair::ToolCallAccumulator;
+use crate::transformers::providers::provider_trait::{ChatRequest, ChatResponse, ChatStreamChunk, ProviderTransformer, StreamEncoder};
+use crate::transformers::structured_output::StructuredOutput;
+use crate::transformers::tool_names::ToolNameMap;
+
+/// A transformer that rewrites requests and responses in the universal format without
+/// changing the wire format (e.g. clamping `max_tokens`). All hooks default to no-ops.
+pub trait TransformerHook: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn transform_request(&self, _request: &mut ChatRequest) -> TransformerResult<()> {
+        Ok(())
+    }
+
+    fn transform_response(&self, _response: &mut ChatResponse) -> TransformerResult<()> {
+        Ok(())
+    }
+
+    fn transform_stream_chunk(&self, _chunk: &mut ChatStreamChunk) -> TransformerResult<()> {
+        Ok(())
+    }
+}
+
+/// Builds a hook from the options given in `["name", {options}]`, or `None` for a bare name.
+pub type HookFactory = Box<dyn Fn(Option<&Value>) -> TransformerResult<Box<dyn TransformerHook>> + Send + Sync>;
+
+/// Builds a configured provider transformer from `["name", {options}]`.
+pub type ProviderFactory = Box<dyn Fn(&Value) -> TransformerResult<Box<dyn ProviderTransformer>> + Send + Sync>;
+
+/// One entry of a `use` array: `"name"` or `["name", {options}]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainEntry {
+    pub name: String,
+    pub options: Option<Value>,
+}
+
+impl ChainEntry {
+    pub fn parse(entry: &Value) -> TransformerResult<Self> {
+        match entry {
+            Value::String(name) => Ok(Self { name: name.clone(), options: None }),
+            Value::Array(items) => {
+                let name = items.first().and_then(Value::as_str).ok_or_else(|| {
+                    TransformerError::Configuration(format!("Transformer entry {} must start with a name", entry))
+                })?;
+                if items.len() > 2 {
+                    return Err(TransformerError::Configuration(format!(
+                        "Transformer entry {} must be [name] or [name, options]",
+                        entry
+                    )));
+                }
+                Ok(Self {
+                    name: name.to_string(),
+                    options: items.get(1).filter(|options| !options.is_null()).cloned(),
+                })
+            }
+            _ => Err(TransformerError::Configuration(format!("Invalid transformer entry: {}", entry))),
+        }
+    }
+
+    /// The provider-level `use` list followed by the model's own list. A model entry that
+    /// names a transformer already in the provider list replaces it in place, so per-model
+    /// options override the provider's.
+    pub fn resolve(transformer: &Transformer, model: &str) -> TransformerResult<Vec<Self>> {
+        let mut entries = transformer
+            .use_transformers
+            .iter()
+            .map(Self::parse)
+            .collect::<TransformerResult<Vec<_>>>()?;
+
+        if let Some(model_transformer) = transformer.model_specific.get(model) {
+            for entry in &model_transformer.use_transformers {
+                let entry = Self::parse(entry)?;
+                match entries.iter_mut().find(|existing| existing.name == entry.name) {
+                    Some(existing) => *existing = entry,
+                    None => entries.push(entry),
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+pub(crate) enum ChainProvider<'a> {
+    Shared(&'a dyn ProviderTransformer),
+    Configured(Box<dyn ProviderTransformer>),
+}
+
+/// The resolved chain for one provider and model: the provider transformer that owns the
+/// wire format, plus hooks in `use` order. A chain is resolved per request, so it also
+/// carries the request's stream state.
+pub struct TransformerChain<'a> {
+    provider: ChainProvider<'a>,
+    hooks: Vec<Box<dyn TransformerHook>>,
+    tool_calls: Mutex<ToolCallAccumulator>,
+    /// Set once a request with `response_format` has been converted through the chain
+    structured_output: Mutex<Option<StructuredOutput>>,
+    /// Created on the first chunk encoded through the chain, with the client format's name
+    stream_encoder: Mutex<Option<(&'static str, Box<dyn StreamEncoder>)>>,
+    identity: Option<ResponseIdentity>,
+}
+
+impl<'a> TransformerChain<'a> {
+    pub(crate) fn new(provider: ChainProvider<'a>, hooks: Vec<Box<dyn TransformerHook>>) -> Self {
+        Self {
+            provider,
+            hooks,
+            tool_calls: Mutex::new(ToolCallAccumulator::new()),
+            structured_output: Mutex::new(None),
+            stream_encoder: Mutex::new(None),
+            identity: None,
+        }
+    }
+
+    /// Rewrites the id and model of every response converted through this chain; see
+    /// [`ResponseIdentity`].
+    pub fn with_identity(mut self, identity: ResponseIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    pub fn identity(&self) -> Option<&ResponseIdentity> {
+        self.identity.as_ref()
+    }
+
+    pub fn provider(&self) -> &dyn ProviderTransformer {
+        match &self.provider {
+            ChainProvider::Shared(provider) => *provider,
+            ChainProvider::Configured(provider) => provider.as_ref(),
+        }
+    }
+
+    pub fn hook_names(&self) -> Vec<&str> {
+        self.hooks.iter().map(|hook| hook.name()).collect()
+    }
+
+    /// Runs request hooks in `use` order.
+    pub fn apply_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        self.hooks.iter().try_for_each(|hook| hook.transform_request(request))
+    }
+
+    /// Runs response hooks in reverse, so the first hook sees the response last.
+    pub fn apply_response(&self, response: &mut ChatResponse) -> TransformerResult<()> {
+        self.hooks.iter().rev().try_for_each(|hook| hook.transform_response(response))
+    }
+
+    pub fn apply_stream_chunk(&self, chunk: &mut ChatStreamChunk) -> TransformerResult<()> {
+        self.hooks.iter().rev().try_for_each(|hook| hook.transform_stream_chunk(chunk))
+    }
+
+    /// Records whether the request converted through this chain asked for structured
+    /// output, which decides whether responses are unwrapped; see [`StructuredOutput`].
+    pub fn record_request(&self, request: &ChatRequest) {
+        *self.structured_output.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            request.response_format.is_some().then(StructuredOutput::new);
+    }
+
+    pub fn unwrap_structured_output(&self, response: &mut ChatResponse) {
+        let structured_output = self.structured_output.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(structured_output) = structured_output.as_ref() {
+            structured_output.unwrap_response(response);
+        }
+    }
+
+    pub fn unwrap_structured_output_chunk(&self, chunk: &mut ChatStreamChunk) {
+        let mut structured_output = self.structured_output.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(structured_output) = structured_output.as_mut() {
+            structured_output.unwrap_stream_chunk(chunk);
+        }
+    }
+
+    /// Buffers tool-call argument fragments and releases each call's arguments whole and
+    /// repaired; see [`ToolCallAccumulator`].
+    pub fn accumulate_tool_calls(&self, chunk: &mut ChatStreamChunk, tool_names: &ToolNameMap) {
+        self.tool_calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .process_chunk(chunk, tool_names);
+    }
+
+    /// Encodes a universal chunk in the client's format, keeping the format's stream state
+    /// (see [`StreamEncoder`]) for the rest of the stream.
+    pub fn encode_stream_chunk(&self, target: &dyn ProviderTransformer, chunk: &ChatStreamChunk) -> TransformerResult<Value> {
+        let mut stream_encoder = self.stream_encoder.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let format = target.provider_name();
+        if stream_encoder.as_ref().is_none_or(|(name, _)| *name != format) {
+            *stream_encoder = target.stream_encoder().map(|encoder| (format, encoder));
+        }
+        match stream_encoder.as_mut() {
+            Some((_, encoder)) => encoder.encode(chunk),
+            None => target.from_universal_stream_chunk(chunk),
+        }
+    }
+
+    /// The events still owed to the client once the upstream stream ends, as an array.
+    pub fn finish_stream(&self) -> TransformerResult<Value> {
+        let mut stream_encoder = self.stream_encoder.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match stream_encoder.as_mut() {
+            Some((_, encoder)) => encoder.finish(),
+            None => Ok(Value::Array(Vec::new())),
+        }
+    }
+
+}
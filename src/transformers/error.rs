@@ -1,5 +1,9 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use thiserror::Error;
 
+use crate::transformers::stream::encode_sse_event;
+
 #[derive(Debug, Error)]
 pub enum TransformerError {
     #[error("Serialization error: {0}")]
@@ -18,6 +22,226 @@ pub enum TransformerError {
     ProviderError(String),
     #[error("Configuration error: {0}")]
     Configuration(String),
+    #[error("Script error: {0}")]
+    Script(String),
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+}
+
+pub type TransformerResult<T> = Result<T, TransformerError>;
+
+/// Anthropic's error `type`s. Clients decide whether to retry from the type and status,
+/// so every gateway failure is reported as one of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayErrorKind {
+    InvalidRequestError,
+    AuthenticationError,
+    PermissionError,
+    NotFoundError,
+    RequestTooLarge,
+    RateLimitError,
+    ApiError,
+    OverloadedError,
+}
+
+impl GatewayErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidRequestError => "invalid_request_error",
+            Self::AuthenticationError => "authentication_error",
+            Self::PermissionError => "permission_error",
+            Self::NotFoundError => "not_found_error",
+            Self::RequestTooLarge => "request_too_large",
+            Self::RateLimitError => "rate_limit_error",
+            Self::ApiError => "api_error",
+            Self::OverloadedError => "overloaded_error",
+        }
+    }
+
+    /// The HTTP status Anthropic uses for this type.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::InvalidRequestError => 400,
+            Self::AuthenticationError => 401,
+            Self::PermissionError => 403,
+            Self::NotFoundError => 404,
+            Self::RequestTooLarge => 413,
+            Self::RateLimitError => 429,
+            Self::ApiError => 500,
+            Self::OverloadedError => 529,
+        }
+    }
+
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => Self::AuthenticationError,
+            403 => Self::PermissionError,
+            404 => Self::NotFoundError,
+            413 => Self::RequestTooLarge,
+            429 => Self::RateLimitError,
+            503 | 529 => Self::OverloadedError,
+            400..=499 => Self::InvalidRequestError,
+            _ => Self::ApiError,
+        }
+    }
+
+    /// Maps a provider's own error code: Anthropic and OpenAI `error.type`/`error.code`,
+    /// or Gemini's `error.status`.
+    fn from_code(code: &str) -> Option<Self> {
+        let kind = match code {
+            "invalid_request_error" | "INVALID_ARGUMENT" | "FAILED_PRECONDITION" | "context_length_exceeded" => {
+                Self::InvalidRequestError
+            }
+            "authentication_error" | "invalid_api_key" | "UNAUTHENTICATED" => Self::AuthenticationError,
+            // An exhausted billing quota is not retryable, unlike a rate limit
+            "permission_error" | "insufficient_quota" | "PERMISSION_DENIED" => Self::PermissionError,
+            "not_found_error" | "model_not_found" | "NOT_FOUND" => Self::NotFoundError,
+            "request_too_large" => Self::RequestTooLarge,
+            "rate_limit_error" | "rate_limit_exceeded" | "RESOURCE_EXHAUSTED" => Self::RateLimitError,
+            "overloaded_error" | "overloaded" | "engine_overloaded" | "UNAVAILABLE" => Self::OverloadedError,
+            "api_error" | "server_error" | "INTERNAL" | "DEADLINE_EXCEEDED" => Self::ApiError,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+impl std::fmt::Display for GatewayErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
-pub type TransformerResult<T> = Result<T, TransformerError>;
\ No newline at end of file
+/// A failure reported to the client in Anthropic's error format, either as an HTTP
+/// response or, once a stream has started, as an `event: error`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind}: {message}")]
+pub struct GatewayError {
+    pub kind: GatewayErrorKind,
+    pub message: String,
+    /// The provider's HTTP status, when the error came from upstream
+    pub upstream_status: Option<u16>,
+}
+
+impl GatewayError {
+    pub fn new(kind: GatewayErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            upstream_status: None,
+        }
+    }
+
+    /// Maps an upstream error response. The body's error code refines the status, since
+    /// providers disagree on statuses (Gemini reports quota errors as 429 `RESOURCE_EXHAUSTED`,
+    /// some OpenAI-compatible servers report overload as 500).
+    pub fn from_upstream(status: u16, body: &[u8]) -> Self {
+        let parsed: Option<Value> = serde_json::from_slice(body).ok();
+        let error = parsed.as_ref().and_then(Self::error_object);
+
+        let kind = error
+            .and_then(Self::error_code)
+            .and_then(GatewayErrorKind::from_code)
+            .unwrap_or_else(|| GatewayErrorKind::from_status(status));
+        let message = error
+            .and_then(|error| error.get("message"))
+            .and_then(|message| message.as_str())
+            .map(str::to_string)
+            .or_else(|| {
+                let text = String::from_utf8_lossy(body).trim().to_string();
+                (!text.is_empty()).then_some(text)
+            })
+            .unwrap_or_else(|| format!("Upstream returned HTTP {}", status));
+
+        Self {
+            kind,
+            message,
+            upstream_status: Some(status),
+        }
+    }
+
+    /// Recognizes an error delivered inside an upstream stream: an Anthropic `error` event,
+    /// or an OpenAI/Gemini chunk carrying an `error` object.
+    pub fn from_stream_event(event: &Value) -> Option<Self> {
+        let error = Self::error_object(event)?;
+        // Gemini puts the HTTP status in a numeric `code`
+        let status = error.get("code").and_then(|code| code.as_u64());
+        let kind = Self::error_code(error)
+            .and_then(GatewayErrorKind::from_code)
+            .or_else(|| status.map(|status| GatewayErrorKind::from_status(status as u16)))
+            .unwrap_or(GatewayErrorKind::ApiError);
+        let message = error
+            .get("message")
+            .and_then(|message| message.as_str())
+            .unwrap_or("Upstream stream failed");
+        Some(Self::new(kind, message))
+    }
+
+    /// `{"error": {...}}` from any provider; Gemini may wrap it in a one-element array.
+    fn error_object(body: &Value) -> Option<&Value> {
+        let body = match body {
+            Value::Array(items) => items.first()?,
+            body => body,
+        };
+        body.get("error").filter(|error| error.is_object())
+    }
+
+    fn error_code(error: &Value) -> Option<&str> {
+        ["status", "code", "type"].iter().find_map(|field| {
+            error
+                .get(*field)
+                .and_then(|code| code.as_str())
+                .filter(|code| GatewayErrorKind::from_code(code).is_some())
+        })
+    }
+
+    pub fn status(&self) -> u16 {
+        self.kind.status()
+    }
+
+    /// The Anthropic error body.
+    pub fn to_anthropic(&self) -> Value {
+        json!({
+            "type": "error",
+            "error": {
+                "type": self.kind.as_str(),
+                "message": self.message,
+            }
+        })
+    }
+
+    /// The error as an SSE event, for failures after the stream has started.
+    pub fn to_sse_event(&self) -> String {
+        encode_sse_event(&self.to_anthropic())
+    }
+}
+
+impl GatewayError {
+    /// Maps a failure converting the upstream's response or stream for the client, such as
+    /// a body that does not parse or a corrupt event-stream frame. The client's request was
+    /// fine, so these are always `api_error`; `From<TransformerError>` is for the request side.
+    pub fn from_response_error(error: TransformerError) -> Self {
+        Self::new(GatewayErrorKind::ApiError, error.to_string())
+    }
+}
+
+/// Maps a failure converting the client's request: malformed input is the client's fault.
+/// Use `GatewayError::from_response_error` for failures on the response side.
+impl From<TransformerError> for GatewayError {
+    fn from(error: TransformerError) -> Self {
+        let kind = match &error {
+            TransformerError::Deserialization(_)
+            | TransformerError::InvalidFormat(_)
+            | TransformerError::ToolConversion(_)
+            | TransformerError::MessageConversion(_)
+            | TransformerError::UnsupportedProvider(_) => GatewayErrorKind::InvalidRequestError,
+            TransformerError::Serialization(_)
+            | TransformerError::ProviderError(_)
+            | TransformerError::Configuration(_)
+            | TransformerError::Script(_)
+            | TransformerError::Plugin(_) => GatewayErrorKind::ApiError,
+        };
+        Self::new(kind, error.to_string())
+    }
+}
@@ -0,0 +1,419 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+use crate::transformers::chain::{HookFactory, TransformerHook};
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::provider_trait::*;
+
+const THINK_OPEN: &str = "<think>";
+const THINK_CLOSE: &str = "</think>";
+/// Output room left for the answer when `reasoning` has to set `max_tokens` itself.
+const REASONING_ANSWER_TOKENS: u32 = 4096;
+
+/// The built-in hooks, keyed by the name used in `Provider.transformer.use`.
+pub fn builtin_hooks() -> Vec<(&'static str, HookFactory)> {
+    vec![
+        ("maxtoken", Box::new(|options| Ok(Box::new(MaxTokenHook::from_options(options)?)))),
+        ("sampling", Box::new(|options| Ok(Box::new(SamplingHook::from_options(options)?)))),
+        ("cleancache", Box::new(|_| Ok(Box::new(CleanCacheHook)))),
+        ("tooluse", Box::new(|options| Ok(Box::new(ToolUseHook::from_options(options)?)))),
+        ("reasoning", Box::new(|options| Ok(Box::new(ReasoningHook::from_options(options)?)))),
+        ("stripthinking", Box::new(|_| Ok(Box::new(StripThinkingHook::new())))),
+    ]
+}
+
+fn parse_options<T: DeserializeOwned + Default>(name: &str, options: Option<&Value>) -> TransformerResult<T> {
+    match options {
+        Some(options) => serde_json::from_value(options.clone())
+            .map_err(|e| TransformerError::Configuration(format!("Invalid options for '{}': {}", name, e))),
+        None => Ok(T::default()),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaxTokenOptions {
+    pub max_tokens: Option<u32>,
+}
+
+/// `["maxtoken", {"max_tokens": 4096}]`: sends the given `max_tokens` regardless of the client.
+pub struct MaxTokenHook {
+    max_tokens: u32,
+}
+
+impl MaxTokenHook {
+    pub fn new(max_tokens: u32) -> Self {
+        Self { max_tokens }
+    }
+
+    pub fn from_options(options: Option<&Value>) -> TransformerResult<Self> {
+        let options: MaxTokenOptions = parse_options("maxtoken", options)?;
+        let max_tokens = options
+            .max_tokens
+            .ok_or_else(|| TransformerError::Configuration("'maxtoken' requires a max_tokens option".to_string()))?;
+        Ok(Self::new(max_tokens))
+    }
+}
+
+impl TransformerHook for MaxTokenHook {
+    fn name(&self) -> &str {
+        "maxtoken"
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        request.max_tokens = Some(self.max_tokens);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamplingOptions {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+}
+
+/// `["sampling", {"temperature": 0.6, "top_p": 0.95}]`: overrides whichever values are set.
+pub struct SamplingHook {
+    options: SamplingOptions,
+}
+
+impl SamplingHook {
+    pub fn with_options(options: SamplingOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn from_options(options: Option<&Value>) -> TransformerResult<Self> {
+        let options: SamplingOptions = parse_options("sampling", options)?;
+        if options.temperature.is_none() && options.top_p.is_none() {
+            return Err(TransformerError::Configuration(
+                "'sampling' requires a temperature or top_p option".to_string(),
+            ));
+        }
+        Ok(Self::with_options(options))
+    }
+}
+
+impl TransformerHook for SamplingHook {
+    fn name(&self) -> &str {
+        "sampling"
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        if let Some(temperature) = self.options.temperature {
+            request.temperature = Some(temperature);
+        }
+        if let Some(top_p) = self.options.top_p {
+            request.top_p = Some(top_p);
+        }
+        Ok(())
+    }
+}
+
+/// `cleancache`: drops `cache_control` markers for providers that reject them.
+pub struct CleanCacheHook;
+
+impl TransformerHook for CleanCacheHook {
+    fn name(&self) -> &str {
+        "cleancache"
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        for message in &mut request.messages {
+            if let MessageContent::Parts(parts) = &mut message.content {
+                for part in parts {
+                    part.cache_control = None;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolUseOptions {
+    /// Forces this tool instead of "any tool"
+    pub tool_name: Option<String>,
+}
+
+/// `tooluse`: when the request has tools, requires the model to call one. Useful for models
+/// that otherwise answer in prose instead of calling tools.
+pub struct ToolUseHook {
+    options: ToolUseOptions,
+}
+
+impl ToolUseHook {
+    pub fn with_options(options: ToolUseOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn from_options(options: Option<&Value>) -> TransformerResult<Self> {
+        Ok(Self::with_options(parse_options("tooluse", options)?))
+    }
+}
+
+impl TransformerHook for ToolUseHook {
+    fn name(&self) -> &str {
+        "tooluse"
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        let Some(tools) = request.tools.as_ref().filter(|tools| !tools.is_empty()) else {
+            return Ok(());
+        };
+        // An explicit "none" or a specific tool from the client wins
+        if matches!(request.tool_choice, Some(ToolChoice::None(_)) | Some(ToolChoice::Specific(_))) {
+            return Ok(());
+        }
+
+        request.tool_choice = Some(match &self.options.tool_name {
+            Some(name) if tools.iter().any(|tool| &tool.function.name == name) => {
+                ToolChoice::Specific(ToolChoiceSpecific {
+                    choice_type: "function".to_string(),
+                    function: FunctionChoice { name: name.clone() },
+                })
+            }
+            _ => ToolChoice::Required("required".to_string()),
+        });
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReasoningOptions {
+    /// `false` removes reasoning from every request
+    pub enabled: Option<bool>,
+    /// Forces `low`, `medium` or `high`
+    pub effort: Option<String>,
+    /// Forces the thinking budget
+    pub budget_tokens: Option<u32>,
+}
+
+/// `reasoning`: fills in whichever reasoning knob the target reads. Anthropic and Gemini
+/// take a token budget, OpenAI-style APIs take an effort level; a request carrying only one
+/// gets the other derived from it. The budget is kept below `max_tokens`.
+pub struct ReasoningHook {
+    options: ReasoningOptions,
+}
+
+impl ReasoningHook {
+    pub fn with_options(options: ReasoningOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn from_options(options: Option<&Value>) -> TransformerResult<Self> {
+        let options: ReasoningOptions = parse_options("reasoning", options)?;
+        if let Some(effort) = &options.effort
+            && Self::budget_for_effort(effort).is_none()
+        {
+            return Err(TransformerError::Configuration(format!(
+                "'reasoning' effort must be low, medium or high, got '{}'",
+                effort
+            )));
+        }
+        Ok(Self::with_options(options))
+    }
+
+    pub fn effort_for_budget(budget_tokens: u32) -> &'static str {
+        match budget_tokens {
+            0..4096 => "low",
+            4096..16384 => "medium",
+            _ => "high",
+        }
+    }
+
+    pub fn budget_for_effort(effort: &str) -> Option<u32> {
+        match effort {
+            "low" => Some(2048),
+            "medium" => Some(8192),
+            "high" => Some(24576),
+            _ => None,
+        }
+    }
+}
+
+impl TransformerHook for ReasoningHook {
+    fn name(&self) -> &str {
+        "reasoning"
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        if self.options.enabled == Some(false) {
+            request.reasoning = None;
+            return Ok(());
+        }
+
+        let forced = self.options.effort.is_some() || self.options.budget_tokens.is_some();
+        if request.reasoning.is_none() && (forced || self.options.enabled == Some(true)) {
+            request.reasoning = Some(ReasoningConfig {
+                enabled: true,
+                ..Default::default()
+            });
+        }
+        let Some(reasoning) = request.reasoning.as_mut().filter(|reasoning| reasoning.enabled) else {
+            return Ok(());
+        };
+
+        if let Some(effort) = &self.options.effort {
+            reasoning.effort = Some(effort.clone());
+        }
+        if let Some(budget_tokens) = self.options.budget_tokens {
+            reasoning.budget_tokens = Some(budget_tokens);
+        }
+
+        match (reasoning.budget_tokens, reasoning.effort.as_deref()) {
+            (Some(budget_tokens), None) => reasoning.effort = Some(Self::effort_for_budget(budget_tokens).to_string()),
+            (None, Some(effort)) => reasoning.budget_tokens = Self::budget_for_effort(effort),
+            (None, None) => {
+                reasoning.effort = Some("medium".to_string());
+                reasoning.budget_tokens = Self::budget_for_effort("medium");
+            }
+            (Some(_), Some(_)) => {}
+        }
+
+        // Anthropic rejects a budget that is not below `max_tokens`, and falls back to a small
+        // `max_tokens` when none is given
+        if let Some(budget_tokens) = reasoning.budget_tokens {
+            match request.max_tokens {
+                Some(max_tokens) if budget_tokens >= max_tokens => reasoning.budget_tokens = Some(max_tokens / 2),
+                Some(_) => {}
+                None => request.max_tokens = Some(budget_tokens + REASONING_ANSWER_TOKENS),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `stripthinking`: removes thinking parts and inline `<think>...</think>` spans, both from
+/// the history sent upstream and from the model's output.
+pub struct StripThinkingHook {
+    stream: Mutex<ThinkTagState>,
+}
+
+/// Stream state: whether we are inside a `<think>` span, plus a trailing fragment that may
+/// be the start of a tag completed by the next chunk.
+#[derive(Default)]
+struct ThinkTagState {
+    in_think: bool,
+    pending: String,
+}
+
+impl StripThinkingHook {
+    pub fn new() -> Self {
+        Self { stream: Mutex::new(ThinkTagState::default()) }
+    }
+
+    fn is_thinking_part(part: &MessagePart) -> bool {
+        part.part_type == "thinking" || part.part_type == "redacted_thinking"
+    }
+
+    fn strip_content(content: &mut MessageContent) {
+        match content {
+            MessageContent::Text(text) => *text = Self::strip_think_tags(text),
+            MessageContent::Parts(parts) => {
+                parts.retain(|part| !Self::is_thinking_part(part));
+                for part in parts {
+                    if let Some(text) = &part.text {
+                        part.text = Some(Self::strip_think_tags(text));
+                    }
+                }
+            }
+        }
+    }
+
+    fn strip_think_tags(text: &str) -> String {
+        let mut state = ThinkTagState::default();
+        let mut stripped = Self::strip_streaming(text, &mut state);
+        if !state.in_think {
+            stripped.push_str(&state.pending);
+        }
+        if stripped.len() == text.len() {
+            stripped
+        } else {
+            stripped.trim_start().to_string()
+        }
+    }
+
+    /// Strips `<think>` spans from one piece of text. The state carries over between calls
+    /// so spans, and the tags themselves, may be split across stream chunks.
+    fn strip_streaming(text: &str, state: &mut ThinkTagState) -> String {
+        let text = std::mem::take(&mut state.pending) + text;
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text.as_str();
+        loop {
+            let tag = if state.in_think { THINK_CLOSE } else { THINK_OPEN };
+            match rest.find(tag) {
+                Some(index) => {
+                    if !state.in_think {
+                        output.push_str(&rest[..index]);
+                    }
+                    rest = &rest[index + tag.len()..];
+                    state.in_think = !state.in_think;
+                }
+                None => {
+                    // Hold back a suffix that could be the start of the next tag
+                    let held = (1..tag.len().min(rest.len() + 1))
+                        .rev()
+                        .find(|&len| {
+                            let start = rest.len() - len;
+                            rest.is_char_boundary(start) && tag.starts_with(&rest[start..])
+                        })
+                        .unwrap_or(0);
+                    let (emit, pending) = rest.split_at(rest.len() - held);
+                    if !state.in_think {
+                        output.push_str(emit);
+                    }
+                    state.pending = pending.to_string();
+                    return output;
+                }
+            }
+        }
+    }
+}
+
+impl Default for StripThinkingHook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransformerHook for StripThinkingHook {
+    fn name(&self) -> &str {
+        "stripthinking"
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        for message in request.messages.iter_mut().filter(|message| message.role == "assistant") {
+            Self::strip_content(&mut message.content);
+        }
+        Ok(())
+    }
+
+    fn transform_response(&self, response: &mut ChatResponse) -> TransformerResult<()> {
+        for choice in &mut response.choices {
+            Self::strip_content(&mut choice.message.content);
+        }
+        Ok(())
+    }
+
+    fn transform_stream_chunk(&self, chunk: &mut ChatStreamChunk) -> TransformerResult<()> {
+        let mut state = self
+            .stream
+            .lock()
+            .map_err(|_| TransformerError::ProviderError("stripthinking state poisoned".to_string()))?;
+        for choice in &mut chunk.choices {
+            let mut content = match &choice.delta.content {
+                Some(content) => Self::strip_streaming(content, &mut state),
+                None => String::new(),
+            };
+            // A held-back fragment that never became a tag is ordinary text
+            if choice.finish_reason.is_some() && !state.in_think {
+                content.push_str(&std::mem::take(&mut state.pending));
+            }
+            if choice.delta.content.is_some() || !content.is_empty() {
+                choice.delta.content = Some(content);
+            }
+        }
+        Ok(())
+    }
+}
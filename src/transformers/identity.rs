@@ -0,0 +1,124 @@
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::config::types::ResponseModel;
+use crate::transformers::providers::provider_trait::*;
+
+/// Response header naming the provider that actually served the request.
+pub const PROVIDER_HEADER: &str = "x-ccr-provider";
+/// Response header naming the upstream model that actually served the request.
+pub const MODEL_HEADER: &str = "x-ccr-model";
+
+const MESSAGE_ID_PREFIX: &str = "msg_";
+const SERVER_TOOL_USE_ID_PREFIX: &str = "srvtoolu_";
+const TOOL_CALL_ID_PREFIX: &str = "call_";
+const MESSAGE_ID_LEN: usize = 24;
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+fn base62_id(prefix: &str, seed: &[u8]) -> String {
+    let digest = Sha256::digest(seed);
+    let id: String = digest
+        .iter()
+        .take(MESSAGE_ID_LEN)
+        .map(|byte| BASE62[*byte as usize % BASE62.len()] as char)
+        .collect();
+    format!("{}{}", prefix, id)
+}
+
+/// A fresh `msg_` id in the shape Anthropic clients expect.
+pub fn generate_message_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    base62_id(MESSAGE_ID_PREFIX, format!("{}:{}:{}", nanos, std::process::id(), count).as_bytes())
+}
+
+/// Maps an upstream id (`chatcmpl-...`, `gen-...`) to a stable `msg_` id, so every chunk of
+/// a stream maps to the same id. `msg_` ids are kept as they are.
+pub fn message_id(upstream_id: &str) -> String {
+    if upstream_id.starts_with(MESSAGE_ID_PREFIX) {
+        upstream_id.to_string()
+    } else {
+        base62_id(MESSAGE_ID_PREFIX, upstream_id.as_bytes())
+    }
+}
+
+/// A stable `srvtoolu_` id for a server tool call synthesized into the response `response_id`.
+pub fn server_tool_use_id(response_id: &str, index: usize) -> String {
+    base62_id(SERVER_TOOL_USE_ID_PREFIX, format!("{}:{}", response_id, index).as_bytes())
+}
+
+/// A stable `call_` id for a tool call from a provider that does not assign ids, derived from
+/// what identifies the call within `seed` (a response timestamp or a message position).
+pub fn tool_call_id(seed: &str, index: usize, name: &str) -> String {
+    base62_id(TOOL_CALL_ID_PREFIX, format!("{}:{}:{}", seed, index, name).as_bytes())
+}
+
+/// What one response tells the client about itself: a fresh message id, the model name
+/// chosen by the `RESPONSE_MODEL` policy, and the real provider/model for the headers.
+///
+/// Built once per request and attached to its chain, so a stream's chunks all carry the
+/// same id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseIdentity {
+    id: String,
+    model: String,
+    provider: String,
+    upstream_model: String,
+}
+
+impl ResponseIdentity {
+    pub fn new(requested_model: &str, provider: &str, upstream_model: &str, policy: ResponseModel) -> Self {
+        let model = match policy {
+            ResponseModel::Requested if !requested_model.is_empty() => requested_model,
+            _ => upstream_model,
+        };
+        Self {
+            id: generate_message_id(),
+            model: model.to_string(),
+            provider: provider.to_string(),
+            upstream_model: upstream_model.to_string(),
+        }
+    }
+
+    /// Identity for a `"provider,model"` route, as returned by the router.
+    pub fn for_route(requested_model: &str, route: &str, policy: ResponseModel) -> Self {
+        let (provider, model) = route.split_once(',').unwrap_or(("", route));
+        Self::new(requested_model, provider, model, policy)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The model name reported in response bodies.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub fn upstream_model(&self) -> &str {
+        &self.upstream_model
+    }
+
+    pub fn apply_to_response(&self, response: &mut ChatResponse) {
+        response.id = self.id.clone();
+        response.model = self.model.clone();
+    }
+
+    pub fn apply_to_stream_chunk(&self, chunk: &mut ChatStreamChunk) {
+        chunk.id = self.id.clone();
+        chunk.model = self.model.clone();
+    }
+
+    /// `x-ccr-provider` / `x-ccr-model` headers, identical for JSON and SSE responses.
+    pub fn headers(&self) -> [(&'static str, &str); 2] {
+        [(PROVIDER_HEADER, &self.provider), (MODEL_HEADER, &self.upstream_model)]
+    }
+}
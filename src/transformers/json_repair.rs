@@ -0,0 +1,459 @@
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::transformers::providers::provider_trait::*;
+use crate::transformers::tool_names::ToolNameMap;
+
+/// Parses JSON that a model got slightly wrong: markdown fences, surrounding prose,
+/// single-quoted strings, unquoted keys, Python literals, comments, trailing or missing
+/// commas, and output cut off mid-string or mid-object. Returns `None` when nothing
+/// JSON-like can be recovered.
+pub fn repair_json(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+
+    let text = strip_code_fence(text);
+    let start = text.find(['{', '['])?;
+    let repaired = Repairer::new(&text[start..]).run()?;
+    serde_json::from_str(&repaired).ok()
+}
+
+/// The body of the first fenced code block in `text`, or `text` itself when there is none.
+fn strip_code_fence(text: &str) -> &str {
+    let Some(start) = text.find("```") else {
+        return text;
+    };
+    let body = text[start + 3..].trim_start_matches(|c: char| c.is_ascii_alphanumeric());
+    match body.find("```") {
+        Some(end) => &body[..end],
+        None => body,
+    }
+}
+
+/// What the repairer emitted last, which decides the separators the next token needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Emitted {
+    Nothing,
+    Open,
+    Key,
+    Colon,
+    Comma,
+    Value,
+}
+
+/// Single pass rewrite into strict JSON. Whitespace outside strings is dropped.
+struct Repairer<'a> {
+    chars: Peekable<Chars<'a>>,
+    out: String,
+    /// Closing brackets of the open containers, innermost last
+    closers: Vec<char>,
+    last: Emitted,
+}
+
+impl<'a> Repairer<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+            out: String::with_capacity(text.len()),
+            closers: Vec::new(),
+            last: Emitted::Nothing,
+        }
+    }
+
+    fn run(mut self) -> Option<String> {
+        while let Some(&c) = self.chars.peek() {
+            // Anything after the first complete top-level value is prose
+            if self.closers.is_empty() && self.last == Emitted::Value {
+                break;
+            }
+            match c {
+                '{' | '[' => {
+                    self.chars.next();
+                    self.before_value();
+                    self.out.push(c);
+                    self.closers.push(if c == '{' { '}' } else { ']' });
+                    self.last = Emitted::Open;
+                }
+                '}' | ']' => {
+                    self.chars.next();
+                    if self.closers.contains(&c) {
+                        while let Some(closer) = self.closers.last().copied() {
+                            self.close(closer);
+                            if closer == c {
+                                break;
+                            }
+                        }
+                    }
+                }
+                ',' => {
+                    self.chars.next();
+                    if self.last == Emitted::Value {
+                        self.out.push(',');
+                        self.last = Emitted::Comma;
+                    }
+                }
+                ':' => {
+                    self.chars.next();
+                    if self.last == Emitted::Key {
+                        self.out.push(':');
+                        self.last = Emitted::Colon;
+                    }
+                }
+                '"' | '\'' => {
+                    self.chars.next();
+                    let text = self.read_string(c);
+                    self.emit_string(&text);
+                }
+                '/' => {
+                    self.chars.next();
+                    self.skip_comment();
+                }
+                c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                    let token = self.read_while(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+                    self.emit_number(&token);
+                }
+                c if c.is_alphabetic() || c == '_' || c == '$' => {
+                    let token = self.read_while(|c| c.is_alphanumeric() || "_$-.".contains(c));
+                    self.emit_bareword(&token);
+                }
+                _ => {
+                    self.chars.next();
+                }
+            }
+        }
+
+        while let Some(closer) = self.closers.last().copied() {
+            self.close(closer);
+        }
+        (self.last == Emitted::Value).then_some(self.out)
+    }
+
+    fn in_object(&self) -> bool {
+        self.closers.last() == Some(&'}')
+    }
+
+    fn at_key(&self) -> bool {
+        self.in_object() && matches!(self.last, Emitted::Open | Emitted::Comma | Emitted::Value)
+    }
+
+    /// Inserts the comma or colon missing before the next token; returns whether the
+    /// token is in key position.
+    fn before_value(&mut self) -> bool {
+        if self.in_object() {
+            match self.last {
+                Emitted::Open | Emitted::Comma => return true,
+                Emitted::Value => {
+                    self.out.push(',');
+                    return true;
+                }
+                Emitted::Key => self.out.push(':'),
+                Emitted::Nothing | Emitted::Colon => {}
+            }
+        } else if self.last == Emitted::Value && !self.closers.is_empty() {
+            self.out.push(',');
+        }
+        false
+    }
+
+    fn close(&mut self, closer: char) {
+        match self.last {
+            Emitted::Comma => {
+                self.out.pop();
+            }
+            Emitted::Key => self.out.push_str(":null"),
+            Emitted::Colon => self.out.push_str("null"),
+            _ => {}
+        }
+        self.out.push(closer);
+        self.closers.pop();
+        self.last = Emitted::Value;
+    }
+
+    fn emit_string(&mut self, text: &str) {
+        let is_key = self.before_value();
+        self.out.push_str(&Value::String(text.to_string()).to_string());
+        self.last = if is_key { Emitted::Key } else { Emitted::Value };
+    }
+
+    fn emit_number(&mut self, token: &str) {
+        let mut number = token.trim_start_matches('+').to_string();
+        if number.starts_with('.') {
+            number.insert(0, '0');
+        } else if number.starts_with("-.") {
+            number.insert(1, '0');
+        }
+        if number.ends_with('.') {
+            number.push('0');
+        }
+
+        if self.at_key() || serde_json::from_str::<serde_json::Number>(&number).is_err() {
+            self.emit_string(token);
+        } else {
+            self.before_value();
+            self.out.push_str(&number);
+            self.last = Emitted::Value;
+        }
+    }
+
+    fn emit_bareword(&mut self, token: &str) {
+        let literal = match token {
+            "true" | "True" | "TRUE" => Some("true"),
+            "false" | "False" | "FALSE" => Some("false"),
+            "null" | "None" | "NULL" | "undefined" | "NaN" | "Infinity" => Some("null"),
+            _ => None,
+        };
+        match literal {
+            Some(literal) if !self.at_key() => {
+                self.before_value();
+                self.out.push_str(literal);
+                self.last = Emitted::Value;
+            }
+            _ => self.emit_string(token),
+        }
+    }
+
+    /// Reads a string body up to the closing `quote` (or the end of input), re-escaping it
+    /// for JSON: raw control characters are escaped and invalid escapes are kept literally.
+    fn read_string(&mut self, quote: char) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                c if c == quote => break,
+                '\\' => match self.chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next_if(char::is_ascii_hexdigit)).collect();
+                        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            Some(decoded) if hex.len() == 4 => text.push(decoded),
+                            _ => {
+                                text.push_str("\\u");
+                                text.push_str(&hex);
+                            }
+                        }
+                    }
+                    Some(escaped @ ('"' | '\'' | '\\' | '/')) => text.push(escaped),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                    None => text.push('\\'),
+                },
+                c => text.push(c),
+            }
+        }
+        text
+    }
+
+    fn read_while(&mut self, accept: impl Fn(char) -> bool) -> String {
+        let mut token = String::new();
+        while let Some(c) = self.chars.next_if(|c| accept(*c)) {
+            token.push(c);
+        }
+        token
+    }
+
+    fn skip_comment(&mut self) {
+        match self.chars.peek() {
+            Some('/') => while self.chars.next_if(|c| *c != '\n').is_some() {},
+            Some('*') => {
+                self.chars.next();
+                let mut previous = '\0';
+                for c in self.chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether `value` satisfies the parts of a JSON schema that matter for tool input:
+/// `type`, `enum`, `required`, `properties` and `items`, checked recursively.
+pub fn matches_schema(value: &Value, schema: &Value) -> bool {
+    let type_ok = match schema.get("type") {
+        Some(Value::String(ty)) => matches_type(value, ty),
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).any(|ty| matches_type(value, ty)),
+        _ => true,
+    };
+    if !type_ok {
+        return false;
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        return false;
+    }
+
+    match value {
+        Value::Object(map) => {
+            let required = schema.get("required").and_then(Value::as_array).into_iter().flatten();
+            let properties = schema.get("properties").and_then(Value::as_object);
+            required.filter_map(Value::as_str).all(|key| map.contains_key(key))
+                && map.iter().all(|(key, item)| {
+                    properties
+                        .and_then(|properties| properties.get(key))
+                        .is_none_or(|property| matches_schema(item, property))
+                })
+        }
+        Value::Array(items) => schema
+            .get("items")
+            .is_none_or(|item_schema| items.iter().all(|item| matches_schema(item, item_schema))),
+        _ => true,
+    }
+}
+
+fn matches_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Tool input for arguments that could not be repaired into a valid object: a tool whose
+/// schema has a single string parameter gets the raw text as that parameter.
+fn input_from_schema(arguments: &str, repaired: Option<&Value>, schema: &Value) -> Option<Value> {
+    let properties = schema.get("properties")?.as_object()?;
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let key = match (required.as_slice(), properties.len()) {
+        ([key], _) => *key,
+        ([], 1) => properties.keys().next()?.as_str(),
+        _ => return None,
+    };
+
+    let text = match repaired {
+        Some(Value::String(text)) => text.clone(),
+        _ => strip_code_fence(arguments.trim()).trim().to_string(),
+    };
+    let mut input = Map::new();
+    input.insert(key.to_string(), Value::String(text));
+    let input = Value::Object(input);
+    matches_schema(&input, schema).then_some(input)
+}
+
+/// Turns a tool call's `arguments` string into a tool input object. Valid JSON objects are
+/// returned as is and anything else is repaired. When repair does not yield an object, the
+/// tool's `schema` decides whether the raw text can stand in for its single parameter. The
+/// last resort is an empty object, so the client reports a validation error for the tool
+/// instead of failing the whole response.
+pub fn parse_tool_arguments(arguments: &str, schema: Option<&Value>) -> Value {
+    let repaired = repair_json(arguments);
+    if let Some(value @ Value::Object(_)) = repaired {
+        return value;
+    }
+
+    schema
+        .and_then(|schema| input_from_schema(arguments, repaired.as_ref(), schema))
+        .unwrap_or_else(|| json!({}))
+}
+
+/// Rewrites tool-call arguments in a response that are not a valid JSON object, using the
+/// client's tool schemas. Valid arguments are left byte for byte.
+pub fn repair_response_tool_calls(response: &mut ChatResponse, tool_names: &ToolNameMap) {
+    for choice in &mut response.choices {
+        for call in choice.tool_calls.iter_mut().flatten() {
+            if !is_json_object(&call.function.arguments) {
+                let schema = tool_names.schema(&call.function.name);
+                call.function.arguments = parse_tool_arguments(&call.function.arguments, schema).to_string();
+            }
+        }
+    }
+}
+
+fn is_json_object(text: &str) -> bool {
+    matches!(serde_json::from_str(text), Ok(Value::Object(_)))
+}
+
+#[derive(Debug, Default)]
+struct PendingToolCall {
+    index: u32,
+    name: Option<String>,
+    arguments: String,
+}
+
+/// Buffers streamed tool-call argument fragments so they can be repaired as a whole.
+///
+/// Argument deltas are held back; the complete (repaired) arguments of a call are emitted
+/// in one delta on the chunk where the next call starts or the choice finishes. Ids and
+/// names still pass through as they arrive. One accumulator serves one response stream.
+#[derive(Debug, Default)]
+pub struct ToolCallAccumulator {
+    /// Call currently receiving fragments, per choice index
+    pending: BTreeMap<u32, PendingToolCall>,
+}
+
+impl ToolCallAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn process_chunk(&mut self, chunk: &mut ChatStreamChunk, tool_names: &ToolNameMap) {
+        for choice in &mut chunk.choices {
+            let mut completed = Vec::new();
+            let mut passthrough = Vec::new();
+
+            for mut call in choice.delta.tool_calls.take().into_iter().flatten() {
+                if self.pending.get(&choice.index).is_some_and(|pending| pending.index != call.index) {
+                    completed.extend(self.pending.remove(&choice.index));
+                }
+                let pending = self.pending.entry(choice.index).or_insert_with(|| PendingToolCall {
+                    index: call.index,
+                    ..PendingToolCall::default()
+                });
+                if let Some(function) = &mut call.function {
+                    if pending.name.is_none() {
+                        pending.name = function.name.clone();
+                    }
+                    pending.arguments.push_str(&function.arguments.take().unwrap_or_default());
+                }
+
+                let is_empty = call.id.is_none()
+                    && call.function.as_ref().is_none_or(|function| function.name.is_none());
+                if !is_empty {
+                    passthrough.push(call);
+                }
+            }
+            if choice.finish_reason.is_some() {
+                completed.extend(self.pending.remove(&choice.index));
+            }
+
+            let mut calls: Vec<StreamToolCall> = completed
+                .into_iter()
+                .map(|pending| {
+                    let schema = pending.name.as_deref().and_then(|name| tool_names.schema(name));
+                    StreamToolCall {
+                        index: pending.index,
+                        id: None,
+                        tool_type: None,
+                        function: Some(StreamFunctionCall {
+                            name: None,
+                            arguments: Some(parse_tool_arguments(&pending.arguments, schema).to_string()),
+                        }),
+                    }
+                })
+                .collect();
+            calls.extend(passthrough);
+            choice.delta.tool_calls = (!calls.is_empty()).then_some(calls);
+        }
+    }
+}
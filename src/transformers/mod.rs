@@ -2,6 +2,16 @@ pub mod transformer_manager;
 pub mod types;
 pub mod providers;
 pub mod error;
+pub mod stream;
+pub mod upstream;
+pub mod auth;
+pub mod tool_names;
+pub mod chain;
+pub mod hooks;
+pub mod script;
+pub mod json_repair;
+pub mod structured_output;
+pub mod identity;
 
 pub use transformer_manager::TransformerManager;
-pub use error::{TransformerError, TransformerResult};
\ No newline at end of file
+pub use error::{GatewayError, GatewayErrorKind, TransformerError, TransformerResult};
\ No newline at end of file
@@ -1,16 +1,42 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::config::types::Provider;
 use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::identity::{message_id, server_tool_use_id};
+use crate::transformers::json_repair::parse_tool_arguments;
 use crate::transformers::providers::provider_trait::*;
+use crate::transformers::upstream::UpstreamRequest;
+
+/// Sent as `anthropic-version` on every upstream request
+const API_VERSION: &str = "2023-06-01";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AnthropicRequest {
     model: String,
     max_tokens: u32,
     messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
-    tools: Option<Vec<AnthropicTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<AnthropicToolDefinition>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<AnthropicToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<AnthropicThinking>,
+    /// Fields not modeled above (`system`, `metadata`, `service_tier`, `mcp_servers`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AnthropicThinking {
+    #[serde(rename = "type")]
+    thinking_type: String,
+    budget_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,20 +49,65 @@ struct AnthropicMessage {
 #[serde(tag = "type")]
 enum AnthropicContent {
     #[serde(rename = "text")]
-    Text { text: String },
+    Text {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<serde_json::Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        citations: Option<Vec<serde_json::Value>>,
+    },
     #[serde(rename = "tool_use")]
     ToolUse {
         id: String,
         name: String,
         input: serde_json::Value,
     },
+    #[serde(rename = "server_tool_use")]
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// `content` is a list of `web_search_result` blocks, or an error object
+    #[serde(rename = "web_search_tool_result")]
+    WebSearchToolResult {
+        tool_use_id: String,
+        content: serde_json::Value,
+    },
+    #[serde(rename = "document")]
+    Document {
+        source: DocumentSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        context: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        citations: Option<AnthropicCitationsConfig>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<serde_json::Value>,
+    },
     #[serde(rename = "tool_result")]
     ToolResult {
         tool_use_id: String,
         content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<serde_json::Value>,
     },
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AnthropicCitationsConfig {
+    enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum AnthropicToolDefinition {
+    Custom(AnthropicTool),
+    /// Tools Anthropic runs itself (`web_search_20250305`, ...), identified by `type`
+    Server(AnthropicServerTool),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AnthropicTool {
     name: String,
@@ -44,12 +115,33 @@ struct AnthropicTool {
     input_schema: serde_json::Value,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AnthropicServerTool {
+    #[serde(rename = "type")]
+    tool_type: String,
+    name: String,
+    #[serde(flatten)]
+    config: serde_json::Map<String, serde_json::Value>,
+}
+
+const WEB_SEARCH_TOOL: &str = "web_search";
+const WEB_SEARCH_TOOL_TYPE: &str = "web_search_20250305";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AnthropicToolChoice {
     #[serde(rename = "type")]
     choice_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    disable_parallel_tool_use: Option<bool>,
 }
 
+/// The tool forced to emulate structured output. Its input is the answer, so when the
+/// request set `response_format` a call to it is unwrapped into the JSON text; see
+/// [`StructuredOutput`](crate::transformers::structured_output::StructuredOutput).
+pub const STRUCTURED_OUTPUT_TOOL: &str = "structured_output";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AnthropicResponse {
     id: String,
@@ -60,29 +152,262 @@ struct AnthropicResponse {
     model: String,
     stop_reason: Option<String>,
     usage: AnthropicUsage,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// `input_tokens` excludes the cached prompt tokens counted in the cache fields.
 struct AnthropicUsage {
+    /// Absent from the `message_delta` usage of older API versions
+    #[serde(default)]
     input_tokens: u32,
     output_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_creation_input_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_read_input_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AnthropicStreamChunk {
     #[serde(rename = "type")]
     chunk_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     delta: Option<AnthropicStreamDelta>,
+    /// The message skeleton carried by `message_start`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<serde_json::Value>,
+    /// The block opened by `content_block_start`; kept raw, as thinking blocks have no
+    /// `AnthropicContent` variant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_block: Option<serde_json::Value>,
+    /// The final counts carried by `message_delta`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage: Option<AnthropicUsage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
+#[serde(untagged)]
 enum AnthropicStreamDelta {
+    Content(AnthropicContentDelta),
+    /// The `message_delta` payload, which has no `type`
+    MessageDelta { stop_reason: Option<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum AnthropicContentDelta {
     #[serde(rename = "text_delta")]
     TextDelta { text: String },
-    #[serde(rename = "tool_use_delta")]
+    #[serde(rename = "input_json_delta", alias = "tool_use_delta")]
     ToolUseDelta { partial_json: String },
+    /// Thinking, signature and citation deltas, which other formats do not carry
+    #[serde(other)]
+    Other,
+}
+
+impl AnthropicStreamChunk {
+    fn event(chunk_type: &str) -> Self {
+        Self {
+            chunk_type: chunk_type.to_string(),
+            index: None,
+            delta: None,
+            message: None,
+            content_block: None,
+            usage: None,
+        }
+    }
+
+    fn message_start(chunk: &ChatStreamChunk, usage: Option<AnthropicUsage>) -> Self {
+        Self {
+            message: Some(serde_json::json!({
+                "id": message_id(&chunk.id),
+                "type": "message",
+                "role": "assistant",
+                "content": [],
+                "model": chunk.model,
+                "stop_reason": null,
+                "stop_sequence": null,
+                "usage": usage.unwrap_or_default()
+            })),
+            ..Self::event("message_start")
+        }
+    }
+
+    fn block_start(index: u32, content_block: serde_json::Value) -> Self {
+        Self {
+            index: Some(index),
+            content_block: Some(content_block),
+            ..Self::event("content_block_start")
+        }
+    }
+
+    fn block_delta(index: u32, delta: AnthropicContentDelta) -> Self {
+        Self {
+            index: Some(index),
+            delta: Some(AnthropicStreamDelta::Content(delta)),
+            ..Self::event("content_block_delta")
+        }
+    }
+
+    fn block_stop(index: u32) -> Self {
+        Self {
+            index: Some(index),
+            ..Self::event("content_block_stop")
+        }
+    }
+
+    fn message_delta(stop_reason: Option<String>, usage: Option<AnthropicUsage>) -> Self {
+        Self {
+            delta: Some(AnthropicStreamDelta::MessageDelta { stop_reason }),
+            usage,
+            ..Self::event("message_delta")
+        }
+    }
+}
+
+fn tool_use_block(call: &StreamToolCall) -> serde_json::Value {
+    let name = call.function.as_ref().and_then(|function| function.name.as_deref());
+    serde_json::json!({
+        "type": "tool_use",
+        "id": call.id.as_deref().unwrap_or_default(),
+        "name": name.unwrap_or_default(),
+        "input": {}
+    })
+}
+
+/// The content block an `AnthropicStreamEncoder` has open
+#[derive(Debug, Clone, Copy)]
+struct OpenBlock {
+    index: u32,
+    text: bool,
+}
+
+/// Encodes one client stream as a well-formed Anthropic event sequence: one message_start,
+/// each text or tool_use block opened with content_block_start and closed with
+/// content_block_stop before the next one, and tool calls numbered after the text block.
+///
+/// A finish without usage is held back: OpenAI sends its usage on a later chunk without
+/// choices, and Anthropic clients expect it on the one message_delta before message_stop.
+#[derive(Debug, Default)]
+pub struct AnthropicStreamEncoder {
+    started: bool,
+    finished: bool,
+    /// Set once the upstream finished, while waiting for its usage
+    stop_pending: bool,
+    stop_reason: Option<String>,
+    open: Option<OpenBlock>,
+    next_index: u32,
+    /// Block index of each tool call, by the call's index in the universal stream
+    tool_blocks: HashMap<u32, u32>,
+}
+
+impl AnthropicStreamEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn start_block(&mut self, events: &mut Vec<AnthropicStreamChunk>, text: bool, content_block: serde_json::Value) -> u32 {
+        self.stop_block(events);
+        let index = self.next_index;
+        self.next_index += 1;
+        self.open = Some(OpenBlock { index, text });
+        events.push(AnthropicStreamChunk::block_start(index, content_block));
+        index
+    }
+
+    fn stop_block(&mut self, events: &mut Vec<AnthropicStreamChunk>) {
+        if let Some(open) = self.open.take() {
+            events.push(AnthropicStreamChunk::block_stop(open.index));
+        }
+    }
+
+    fn finish_message(&mut self, events: &mut Vec<AnthropicStreamChunk>, stop_reason: Option<String>, usage: Option<AnthropicUsage>) {
+        self.stop_block(events);
+        events.push(AnthropicStreamChunk::message_delta(stop_reason, Some(usage.unwrap_or_default())));
+        events.push(AnthropicStreamChunk::event("message_stop"));
+        self.finished = true;
+    }
+
+    fn encode_events(&mut self, chunk: &ChatStreamChunk) -> Vec<AnthropicStreamChunk> {
+        let usage = chunk.usage.as_ref().map(AnthropicTransformer::convert_usage_from_universal);
+        let mut events = Vec::new();
+        if self.finished {
+            return events;
+        }
+        if !self.started {
+            self.started = true;
+            events.push(AnthropicStreamChunk::message_start(chunk, usage.clone()));
+        }
+        let Some(choice) = chunk.choices.first() else {
+            if self.stop_pending {
+                let stop_reason = self.stop_reason.take();
+                self.finish_message(&mut events, stop_reason, usage);
+            } else if usage.is_some() {
+                events.push(AnthropicStreamChunk::message_delta(None, usage));
+            }
+            return events;
+        };
+
+        if let Some(text) = choice.delta.content.as_deref().filter(|text| !text.is_empty()) {
+            let index = match self.open {
+                Some(open) if open.text => open.index,
+                _ => self.start_block(&mut events, true, serde_json::json!({"type": "text", "text": ""})),
+            };
+            events.push(AnthropicStreamChunk::block_delta(
+                index,
+                AnthropicContentDelta::TextDelta { text: text.to_string() },
+            ));
+        }
+
+        for call in choice.delta.tool_calls.iter().flatten() {
+            if call.id.is_some() || !self.tool_blocks.contains_key(&call.index) {
+                let index = self.start_block(&mut events, false, tool_use_block(call));
+                self.tool_blocks.insert(call.index, index);
+            }
+            if let Some(arguments) = call.function.as_ref().and_then(|function| function.arguments.as_ref())
+                && !arguments.is_empty()
+            {
+                events.push(AnthropicStreamChunk::block_delta(
+                    self.tool_blocks[&call.index],
+                    AnthropicContentDelta::ToolUseDelta { partial_json: arguments.clone() },
+                ));
+            }
+        }
+
+        // The finish comes after anything released on the same chunk
+        if let Some(finish_reason) = &choice.finish_reason {
+            let stop_reason = AnthropicTransformer::convert_stop_reason_from_universal(finish_reason);
+            if usage.is_some() {
+                self.finish_message(&mut events, stop_reason, usage);
+            } else {
+                self.stop_block(&mut events);
+                self.stop_pending = true;
+                self.stop_reason = stop_reason;
+            }
+        }
+        events
+    }
+}
+
+impl StreamEncoder for AnthropicStreamEncoder {
+    fn encode(&mut self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
+        let events = self.encode_events(chunk);
+        AnthropicTransformer::encode_stream_events(events)
+    }
+
+    /// Sends a finish still waiting for usage, or closes a stream that ended without one
+    fn finish(&mut self) -> TransformerResult<serde_json::Value> {
+        let mut events = Vec::new();
+        if self.started && !self.finished {
+            let stop_reason = self.stop_reason.take();
+            self.finish_message(&mut events, stop_reason, None);
+        }
+        serde_json::to_value(events).map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
 }
 
 pub struct AnthropicTransformer;
@@ -92,15 +417,63 @@ impl AnthropicTransformer {
         Self
     }
 
+    fn convert_usage_to_universal(usage: &AnthropicUsage) -> Usage {
+        let cached = usage.cache_read_input_tokens.unwrap_or(0) + usage.cache_creation_input_tokens.unwrap_or(0);
+        let prompt_tokens = usage.input_tokens + cached;
+        Usage {
+            prompt_tokens,
+            completion_tokens: usage.output_tokens,
+            total_tokens: prompt_tokens + usage.output_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens,
+            cache_creation_tokens: usage.cache_creation_input_tokens,
+            reasoning_tokens: None,
+        }
+    }
+
+    fn convert_stop_reason_from_universal(finish_reason: &str) -> Option<String> {
+        match finish_reason {
+            "tool_calls" => Some("tool_use".to_string()),
+            "stop" => Some("end_turn".to_string()),
+            "length" => Some("max_tokens".to_string()),
+            "content_filter" => Some("refusal".to_string()),
+            native @ ("end_turn" | "tool_use" | "max_tokens" | "stop_sequence" | "refusal" | "pause_turn") => {
+                Some(native.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// A single event is returned as is, several as an array
+    fn encode_stream_events(mut events: Vec<AnthropicStreamChunk>) -> TransformerResult<serde_json::Value> {
+        let value = if events.len() == 1 {
+            serde_json::to_value(events.remove(0))
+        } else {
+            serde_json::to_value(events)
+        };
+        value.map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    fn convert_usage_from_universal(usage: &Usage) -> AnthropicUsage {
+        let cached = usage.cache_read_tokens.unwrap_or(0) + usage.cache_creation_tokens.unwrap_or(0);
+        AnthropicUsage {
+            input_tokens: usage.prompt_tokens.saturating_sub(cached),
+            output_tokens: usage.completion_tokens,
+            cache_creation_input_tokens: usage.cache_creation_tokens,
+            cache_read_input_tokens: usage.cache_read_tokens,
+        }
+    }
+
     fn convert_message_to_universal(msg: &AnthropicMessage) -> TransformerResult<ChatMessage> {
         let parts: Vec<MessagePart> = msg.content.iter().map(|content| match content {
-            AnthropicContent::Text { text } => MessagePart {
+            AnthropicContent::Text { text, cache_control, .. } => MessagePart {
                 part_type: "text".to_string(),
                 text: Some(text.clone()),
                 tool_use_id: None,
                 tool_name: None,
                 tool_input: None,
                 image_url: None,
+                document: None,
+                cache_control: cache_control.clone(),
             },
             AnthropicContent::ToolUse { id, name, input } => MessagePart {
                 part_type: "tool_use".to_string(),
@@ -109,14 +482,54 @@ impl AnthropicTransformer {
                 tool_name: Some(name.clone()),
                 tool_input: Some(input.clone()),
                 image_url: None,
+                document: None,
+                cache_control: None,
+            },
+            AnthropicContent::Document { source, title, context, citations, cache_control } => MessagePart {
+                part_type: "document".to_string(),
+                text: None,
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: None,
+                document: Some(Document {
+                    source: source.clone(),
+                    title: title.clone(),
+                    context: context.clone(),
+                    citations: citations.as_ref().map(|citations| citations.enabled),
+                }),
+                cache_control: cache_control.clone(),
             },
-            AnthropicContent::ToolResult { tool_use_id, content } => MessagePart {
+            AnthropicContent::ToolResult { tool_use_id, content, cache_control } => MessagePart {
                 part_type: "tool_result".to_string(),
                 text: Some(content.clone()),
                 tool_use_id: Some(tool_use_id.clone()),
                 tool_name: None,
                 tool_input: None,
                 image_url: None,
+                document: None,
+                cache_control: cache_control.clone(),
+            },
+            // Only meaningful to Anthropic: other formats skip these parts
+            AnthropicContent::ServerToolUse { id, name, input } => MessagePart {
+                part_type: "server_tool_use".to_string(),
+                text: None,
+                tool_use_id: Some(id.clone()),
+                tool_name: Some(name.clone()),
+                tool_input: Some(input.clone()),
+                image_url: None,
+                document: None,
+                cache_control: None,
+            },
+            AnthropicContent::WebSearchToolResult { tool_use_id, content } => MessagePart {
+                part_type: "web_search_tool_result".to_string(),
+                text: None,
+                tool_use_id: Some(tool_use_id.clone()),
+                tool_name: None,
+                tool_input: Some(content.clone()),
+                image_url: None,
+                document: None,
+                cache_control: None,
             },
         }).collect();
 
@@ -130,13 +543,15 @@ impl AnthropicTransformer {
     fn convert_message_from_universal(msg: &ChatMessage) -> TransformerResult<AnthropicMessage> {
         let content = match &msg.content {
             MessageContent::Text(text) => {
-                vec![AnthropicContent::Text { text: text.clone() }]
+                vec![AnthropicContent::Text { text: text.clone(), cache_control: None, citations: None }]
             },
             MessageContent::Parts(parts) => {
                 parts.iter().map(|part| {
                     match part.part_type.as_str() {
-                        "text" => AnthropicContent::Text { 
-                            text: part.text.clone().unwrap_or_default() 
+                        "text" => AnthropicContent::Text {
+                            text: part.text.clone().unwrap_or_default(),
+                            cache_control: part.cache_control.clone(),
+                            citations: None,
                         },
                         "tool_use" => AnthropicContent::ToolUse {
                             id: part.tool_use_id.clone().unwrap_or_default(),
@@ -146,9 +561,31 @@ impl AnthropicTransformer {
                         "tool_result" => AnthropicContent::ToolResult {
                             tool_use_id: part.tool_use_id.clone().unwrap_or_default(),
                             content: part.text.clone().unwrap_or_default(),
+                            cache_control: part.cache_control.clone(),
+                        },
+                        "server_tool_use" => AnthropicContent::ServerToolUse {
+                            id: part.tool_use_id.clone().unwrap_or_default(),
+                            name: part.tool_name.clone().unwrap_or_default(),
+                            input: part.tool_input.clone().unwrap_or(serde_json::Value::Null),
                         },
-                        _ => AnthropicContent::Text { 
-                            text: part.text.clone().unwrap_or_default() 
+                        "web_search_tool_result" => AnthropicContent::WebSearchToolResult {
+                            tool_use_id: part.tool_use_id.clone().unwrap_or_default(),
+                            content: part.tool_input.clone().unwrap_or(serde_json::Value::Null),
+                        },
+                        "document" if part.document.is_some() => {
+                            let document = part.document.clone().unwrap();
+                            AnthropicContent::Document {
+                                source: document.source,
+                                title: document.title,
+                                context: document.context,
+                                citations: document.citations.map(|enabled| AnthropicCitationsConfig { enabled }),
+                                cache_control: part.cache_control.clone(),
+                            }
+                        },
+                        _ => AnthropicContent::Text {
+                            text: part.text.clone().unwrap_or_default(),
+                            cache_control: part.cache_control.clone(),
+                            citations: None,
                         },
                     }
                 }).collect()
@@ -181,31 +618,280 @@ impl AnthropicTransformer {
     }
 
     fn convert_tool_choice_to_universal(choice: &AnthropicToolChoice) -> TransformerResult<ToolChoice> {
-        match choice.choice_type.as_str() {
-            "auto" => Ok(ToolChoice::Auto("auto".to_string())),
-            "any" => Ok(ToolChoice::Required("any".to_string())),
-            _ => Ok(ToolChoice::Auto("auto".to_string())),
+        match (choice.choice_type.as_str(), &choice.name) {
+            ("tool", Some(name)) => Ok(ToolChoice::specific(name)),
+            ("tool", None) => Err(TransformerError::InvalidFormat(
+                "tool_choice of type 'tool' requires a name".to_string(),
+            )),
+            (mode, _) => Ok(ToolChoice::from_mode(mode)),
         }
     }
 
-    fn convert_tool_choice_from_universal(choice: &ToolChoice) -> TransformerResult<AnthropicToolChoice> {
-        match choice {
-            ToolChoice::Auto(_) => Ok(AnthropicToolChoice { choice_type: "auto".to_string() }),
-            ToolChoice::Required(_) => Ok(AnthropicToolChoice { choice_type: "any".to_string() }),
-            ToolChoice::None(_) => Ok(AnthropicToolChoice { choice_type: "auto".to_string() }),
-            ToolChoice::Specific(_) => Ok(AnthropicToolChoice { choice_type: "auto".to_string() }),
+    /// `parallel_tool_calls: false` has no field of its own in Anthropic: it rides on the
+    /// tool choice, which defaults to `auto` when the request has none.
+    fn convert_tool_choice_from_universal(
+        choice: Option<&ToolChoice>,
+        parallel_tool_calls: Option<bool>,
+    ) -> Option<AnthropicToolChoice> {
+        let disable_parallel_tool_use = parallel_tool_calls.map(|parallel| !parallel);
+        let (choice_type, name) = match choice {
+            Some(ToolChoice::Auto(_)) => ("auto", None),
+            Some(ToolChoice::Required(_)) => ("any", None),
+            Some(ToolChoice::None(_)) => ("none", None),
+            Some(ToolChoice::Specific(specific)) => ("tool", Some(specific.function.name.clone())),
+            None if disable_parallel_tool_use == Some(true) => ("auto", None),
+            None => return None,
+        };
+        Some(AnthropicToolChoice {
+            choice_type: choice_type.to_string(),
+            name,
+            disable_parallel_tool_use: disable_parallel_tool_use.filter(|disable| *disable),
+        })
+    }
+
+    fn convert_thinking_to_universal(thinking: &AnthropicThinking) -> ReasoningConfig {
+        ReasoningConfig {
+            enabled: thinking.thinking_type == "enabled",
+            budget_tokens: thinking.budget_tokens,
+            effort: None,
+        }
+    }
+
+    fn convert_thinking_from_universal(reasoning: &ReasoningConfig) -> AnthropicThinking {
+        if reasoning.enabled {
+            AnthropicThinking {
+                thinking_type: "enabled".to_string(),
+                budget_tokens: Some(reasoning.budget_tokens.unwrap_or(1024)),
+            }
+        } else {
+            AnthropicThinking {
+                thinking_type: "disabled".to_string(),
+                budget_tokens: None,
+            }
         }
     }
 
     fn extract_text_from_content(content: &Vec<AnthropicContent>) -> String {
         content.iter()
             .filter_map(|c| match c {
-                AnthropicContent::Text { text } => Some(text.clone()),
+                AnthropicContent::Text { text, .. } => Some(text.clone()),
                 _ => None,
             })
             .collect()
     }
 
+    /// Adds `tool_use` blocks for tool calls not already in the content. Arguments that are
+    /// not valid JSON are repaired, since `input` must be an object.
+    fn append_tool_calls(content: &mut Vec<AnthropicContent>, tool_calls: &[ToolCall]) {
+        if tool_calls.is_empty() {
+            return;
+        }
+        content.retain(|block| !matches!(block, AnthropicContent::Text { text, .. } if text.is_empty()));
+
+        for (index, call) in tool_calls.iter().enumerate() {
+            let id = call.id.clone().unwrap_or_else(|| format!("toolu_{}", index));
+            let present = content
+                .iter()
+                .any(|block| matches!(block, AnthropicContent::ToolUse { id: existing, .. } if *existing == id));
+            if !present {
+                content.push(AnthropicContent::ToolUse {
+                    id,
+                    name: call.function.name.clone(),
+                    input: parse_tool_arguments(&call.function.arguments, None),
+                });
+            }
+        }
+    }
+
+    fn structured_output_tool(format: &ResponseFormat) -> AnthropicTool {
+        let description = match format {
+            ResponseFormat::JsonSchema { name, description: Some(description), .. } => {
+                format!("Respond with the {} result: {}", name, description)
+            }
+            ResponseFormat::JsonSchema { name, .. } => format!("Respond with the {} result.", name),
+            ResponseFormat::JsonObject => "Respond with a JSON object.".to_string(),
+        };
+        AnthropicTool {
+            name: STRUCTURED_OUTPUT_TOOL.to_string(),
+            description,
+            input_schema: format.schema(),
+        }
+    }
+
+    fn convert_web_search_to_universal(tool: &AnthropicServerTool) -> TransformerResult<WebSearchOptions> {
+        serde_json::from_value(serde_json::Value::Object(tool.config.clone()))
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))
+    }
+
+    fn convert_web_search_from_universal(options: &WebSearchOptions) -> TransformerResult<AnthropicToolDefinition> {
+        let mut config = match serde_json::to_value(options) {
+            Ok(serde_json::Value::Object(config)) => config,
+            Ok(_) => serde_json::Map::new(),
+            Err(e) => return Err(TransformerError::Serialization(e.to_string())),
+        };
+        config.remove("search_context_size");
+        if let Some(location) = config.get_mut("user_location").and_then(|location| location.as_object_mut()) {
+            location.insert("type".to_string(), serde_json::json!("approximate"));
+        }
+        Ok(AnthropicToolDefinition::Server(AnthropicServerTool {
+            tool_type: WEB_SEARCH_TOOL_TYPE.to_string(),
+            name: WEB_SEARCH_TOOL.to_string(),
+            config,
+        }))
+    }
+
+    /// Collects the searches, results and text citations of a response. A citation covers
+    /// its whole text block; offsets count the characters of the text blocks before it.
+    fn extract_web_search(content: &[AnthropicContent]) -> Option<WebSearchResults> {
+        let mut web_search = WebSearchResults::default();
+        let mut offset = 0;
+        for block in content {
+            match block {
+                AnthropicContent::ServerToolUse { name, input, .. } if name == WEB_SEARCH_TOOL => {
+                    web_search.queries.extend(string_field(input, "query"));
+                }
+                AnthropicContent::WebSearchToolResult { content, .. } => {
+                    // An error result is an object rather than a list
+                    for result in content.as_array().into_iter().flatten() {
+                        if let Some(url) = string_field(result, "url") {
+                            web_search.sources.push(WebSource {
+                                url,
+                                title: string_field(result, "title"),
+                                page_age: string_field(result, "page_age"),
+                            });
+                        }
+                    }
+                }
+                AnthropicContent::Text { text, citations, .. } => {
+                    let end = offset + text.chars().count();
+                    for citation in citations.iter().flatten() {
+                        if citation["type"] != "web_search_result_location" {
+                            continue;
+                        }
+                        if let Some(url) = string_field(citation, "url") {
+                            web_search.citations.push(WebCitation {
+                                url,
+                                title: string_field(citation, "title"),
+                                start_index: offset,
+                                end_index: end,
+                                cited_text: string_field(citation, "cited_text"),
+                                encrypted_index: string_field(citation, "encrypted_index"),
+                            });
+                        }
+                    }
+                    offset = end;
+                }
+                _ => {}
+            }
+        }
+        web_search.non_empty()
+    }
+
+    /// Puts a response's web search into Anthropic's shape: text blocks are split at citation
+    /// boundaries so each span carries its citations, and unless the content already has
+    /// the search blocks (an Anthropic upstream), a `server_tool_use` /
+    /// `web_search_tool_result` pair goes before the first cited block.
+    fn attach_web_search(content: Vec<AnthropicContent>, web_search: &WebSearchResults, response_id: &str) -> Vec<AnthropicContent> {
+        let has_search_blocks = content
+            .iter()
+            .any(|block| matches!(block, AnthropicContent::ServerToolUse { .. }));
+        let mut search_blocks = if has_search_blocks {
+            Vec::new()
+        } else {
+            Self::web_search_blocks(web_search, response_id)
+        };
+
+        let mut output = Vec::with_capacity(content.len() + search_blocks.len());
+        let mut offset = 0;
+        for block in content {
+            let AnthropicContent::Text { text, cache_control, citations } = block else {
+                output.push(block);
+                continue;
+            };
+            let (start, end) = (offset, offset + text.chars().count());
+            offset = end;
+            let cited: Vec<&WebCitation> = web_search
+                .citations
+                .iter()
+                .filter(|citation| citation.start_index < end && citation.end_index > start)
+                .collect();
+            if cited.is_empty() {
+                output.push(AnthropicContent::Text { text, cache_control, citations });
+                continue;
+            }
+
+            output.append(&mut search_blocks);
+            let mut bounds: Vec<usize> = cited
+                .iter()
+                .flat_map(|citation| [citation.start_index, citation.end_index])
+                .filter(|index| *index > start && *index < end)
+                .chain([start, end])
+                .collect();
+            bounds.sort_unstable();
+            bounds.dedup();
+            let chars: Vec<char> = text.chars().collect();
+            for span in bounds.windows(2) {
+                let span_text: String = chars[span[0] - start..span[1] - start].iter().collect();
+                let span_citations: Vec<serde_json::Value> = cited
+                    .iter()
+                    .filter(|citation| citation.start_index <= span[0] && citation.end_index >= span[1])
+                    .map(|citation| Self::convert_citation_from_universal(citation, &span_text))
+                    .collect();
+                output.push(AnthropicContent::Text {
+                    text: span_text,
+                    cache_control: None,
+                    citations: if span_citations.is_empty() { None } else { Some(span_citations) },
+                });
+            }
+            // A cache breakpoint marks the end of the block, so it stays on the last span
+            if let Some(AnthropicContent::Text { cache_control: last, .. }) = output.last_mut() {
+                *last = cache_control;
+            }
+        }
+
+        // Nothing was cited: the search goes first
+        search_blocks.append(&mut output);
+        search_blocks
+    }
+
+    fn web_search_blocks(web_search: &WebSearchResults, response_id: &str) -> Vec<AnthropicContent> {
+        let id = server_tool_use_id(response_id, 0);
+        let results = web_search
+            .sources
+            .iter()
+            .map(|source| {
+                serde_json::json!({
+                    "type": "web_search_result",
+                    "url": source.url,
+                    "title": source.title.clone().unwrap_or_default(),
+                    "encrypted_content": "",
+                    "page_age": source.page_age,
+                })
+            })
+            .collect();
+        vec![
+            AnthropicContent::ServerToolUse {
+                id: id.clone(),
+                name: WEB_SEARCH_TOOL.to_string(),
+                input: serde_json::json!({"query": web_search.queries.join("; ")}),
+            },
+            AnthropicContent::WebSearchToolResult {
+                tool_use_id: id,
+                content: serde_json::Value::Array(results),
+            },
+        ]
+    }
+
+    fn convert_citation_from_universal(citation: &WebCitation, span_text: &str) -> serde_json::Value {
+        serde_json::json!({
+            "type": "web_search_result_location",
+            "url": citation.url,
+            "title": citation.title.clone().unwrap_or_default(),
+            "encrypted_index": citation.encrypted_index.clone().unwrap_or_default(),
+            "cited_text": citation.cited_text.as_deref().unwrap_or(span_text),
+        })
+    }
+
     fn extract_tool_calls_from_content(content: &Vec<AnthropicContent>) -> Vec<ToolCall> {
         content.iter()
             .filter_map(|c| match c {
@@ -225,11 +911,34 @@ impl AnthropicTransformer {
     }
 }
 
+fn string_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    value.get(key).and_then(|field| field.as_str()).map(str::to_string)
+}
+
 impl ProviderTransformer for AnthropicTransformer {
     fn provider_name(&self) -> &'static str {
         "anthropic"
     }
 
+    fn supports_documents(&self) -> bool {
+        true
+    }
+
+    fn passthrough_header_names(&self) -> &'static [&'static str] {
+        &["anthropic-beta"]
+    }
+
+    /// Anthropic authenticates with `x-api-key` rather than a bearer token
+    fn build_upstream_request(&self, provider: &Provider, request: &ChatRequest) -> TransformerResult<UpstreamRequest> {
+        let mut upstream = UpstreamRequest::post(&provider.api_base_url, self.from_universal_request(request)?);
+        upstream.set_header("x-api-key", provider.api_key.clone());
+        upstream.set_header("anthropic-version", API_VERSION);
+        for (name, value) in passthrough_headers(&request.provider_metadata, self.provider_name()) {
+            upstream.set_header(name, value);
+        }
+        Ok(upstream)
+    }
+
     fn to_universal_request(&self, request: &serde_json::Value) -> TransformerResult<ChatRequest> {
         let anthropic_request: AnthropicRequest = serde_json::from_value(request.clone())
             .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
@@ -240,30 +949,48 @@ impl ProviderTransformer for AnthropicTransformer {
             .map(|msg| Self::convert_message_to_universal(msg))
             .collect();
 
-        let tools = anthropic_request
-            .tools
-            .map(|tools| {
-                tools
-                    .iter()
-                    .map(|tool| Self::convert_tool_to_universal(tool))
-                    .collect::<TransformerResult<Vec<Tool>>>()
-            })
-            .transpose()?;
+        let mut tools = Vec::new();
+        let mut web_search = None;
+        for tool in anthropic_request.tools.iter().flatten() {
+            match tool {
+                AnthropicToolDefinition::Custom(tool) => tools.push(Self::convert_tool_to_universal(tool)?),
+                AnthropicToolDefinition::Server(tool) if tool.tool_type.starts_with(WEB_SEARCH_TOOL) => {
+                    web_search = Some(Self::convert_web_search_to_universal(tool)?);
+                }
+                // Other server tools (bash, text editor, ...) have no counterpart elsewhere
+                AnthropicToolDefinition::Server(_) => {}
+            }
+        }
+        let tools = if tools.is_empty() { None } else { Some(tools) };
 
         let tool_choice = anthropic_request
             .tool_choice
-            .map(|choice| Self::convert_tool_choice_to_universal(&choice))
+            .as_ref()
+            .map(Self::convert_tool_choice_to_universal)
             .transpose()?;
+        let parallel_tool_calls = anthropic_request
+            .tool_choice
+            .as_ref()
+            .and_then(|choice| choice.disable_parallel_tool_use)
+            .map(|disable| !disable);
 
         Ok(ChatRequest {
             model: anthropic_request.model,
             messages: messages?,
             temperature: anthropic_request.temperature,
+            top_p: anthropic_request.top_p,
             max_tokens: Some(anthropic_request.max_tokens),
             stream: anthropic_request.stream.unwrap_or(false),
             tools,
             tool_choice,
-            provider_metadata: None,
+            parallel_tool_calls,
+            reasoning: anthropic_request
+                .thinking
+                .as_ref()
+                .map(Self::convert_thinking_to_universal),
+            response_format: None,
+            web_search,
+            provider_metadata: passthrough_metadata(self.provider_name(), anthropic_request.extra),
         })
     }
 
@@ -280,25 +1007,47 @@ impl ProviderTransformer for AnthropicTransformer {
             .map(|tools| {
                 tools
                     .iter()
-                    .map(|tool| Self::convert_tool_from_universal(tool))
-                    .collect::<TransformerResult<Vec<AnthropicTool>>>()
+                    .map(|tool| Self::convert_tool_from_universal(tool).map(AnthropicToolDefinition::Custom))
+                    .collect::<TransformerResult<Vec<AnthropicToolDefinition>>>()
             })
             .transpose()?;
 
-        let tool_choice = request
-            .tool_choice
+        let parallel_tool_calls = request.parallel_tool_calls.filter(|_| tools.is_some());
+        let mut tool_choice = Self::convert_tool_choice_from_universal(request.tool_choice.as_ref(), parallel_tool_calls);
+
+        let mut thinking = request
+            .reasoning
             .as_ref()
-            .map(|choice| Self::convert_tool_choice_from_universal(choice))
-            .transpose()?;
+            .map(Self::convert_thinking_from_universal);
+
+        let mut tools = tools;
+        if let Some(options) = &request.web_search {
+            tools.get_or_insert_default().push(Self::convert_web_search_from_universal(options)?);
+        }
+        if let Some(format) = &request.response_format {
+            tools
+                .get_or_insert_default()
+                .push(AnthropicToolDefinition::Custom(Self::structured_output_tool(format)));
+            tool_choice = Some(AnthropicToolChoice {
+                choice_type: "tool".to_string(),
+                name: Some(STRUCTURED_OUTPUT_TOOL.to_string()),
+                disable_parallel_tool_use: None,
+            });
+            // Anthropic rejects extended thinking together with a forced tool
+            thinking = None;
+        }
 
         let anthropic_request = AnthropicRequest {
             model: request.model.clone(),
             max_tokens: request.max_tokens.unwrap_or(1000),
             messages: messages?,
             temperature: request.temperature,
+            top_p: request.top_p,
             stream: Some(request.stream),
             tools,
             tool_choice,
+            thinking,
+            extra: passthrough_fields(&request.provider_metadata, self.provider_name()),
         };
 
         serde_json::to_value(anthropic_request)
@@ -311,6 +1060,7 @@ impl ProviderTransformer for AnthropicTransformer {
 
         let tool_calls = Self::extract_tool_calls_from_content(&anthropic_response.content);
         let tool_calls = if tool_calls.is_empty() { None } else { Some(tool_calls) };
+        let web_search = Self::extract_web_search(&anthropic_response.content);
 
         let message = Self::convert_message_to_universal(&AnthropicMessage {
             role: anthropic_response.role,
@@ -324,6 +1074,7 @@ impl ProviderTransformer for AnthropicTransformer {
             message,
             finish_reason,
             tool_calls,
+            web_search,
         };
 
         Ok(ChatResponse {
@@ -332,35 +1083,29 @@ impl ProviderTransformer for AnthropicTransformer {
             created: chrono::Utc::now().timestamp() as u64,
             model: anthropic_response.model,
             choices: vec![choice],
-            usage: Usage {
-                prompt_tokens: anthropic_response.usage.input_tokens,
-                completion_tokens: anthropic_response.usage.output_tokens,
-                total_tokens: anthropic_response.usage.input_tokens + anthropic_response.usage.output_tokens,
-            },
-            provider_metadata: None,
+            usage: Self::convert_usage_to_universal(&anthropic_response.usage),
+            provider_metadata: passthrough_metadata(self.provider_name(), anthropic_response.extra),
         })
     }
 
     fn from_universal_response(&self, response: &ChatResponse) -> TransformerResult<serde_json::Value> {
-        let message = Self::convert_message_from_universal(&response.choices[0].message.clone())?;
+        let mut message = Self::convert_message_from_universal(&response.choices[0].message.clone())?;
+        if let Some(web_search) = &response.choices[0].web_search {
+            message.content = Self::attach_web_search(message.content, web_search, &response.id);
+        }
+        Self::append_tool_calls(&mut message.content, response.choices[0].tool_calls.as_deref().unwrap_or_default());
 
-        let stop_reason = match response.choices[0].finish_reason.as_str() {
-            "tool_calls" => Some("tool_use".to_string()),
-            "stop" => Some("end_turn".to_string()),
-            _ => None,
-        };
+        let stop_reason = Self::convert_stop_reason_from_universal(&response.choices[0].finish_reason);
 
         let anthropic_response = AnthropicResponse {
-            id: response.id.clone(),
+            id: message_id(&response.id),
             response_type: "message".to_string(),
             role: "assistant".to_string(),
             content: message.content,
             model: response.model.clone(),
             stop_reason,
-            usage: AnthropicUsage {
-                input_tokens: response.usage.prompt_tokens,
-                output_tokens: response.usage.completion_tokens,
-            },
+            usage: Self::convert_usage_from_universal(&response.usage),
+            extra: passthrough_fields(&response.provider_metadata, self.provider_name()),
         };
 
         serde_json::to_value(anthropic_response)
@@ -370,91 +1115,125 @@ impl ProviderTransformer for AnthropicTransformer {
     fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk> {
         let anthropic_chunk: AnthropicStreamChunk = serde_json::from_value(chunk.clone())
             .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+        let block_index = anthropic_chunk.index.unwrap_or(0);
+        // message_start carries the prompt counts in its message, message_delta the final output count
+        let usage = anthropic_chunk
+            .usage
+            .clone()
+            .or_else(|| serde_json::from_value(anthropic_chunk.message.as_ref()?.get("usage")?.clone()).ok())
+            .map(|usage| Self::convert_usage_to_universal(&usage));
+        let delta = |content: Option<String>, tool_calls: Option<Vec<StreamToolCall>>| StreamDelta {
+            role: Some("assistant".to_string()),
+            content,
+            tool_calls,
+        };
 
-        match &anthropic_chunk.delta {
-            Some(AnthropicStreamDelta::TextDelta { text }) => {
-                let delta = StreamDelta {
-                    role: Some("assistant".to_string()),
-                    content: Some(text.clone()),
-                    tool_calls: None,
-                };
-                let choice = StreamChoice {
-                    index: anthropic_chunk.index.unwrap_or(0),
-                    delta,
-                    finish_reason: None,
-                };
-                return Ok(ChatStreamChunk {
-                    id: "stream".to_string(),
-                    object: "chat.completion.chunk".to_string(),
-                    created: chrono::Utc::now().timestamp() as u64,
-                    model: "anthropic".to_string(),
-                    choices: vec![choice],
-                    provider_metadata: None,
-                });
-            },
-            Some(AnthropicStreamDelta::ToolUseDelta { .. }) => {
-                let delta = StreamDelta {
-                    role: Some("assistant".to_string()),
-                    content: None,
-                    tool_calls: Some(vec![StreamToolCall {
-                        index: 0,
-                        id: Some("tool_".to_string()),
+        // Text keeps the block index as its choice index; tool calls use it as the call index
+        let choice = match (anthropic_chunk.chunk_type.as_str(), &anthropic_chunk.delta) {
+            ("content_block_start", _) => match anthropic_chunk.content_block.as_ref() {
+                Some(block) if block.get("type").and_then(|t| t.as_str()) == Some("tool_use") => Some(StreamChoice {
+                    index: 0,
+                    delta: delta(None, Some(vec![StreamToolCall {
+                        index: block_index,
+                        id: string_field(block, "id"),
                         tool_type: Some("function".to_string()),
-                        function: Some(StreamFunctionCall {
-                            name: None,
-                            arguments: None,
-                        }),
-                    }]),
-                };
-                let choice = StreamChoice {
-                    index: anthropic_chunk.index.unwrap_or(0),
-                    delta,
+                        function: Some(StreamFunctionCall { name: string_field(block, "name"), arguments: None }),
+                    }])),
                     finish_reason: None,
-                };
-                return Ok(ChatStreamChunk {
-                    id: "stream".to_string(),
-                    object: "chat.completion.chunk".to_string(),
-                    created: chrono::Utc::now().timestamp() as u64,
-                    model: "anthropic".to_string(),
-                    choices: vec![choice],
-                    provider_metadata: None,
-                });
+                }),
+                _ => None,
             },
-            None => {
-                return Ok(ChatStreamChunk {
-                    id: "stream".to_string(),
-                    object: "chat.completion.chunk".to_string(),
-                    created: chrono::Utc::now().timestamp() as u64,
-                    model: "anthropic".to_string(),
-                    choices: vec![],
-                    provider_metadata: None,
-                });
-            }
-        }
+            (_, Some(AnthropicStreamDelta::Content(AnthropicContentDelta::TextDelta { text }))) => Some(StreamChoice {
+                index: block_index,
+                delta: delta(Some(text.clone()), None),
+                finish_reason: None,
+            }),
+            (_, Some(AnthropicStreamDelta::Content(AnthropicContentDelta::ToolUseDelta { partial_json }))) => Some(StreamChoice {
+                index: 0,
+                delta: delta(None, Some(vec![StreamToolCall {
+                    index: block_index,
+                    id: None,
+                    tool_type: None,
+                    function: Some(StreamFunctionCall { name: None, arguments: Some(partial_json.clone()) }),
+                }])),
+                finish_reason: None,
+            }),
+            (_, Some(AnthropicStreamDelta::MessageDelta { stop_reason: Some(stop_reason) })) => Some(StreamChoice {
+                index: 0,
+                delta: StreamDelta { role: None, content: None, tool_calls: None },
+                finish_reason: Some(stop_reason.clone()),
+            }),
+            _ => None,
+        };
+
+        Ok(ChatStreamChunk {
+            id: "stream".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: chrono::Utc::now().timestamp() as u64,
+            model: "anthropic".to_string(),
+            choices: choice.into_iter().collect(),
+            usage,
+            provider_metadata: None,
+        })
     }
 
+    /// Encodes each chunk on its own: no content_block_start for text or content_block_stop,
+    /// and block indices come straight from the chunk. Convert through a chain, which keeps
+    /// an `AnthropicStreamEncoder`, for a well-formed stream. One chunk can map to several
+    /// events; those are returned as an array.
     fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
-        let choice = &chunk.choices[0];
-        
-        if let Some(content) = &choice.delta.content {
-            let anthropic_chunk = AnthropicStreamChunk {
-                chunk_type: "content_block_delta".to_string(),
-                index: Some(choice.index),
-                delta: Some(AnthropicStreamDelta::TextDelta { 
-                    text: content.clone() 
-                }),
-            };
-            return serde_json::to_value(anthropic_chunk)
-                .map_err(|e| TransformerError::Serialization(e.to_string()));
-        }
+        let usage = chunk.usage.as_ref().map(Self::convert_usage_from_universal);
+        let mut events = Vec::new();
 
-        let anthropic_chunk = AnthropicStreamChunk {
-            chunk_type: "message_stop".to_string(),
-            index: None,
-            delta: None,
+        // A chunk with only usage, as OpenAI sends last, becomes a message_delta without stop_reason
+        let Some(choice) = chunk.choices.first() else {
+            events.push(AnthropicStreamChunk::message_delta(None, usage));
+            return Self::encode_stream_events(events);
         };
 
-        serde_json::to_value(anthropic_chunk)
-            .map_err(|e| TransformerError::Serialization(e.to_string()))
+        // The opening chunk carries only the role: it becomes message_start with the id and model
+        let opening = choice.delta.role.is_some()
+            && choice.delta.content.as_deref().unwrap_or_default().is_empty()
+            && choice.delta.tool_calls.is_none()
+            && choice.finish_reason.is_none();
+        if opening {
+            events.push(AnthropicStreamChunk::message_start(chunk, usage));
+            return Self::encode_stream_events(events);
+        }
+
+        if let Some(content) = &choice.delta.content
+            && !content.is_empty()
+        {
+            events.push(AnthropicStreamChunk::block_delta(
+                choice.index,
+                AnthropicContentDelta::TextDelta { text: content.clone() },
+            ));
+        }
+
+        for call in choice.delta.tool_calls.iter().flatten() {
+            if call.id.is_some() {
+                events.push(AnthropicStreamChunk::block_start(call.index, tool_use_block(call)));
+            }
+            if let Some(arguments) = call.function.as_ref().and_then(|function| function.arguments.as_ref())
+                && !arguments.is_empty()
+            {
+                events.push(AnthropicStreamChunk::block_delta(
+                    call.index,
+                    AnthropicContentDelta::ToolUseDelta { partial_json: arguments.clone() },
+                ));
+            }
+        }
+
+        if let Some(finish_reason) = &choice.finish_reason {
+            let stop_reason = Self::convert_stop_reason_from_universal(finish_reason);
+            events.push(AnthropicStreamChunk::message_delta(stop_reason, Some(usage.unwrap_or_default())));
+            events.push(AnthropicStreamChunk::event("message_stop"));
+        }
+
+        Self::encode_stream_events(events)
+    }
+
+    fn stream_encoder(&self) -> Option<Box<dyn StreamEncoder>> {
+        Some(Box::new(AnthropicStreamEncoder::new()))
     }
 }
\ No newline at end of file
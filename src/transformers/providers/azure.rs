@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::config::types::Provider;
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::openai::OpenAITransformer;
+use crate::transformers::providers::provider_trait::*;
+use crate::transformers::upstream::UpstreamRequest;
+
+const DEFAULT_API_VERSION: &str = "2024-10-21";
+const CONTENT_FILTER_CODE: &str = "content_filter";
+
+/// Options accepted from `["azure", {...}]` in a provider's `transformer.use` list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AzureOpenAIOptions {
+    pub api_version: Option<String>,
+    /// Model name → deployment name; models without an entry use their own name as deployment
+    #[serde(default)]
+    pub deployments: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AzureOpenAIError {
+    pub code: Option<String>,
+    pub message: String,
+    pub param: Option<String>,
+    pub status: Option<u16>,
+    pub innererror: Option<serde_json::Value>,
+}
+
+impl AzureOpenAIError {
+    pub fn is_content_filter(&self) -> bool {
+        self.code.as_deref() == Some(CONTENT_FILTER_CODE)
+    }
+
+    /// Names of the filter categories that fired, e.g. `["hate", "violence"]`.
+    pub fn filtered_categories(&self) -> Vec<String> {
+        self.innererror
+            .as_ref()
+            .and_then(|inner| inner.get("content_filter_result"))
+            .and_then(|result| result.as_object())
+            .map(filtered_categories)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AzureOpenAIErrorEnvelope {
+    error: AzureOpenAIError,
+}
+
+fn filtered_categories(result: &serde_json::Map<String, serde_json::Value>) -> Vec<String> {
+    result
+        .iter()
+        .filter(|(_, category)| category.get("filtered").and_then(|f| f.as_bool()) == Some(true))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Azure OpenAI serves the OpenAI chat completions body from per-deployment URLs, so the
+/// body conversion is delegated to `OpenAITransformer` and only routing, auth and Azure's
+/// content filtering are handled here.
+pub struct AzureOpenAITransformer {
+    openai: OpenAITransformer,
+    options: AzureOpenAIOptions,
+}
+
+impl AzureOpenAITransformer {
+    pub fn new() -> Self {
+        Self::with_options(AzureOpenAIOptions::default())
+    }
+
+    pub fn with_options(options: AzureOpenAIOptions) -> Self {
+        Self {
+            openai: OpenAITransformer::new(),
+            options,
+        }
+    }
+
+    pub fn from_options_value(options: &serde_json::Value) -> TransformerResult<Self> {
+        serde_json::from_value(options.clone())
+            .map(Self::with_options)
+            .map_err(|e| TransformerError::Configuration(e.to_string()))
+    }
+
+    pub fn api_version(&self) -> &str {
+        self.options.api_version.as_deref().unwrap_or(DEFAULT_API_VERSION)
+    }
+
+    pub fn deployment_for<'a>(&'a self, model: &'a str) -> &'a str {
+        self.options
+            .deployments
+            .get(model)
+            .map(|deployment| deployment.as_str())
+            .unwrap_or(model)
+    }
+
+    pub fn parse_error(body: &serde_json::Value) -> Option<AzureOpenAIError> {
+        serde_json::from_value::<AzureOpenAIErrorEnvelope>(body.clone())
+            .ok()
+            .map(|envelope| envelope.error)
+    }
+
+    fn check_error(body: &serde_json::Value) -> TransformerResult<()> {
+        let Some(error) = Self::parse_error(body) else {
+            return Ok(());
+        };
+        if error.is_content_filter() {
+            let categories = error.filtered_categories();
+            return Err(TransformerError::ProviderError(if categories.is_empty() {
+                format!("Azure content filter blocked the request: {}", error.message)
+            } else {
+                format!(
+                    "Azure content filter blocked the request ({}): {}",
+                    categories.join(", "),
+                    error.message
+                )
+            }));
+        }
+        Err(TransformerError::ProviderError(format!(
+            "Azure OpenAI error {}: {}",
+            error.code.unwrap_or_default(),
+            error.message
+        )))
+    }
+
+    /// Keeps the prompt and completion filter annotations so callers can tell why a
+    /// response ended with `finish_reason: "content_filter"`.
+    fn collect_metadata(body: &serde_json::Value) -> Option<HashMap<String, serde_json::Value>> {
+        let mut metadata = HashMap::new();
+        if let Some(prompt_filter_results) = body.get("prompt_filter_results") {
+            metadata.insert("prompt_filter_results".to_string(), prompt_filter_results.clone());
+        }
+        if let Some(content_filter_results) = body.pointer("/choices/0/content_filter_results") {
+            metadata.insert("content_filter_results".to_string(), content_filter_results.clone());
+        }
+        if metadata.is_empty() { None } else { Some(metadata) }
+    }
+}
+
+impl Default for AzureOpenAITransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderTransformer for AzureOpenAITransformer {
+    fn provider_name(&self) -> &'static str {
+        "azure"
+    }
+
+    fn to_universal_request(&self, request: &serde_json::Value) -> TransformerResult<ChatRequest> {
+        self.openai.to_universal_request(request)
+    }
+
+    fn from_universal_request(&self, request: &ChatRequest) -> TransformerResult<serde_json::Value> {
+        let mut body = self.openai.from_universal_request(request)?;
+        // The deployment in the URL selects the model
+        if let Some(object) = body.as_object_mut() {
+            object.remove("model");
+        }
+        Ok(body)
+    }
+
+    fn to_universal_response(&self, response: &serde_json::Value) -> TransformerResult<ChatResponse> {
+        Self::check_error(response)?;
+
+        // A filtered completion carries no message content at all
+        let mut response = response.clone();
+        if let Some(message) = response.pointer_mut("/choices/0/message")
+            && message.get("role").is_none()
+        {
+            message["role"] = serde_json::json!("assistant");
+        }
+
+        let mut universal = self.openai.to_universal_response(&response)?;
+        if let Some(metadata) = Self::collect_metadata(&response) {
+            universal.provider_metadata.get_or_insert_default().extend(metadata);
+        }
+        Ok(universal)
+    }
+
+    fn from_universal_response(&self, response: &ChatResponse) -> TransformerResult<serde_json::Value> {
+        self.openai.from_universal_response(response)
+    }
+
+    fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk> {
+        Self::check_error(chunk)?;
+
+        // The first chunk only carries `prompt_filter_results` and has no choices
+        let has_choices = chunk
+            .get("choices")
+            .and_then(|choices| choices.as_array())
+            .is_some_and(|choices| !choices.is_empty());
+        let has_usage = chunk.get("usage").is_some_and(|usage| !usage.is_null());
+        let mut universal = if has_choices || has_usage {
+            self.openai.to_universal_stream_chunk(chunk)?
+        } else {
+            ChatStreamChunk {
+                id: chunk["id"].as_str().unwrap_or_default().to_string(),
+                object: chunk["object"].as_str().unwrap_or("chat.completion.chunk").to_string(),
+                created: chunk["created"].as_u64().unwrap_or_default(),
+                model: chunk["model"].as_str().unwrap_or_default().to_string(),
+                choices: vec![],
+                usage: None,
+                provider_metadata: None,
+            }
+        };
+        if let Some(metadata) = Self::collect_metadata(chunk) {
+            universal.provider_metadata.get_or_insert_default().extend(metadata);
+        }
+        Ok(universal)
+    }
+
+    fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
+        self.openai.from_universal_stream_chunk(chunk)
+    }
+
+    fn build_upstream_request(&self, provider: &Provider, request: &ChatRequest) -> TransformerResult<UpstreamRequest> {
+        let base_url = provider.api_base_url.trim_end_matches('/');
+        if base_url.is_empty() {
+            return Err(TransformerError::Configuration(format!(
+                "Azure provider {} requires api_base_url",
+                provider.name
+            )));
+        }
+
+        let url = format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            base_url,
+            self.deployment_for(&request.model),
+            self.api_version()
+        );
+        let mut upstream = UpstreamRequest::post(url, self.from_universal_request(request)?);
+        upstream.set_header("api-key", provider.api_key.clone());
+        Ok(upstream)
+    }
+}
@@ -0,0 +1,832 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use crate::config::types::Provider;
+use crate::transformers::auth::{AwsCredentials, SigV4Signer};
+use crate::transformers::auth::sigv4::uri_encode;
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::provider_trait::*;
+use crate::transformers::stream::{EventStreamMessage, StreamFormat};
+use crate::transformers::upstream::UpstreamRequest;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BedrockRequest {
+    messages: Vec<BedrockMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<Vec<BedrockContentBlock>>,
+    #[serde(rename = "inferenceConfig", skip_serializing_if = "Option::is_none")]
+    inference_config: Option<BedrockInferenceConfig>,
+    #[serde(rename = "toolConfig", skip_serializing_if = "Option::is_none")]
+    tool_config: Option<BedrockToolConfig>,
+    #[serde(rename = "additionalModelRequestFields", skip_serializing_if = "Option::is_none")]
+    additional_model_request_fields: Option<serde_json::Value>,
+    /// Fields not modeled above (`guardrailConfig`, `promptVariables`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockMessage {
+    role: String,
+    content: Vec<BedrockContentBlock>,
+}
+
+/// Converse content blocks are a union: exactly one member is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BedrockContentBlock {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<BedrockImage>,
+    #[serde(rename = "toolUse", skip_serializing_if = "Option::is_none")]
+    tool_use: Option<BedrockToolUse>,
+    #[serde(rename = "toolResult", skip_serializing_if = "Option::is_none")]
+    tool_result: Option<BedrockToolResult>,
+    #[serde(rename = "cachePoint", skip_serializing_if = "Option::is_none")]
+    cache_point: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockImage {
+    format: String,
+    source: BedrockImageSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockImageSource {
+    bytes: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockToolUse {
+    #[serde(rename = "toolUseId")]
+    tool_use_id: String,
+    name: String,
+    input: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockToolResult {
+    #[serde(rename = "toolUseId")]
+    tool_use_id: String,
+    content: Vec<BedrockToolResultContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockToolResultContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BedrockInferenceConfig {
+    #[serde(rename = "maxTokens", skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(rename = "topP", skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockToolConfig {
+    tools: Vec<BedrockTool>,
+    #[serde(rename = "toolChoice", skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockTool {
+    #[serde(rename = "toolSpec")]
+    tool_spec: BedrockToolSpec,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockToolSpec {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "inputSchema")]
+    input_schema: BedrockInputSchema,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockInputSchema {
+    json: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockResponse {
+    output: BedrockOutput,
+    #[serde(rename = "stopReason")]
+    stop_reason: String,
+    usage: BedrockUsage,
+    /// Fields not modeled above (`metrics`, `trace`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockOutput {
+    message: BedrockMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BedrockUsage {
+    /// Excludes the cached prompt tokens below
+    #[serde(rename = "inputTokens")]
+    input_tokens: u32,
+    #[serde(rename = "outputTokens")]
+    output_tokens: u32,
+    #[serde(rename = "totalTokens")]
+    total_tokens: u32,
+    #[serde(rename = "cacheReadInputTokens", default, skip_serializing_if = "Option::is_none")]
+    cache_read_input_tokens: Option<u32>,
+    #[serde(rename = "cacheWriteInputTokens", default, skip_serializing_if = "Option::is_none")]
+    cache_write_input_tokens: Option<u32>,
+}
+
+impl BedrockUsage {
+    fn to_universal(&self) -> Usage {
+        let cached = self.cache_read_input_tokens.unwrap_or(0) + self.cache_write_input_tokens.unwrap_or(0);
+        Usage {
+            prompt_tokens: self.input_tokens + cached,
+            completion_tokens: self.output_tokens,
+            total_tokens: self.total_tokens,
+            cache_read_tokens: self.cache_read_input_tokens,
+            cache_creation_tokens: self.cache_write_input_tokens,
+            reasoning_tokens: None,
+        }
+    }
+
+    fn from_universal(usage: &Usage) -> Self {
+        let cached = usage.cache_read_tokens.unwrap_or(0) + usage.cache_creation_tokens.unwrap_or(0);
+        Self {
+            input_tokens: usage.prompt_tokens.saturating_sub(cached),
+            output_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+            cache_read_input_tokens: usage.cache_read_tokens,
+            cache_write_input_tokens: usage.cache_creation_tokens,
+        }
+    }
+}
+
+/// Options accepted from `["bedrock", {...}]` in a provider's `transformer.use` list.
+/// Credentials that are not configured fall back to the standard AWS environment variables.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BedrockOptions {
+    pub region: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub session_token: Option<String>,
+}
+
+pub struct BedrockTransformer {
+    options: BedrockOptions,
+}
+
+impl BedrockTransformer {
+    pub fn new() -> Self {
+        Self::with_options(BedrockOptions::default())
+    }
+
+    pub fn with_options(options: BedrockOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn from_options_value(options: &serde_json::Value) -> TransformerResult<Self> {
+        serde_json::from_value(options.clone())
+            .map(Self::with_options)
+            .map_err(|e| TransformerError::Configuration(e.to_string()))
+    }
+
+    pub fn region(&self) -> String {
+        self.options
+            .region
+            .clone()
+            .or_else(|| env::var("AWS_REGION").ok())
+            .or_else(|| env::var("AWS_DEFAULT_REGION").ok())
+            .unwrap_or_else(|| "us-east-1".to_string())
+    }
+
+    pub fn credentials(&self) -> TransformerResult<AwsCredentials> {
+        match (&self.options.access_key_id, &self.options.secret_access_key) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(AwsCredentials::new(
+                access_key_id.clone(),
+                secret_access_key.clone(),
+                self.options.session_token.clone(),
+            )),
+            _ => AwsCredentials::from_env().ok_or_else(|| {
+                TransformerError::Configuration(
+                    "Bedrock requires access_key_id/secret_access_key or AWS_* environment variables"
+                        .to_string(),
+                )
+            }),
+        }
+    }
+
+    /// Converts one decoded ConverseStream frame into the `{eventType: payload}` JSON shape
+    /// accepted by `to_universal_stream_chunk`.
+    pub fn event_to_json(message: &EventStreamMessage) -> TransformerResult<serde_json::Value> {
+        let payload: serde_json::Value = if message.payload.is_empty() {
+            serde_json::json!({})
+        } else {
+            serde_json::from_slice(&message.payload)
+                .map_err(|e| TransformerError::Deserialization(e.to_string()))?
+        };
+
+        if message.header_str(":message-type") == Some("exception") {
+            let exception = message.header_str(":exception-type").unwrap_or("exception");
+            let detail = payload["message"].as_str().unwrap_or_default();
+            return Err(TransformerError::ProviderError(format!("Bedrock {}: {}", exception, detail)));
+        }
+
+        let event_type = message.header_str(":event-type").ok_or_else(|| {
+            TransformerError::InvalidFormat("Event-stream frame has no :event-type".to_string())
+        })?;
+
+        let mut event = serde_json::Map::new();
+        event.insert(event_type.to_string(), payload);
+        Ok(serde_json::Value::Object(event))
+    }
+
+    fn image_from_url(url: &str) -> Option<BedrockImage> {
+        let (header, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+        let format = header.strip_prefix("image/").unwrap_or("png");
+        Some(BedrockImage {
+            format: if format == "jpg" { "jpeg".to_string() } else { format.to_string() },
+            source: BedrockImageSource { bytes: data.to_string() },
+        })
+    }
+
+    fn convert_part_from_universal(part: &MessagePart) -> Option<BedrockContentBlock> {
+        match part.part_type.as_str() {
+            "tool_use" | "function_call" => Some(BedrockContentBlock {
+                tool_use: Some(BedrockToolUse {
+                    tool_use_id: part.tool_use_id.clone().unwrap_or_default(),
+                    name: part.tool_name.clone().unwrap_or_default(),
+                    input: part.tool_input.clone().unwrap_or(serde_json::json!({})),
+                }),
+                ..Default::default()
+            }),
+            "tool_result" | "function_response" => Some(BedrockContentBlock {
+                tool_result: Some(BedrockToolResult {
+                    tool_use_id: part.tool_use_id.clone().unwrap_or_default(),
+                    content: vec![BedrockToolResultContent {
+                        text: Some(part.text.clone().unwrap_or_default()),
+                        json: None,
+                    }],
+                    status: None,
+                }),
+                ..Default::default()
+            }),
+            "image" => part
+                .image_url
+                .as_ref()
+                .and_then(|image| Self::image_from_url(&image.url))
+                .map(|image| BedrockContentBlock {
+                    image: Some(image),
+                    ..Default::default()
+                }),
+            // Converse rejects blank text blocks
+            _ => part
+                .text
+                .clone()
+                .filter(|text| !text.is_empty())
+                .map(|text| BedrockContentBlock {
+                    text: Some(text),
+                    ..Default::default()
+                }),
+        }
+    }
+
+    fn convert_content_from_universal(content: &MessageContent) -> Vec<BedrockContentBlock> {
+        match content {
+            MessageContent::Text(text) if text.is_empty() => vec![],
+            MessageContent::Text(text) => vec![BedrockContentBlock {
+                text: Some(text.clone()),
+                ..Default::default()
+            }],
+            MessageContent::Parts(parts) => {
+                let mut blocks = Vec::new();
+                for part in parts {
+                    if let Some(block) = Self::convert_part_from_universal(part) {
+                        blocks.push(block);
+                    }
+                    if part.cache_control.is_some() {
+                        blocks.push(BedrockContentBlock {
+                            cache_point: Some(serde_json::json!({"type": "default"})),
+                            ..Default::default()
+                        });
+                    }
+                }
+                blocks
+            }
+        }
+    }
+
+    fn convert_block_to_universal(block: &BedrockContentBlock) -> Option<MessagePart> {
+        if let Some(text) = &block.text {
+            return Some(MessagePart {
+                part_type: "text".to_string(),
+                text: Some(text.clone()),
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: None,
+                document: None,
+                cache_control: None,
+            });
+        }
+        if let Some(tool_use) = &block.tool_use {
+            return Some(MessagePart {
+                part_type: "tool_use".to_string(),
+                text: None,
+                tool_use_id: Some(tool_use.tool_use_id.clone()),
+                tool_name: Some(tool_use.name.clone()),
+                tool_input: Some(tool_use.input.clone()),
+                image_url: None,
+                document: None,
+                cache_control: None,
+            });
+        }
+        if let Some(tool_result) = &block.tool_result {
+            let text = tool_result
+                .content
+                .iter()
+                .map(|content| match (&content.text, &content.json) {
+                    (Some(text), _) => text.clone(),
+                    (None, Some(json)) => json.to_string(),
+                    (None, None) => String::new(),
+                })
+                .collect::<Vec<_>>()
+                .join("");
+            return Some(MessagePart {
+                part_type: "tool_result".to_string(),
+                text: Some(text),
+                tool_use_id: Some(tool_result.tool_use_id.clone()),
+                tool_name: None,
+                tool_input: None,
+                image_url: None,
+                document: None,
+                cache_control: None,
+            });
+        }
+        if let Some(image) = &block.image {
+            return Some(MessagePart {
+                part_type: "image".to_string(),
+                text: None,
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: Some(ImageUrl {
+                    url: format!("data:image/{};base64,{}", image.format, image.source.bytes),
+                    detail: None,
+                }),
+                document: None,
+                cache_control: None,
+            });
+        }
+        None
+    }
+
+    fn map_stop_reason_to_universal(stop_reason: &str) -> String {
+        match stop_reason {
+            "tool_use" => "tool_calls".to_string(),
+            "end_turn" | "stop_sequence" => "stop".to_string(),
+            "max_tokens" => "length".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    fn map_stop_reason_from_universal(finish_reason: &str) -> String {
+        match finish_reason {
+            "tool_calls" | "tool_use" => "tool_use".to_string(),
+            "length" | "max_tokens" => "max_tokens".to_string(),
+            _ => "end_turn".to_string(),
+        }
+    }
+
+    fn convert_tool_choice_from_universal(choice: &ToolChoice) -> Option<serde_json::Value> {
+        match choice {
+            ToolChoice::Auto(_) => Some(serde_json::json!({"auto": {}})),
+            ToolChoice::Required(_) => Some(serde_json::json!({"any": {}})),
+            ToolChoice::Specific(spec) => Some(serde_json::json!({"tool": {"name": spec.function.name}})),
+            // Converse has no "none"; leaving toolChoice unset keeps the model free to answer in text
+            ToolChoice::None(_) => None,
+        }
+    }
+
+    fn empty_chunk(choices: Vec<StreamChoice>) -> ChatStreamChunk {
+        ChatStreamChunk {
+            id: "bedrock_stream".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: chrono::Utc::now().timestamp() as u64,
+            model: "bedrock".to_string(),
+            choices,
+            usage: None,
+            provider_metadata: None,
+        }
+    }
+}
+
+impl Default for BedrockTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderTransformer for BedrockTransformer {
+    fn provider_name(&self) -> &'static str {
+        "bedrock"
+    }
+
+    fn to_universal_request(&self, request: &serde_json::Value) -> TransformerResult<ChatRequest> {
+        let bedrock_request: BedrockRequest = serde_json::from_value(request.clone())
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+
+        let mut messages = Vec::new();
+        if let Some(system) = &bedrock_request.system {
+            let text: String = system.iter().filter_map(|block| block.text.clone()).collect();
+            messages.push(ChatMessage {
+                role: "system".to_string(),
+                content: MessageContent::Text(text),
+                name: None,
+            });
+        }
+        for message in &bedrock_request.messages {
+            messages.push(ChatMessage {
+                role: message.role.clone(),
+                content: MessageContent::Parts(
+                    message.content.iter().filter_map(Self::convert_block_to_universal).collect(),
+                ),
+                name: None,
+            });
+        }
+
+        let tools = bedrock_request.tool_config.as_ref().map(|config| {
+            config
+                .tools
+                .iter()
+                .map(|tool| Tool {
+                    tool_type: "function".to_string(),
+                    function: FunctionDefinition {
+                        name: tool.tool_spec.name.clone(),
+                        description: tool.tool_spec.description.clone(),
+                        parameters: tool.tool_spec.input_schema.json.clone(),
+                    },
+                })
+                .collect()
+        });
+
+        let tool_choice = bedrock_request
+            .tool_config
+            .as_ref()
+            .and_then(|config| config.tool_choice.as_ref())
+            .map(|choice| {
+                if choice.get("any").is_some() {
+                    ToolChoice::Required("any".to_string())
+                } else if let Some(name) = choice.pointer("/tool/name").and_then(|n| n.as_str()) {
+                    ToolChoice::Specific(ToolChoiceSpecific {
+                        choice_type: "function".to_string(),
+                        function: FunctionChoice { name: name.to_string() },
+                    })
+                } else {
+                    ToolChoice::Auto("auto".to_string())
+                }
+            });
+
+        let inference_config = bedrock_request.inference_config.unwrap_or_default();
+
+        Ok(ChatRequest {
+            // The model id travels in the URL path, not the body
+            model: "bedrock".to_string(),
+            messages,
+            temperature: inference_config.temperature,
+            top_p: inference_config.top_p,
+            max_tokens: inference_config.max_tokens,
+            stream: false,
+            tools,
+            tool_choice,
+            parallel_tool_calls: None,
+            reasoning: None,
+            response_format: None,
+            web_search: None,
+            provider_metadata: passthrough_metadata(self.provider_name(), bedrock_request.extra),
+        })
+    }
+
+    fn from_universal_request(&self, request: &ChatRequest) -> TransformerResult<serde_json::Value> {
+        let mut system = Vec::new();
+        let mut messages: Vec<BedrockMessage> = Vec::new();
+
+        for message in &request.messages {
+            if message.role == "system" {
+                system.extend(Self::convert_content_from_universal(&message.content));
+                continue;
+            }
+
+            // Converse only knows user/assistant, and requires the two to alternate
+            let role = if message.role == "assistant" { "assistant" } else { "user" };
+            let content = Self::convert_content_from_universal(&message.content);
+            if content.is_empty() {
+                continue;
+            }
+            match messages.last_mut() {
+                Some(last) if last.role == role => last.content.extend(content),
+                _ => messages.push(BedrockMessage {
+                    role: role.to_string(),
+                    content,
+                }),
+            }
+        }
+
+        let tool_config = request.tools.as_ref().map(|tools| BedrockToolConfig {
+            tools: tools
+                .iter()
+                .map(|tool| BedrockTool {
+                    tool_spec: BedrockToolSpec {
+                        name: tool.function.name.clone(),
+                        description: tool.function.description.clone(),
+                        input_schema: BedrockInputSchema {
+                            json: tool.function.parameters.clone(),
+                        },
+                    },
+                })
+                .collect(),
+            tool_choice: request
+                .tool_choice
+                .as_ref()
+                .and_then(Self::convert_tool_choice_from_universal),
+        });
+
+        // Extended thinking is passed through to Anthropic models as a native request field
+        let additional_model_request_fields = request
+            .reasoning
+            .as_ref()
+            .filter(|reasoning| reasoning.enabled && request.model.contains("anthropic."))
+            .map(|reasoning| {
+                serde_json::json!({
+                    "thinking": {
+                        "type": "enabled",
+                        "budget_tokens": reasoning.budget_tokens.unwrap_or(1024),
+                    }
+                })
+            });
+
+        let bedrock_request = BedrockRequest {
+            messages,
+            system: if system.is_empty() { None } else { Some(system) },
+            inference_config: Some(BedrockInferenceConfig {
+                max_tokens: request.max_tokens,
+                temperature: request.temperature,
+                top_p: request.top_p,
+            }),
+            tool_config,
+            additional_model_request_fields,
+            extra: passthrough_fields(&request.provider_metadata, self.provider_name()),
+        };
+
+        serde_json::to_value(bedrock_request)
+            .map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    fn to_universal_response(&self, response: &serde_json::Value) -> TransformerResult<ChatResponse> {
+        let bedrock_response: BedrockResponse = serde_json::from_value(response.clone())
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+
+        let parts: Vec<MessagePart> = bedrock_response
+            .output
+            .message
+            .content
+            .iter()
+            .filter_map(Self::convert_block_to_universal)
+            .collect();
+
+        let tool_calls: Vec<ToolCall> = bedrock_response
+            .output
+            .message
+            .content
+            .iter()
+            .filter_map(|block| block.tool_use.as_ref())
+            .map(|tool_use| ToolCall {
+                id: Some(tool_use.tool_use_id.clone()),
+                tool_type: "function".to_string(),
+                function: FunctionCall {
+                    name: tool_use.name.clone(),
+                    arguments: serde_json::to_string(&tool_use.input).unwrap_or_default(),
+                },
+            })
+            .collect();
+
+        let created = chrono::Utc::now().timestamp() as u64;
+
+        Ok(ChatResponse {
+            id: format!("bedrock-{}", created),
+            object: "chat.completion".to_string(),
+            created,
+            model: "bedrock".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: ChatMessage {
+                    role: bedrock_response.output.message.role,
+                    content: MessageContent::Parts(parts),
+                    name: None,
+                },
+                finish_reason: Self::map_stop_reason_to_universal(&bedrock_response.stop_reason),
+                tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+                web_search: None,
+            }],
+            usage: bedrock_response.usage.to_universal(),
+            provider_metadata: passthrough_metadata(self.provider_name(), bedrock_response.extra),
+        })
+    }
+
+    fn from_universal_response(&self, response: &ChatResponse) -> TransformerResult<serde_json::Value> {
+        let choice = &response.choices[0];
+        let bedrock_response = BedrockResponse {
+            output: BedrockOutput {
+                message: BedrockMessage {
+                    role: "assistant".to_string(),
+                    content: Self::convert_content_from_universal(&choice.message.content),
+                },
+            },
+            stop_reason: Self::map_stop_reason_from_universal(&choice.finish_reason),
+            usage: BedrockUsage::from_universal(&response.usage),
+            extra: passthrough_fields(&response.provider_metadata, self.provider_name()),
+        };
+
+        serde_json::to_value(bedrock_response)
+            .map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk> {
+        let choice = |delta: StreamDelta, finish_reason: Option<String>| StreamChoice {
+            index: 0,
+            delta,
+            finish_reason,
+        };
+        let empty_delta = || StreamDelta {
+            role: None,
+            content: None,
+            tool_calls: None,
+        };
+
+        if chunk.get("messageStart").is_some() {
+            return Ok(Self::empty_chunk(vec![choice(
+                StreamDelta {
+                    role: Some("assistant".to_string()),
+                    ..empty_delta()
+                },
+                None,
+            )]));
+        }
+
+        if let Some(start) = chunk.get("contentBlockStart") {
+            let index = start["contentBlockIndex"].as_u64().unwrap_or(0) as u32;
+            if let Some(tool_use) = start.pointer("/start/toolUse") {
+                return Ok(Self::empty_chunk(vec![choice(
+                    StreamDelta {
+                        tool_calls: Some(vec![StreamToolCall {
+                            index,
+                            id: tool_use["toolUseId"].as_str().map(str::to_string),
+                            tool_type: Some("function".to_string()),
+                            function: Some(StreamFunctionCall {
+                                name: tool_use["name"].as_str().map(str::to_string),
+                                arguments: Some(String::new()),
+                            }),
+                        }]),
+                        ..empty_delta()
+                    },
+                    None,
+                )]));
+            }
+            return Ok(Self::empty_chunk(vec![]));
+        }
+
+        if let Some(delta) = chunk.get("contentBlockDelta") {
+            let index = delta["contentBlockIndex"].as_u64().unwrap_or(0) as u32;
+            if let Some(text) = delta.pointer("/delta/text").and_then(|t| t.as_str()) {
+                return Ok(Self::empty_chunk(vec![choice(
+                    StreamDelta {
+                        content: Some(text.to_string()),
+                        ..empty_delta()
+                    },
+                    None,
+                )]));
+            }
+            if let Some(input) = delta.pointer("/delta/toolUse/input").and_then(|i| i.as_str()) {
+                return Ok(Self::empty_chunk(vec![choice(
+                    StreamDelta {
+                        tool_calls: Some(vec![StreamToolCall {
+                            index,
+                            id: None,
+                            tool_type: None,
+                            function: Some(StreamFunctionCall {
+                                name: None,
+                                arguments: Some(input.to_string()),
+                            }),
+                        }]),
+                        ..empty_delta()
+                    },
+                    None,
+                )]));
+            }
+            return Ok(Self::empty_chunk(vec![]));
+        }
+
+        if let Some(stop) = chunk.get("messageStop") {
+            let stop_reason = stop["stopReason"].as_str().unwrap_or("end_turn");
+            return Ok(Self::empty_chunk(vec![choice(
+                empty_delta(),
+                Some(Self::map_stop_reason_to_universal(stop_reason)),
+            )]));
+        }
+
+        if let Some(metadata) = chunk.get("metadata") {
+            let mut chunk = Self::empty_chunk(vec![]);
+            if let Some(usage) = metadata.get("usage") {
+                let usage: BedrockUsage = serde_json::from_value(usage.clone())
+                    .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+                chunk.usage = Some(usage.to_universal());
+            }
+            return Ok(chunk);
+        }
+
+        // contentBlockStop and unknown events carry nothing to forward
+        Ok(Self::empty_chunk(vec![]))
+    }
+
+    fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
+        let Some(choice) = chunk.choices.first() else {
+            return Ok(serde_json::json!({"contentBlockStop": {"contentBlockIndex": 0}}));
+        };
+
+        if let Some(finish_reason) = &choice.finish_reason {
+            return Ok(serde_json::json!({
+                "messageStop": {"stopReason": Self::map_stop_reason_from_universal(finish_reason)}
+            }));
+        }
+
+        if let Some(call) = choice.delta.tool_calls.as_ref().and_then(|calls| calls.first()) {
+            let function = call.function.clone().unwrap_or(StreamFunctionCall {
+                name: None,
+                arguments: None,
+            });
+            if let (Some(id), Some(name)) = (&call.id, &function.name) {
+                return Ok(serde_json::json!({
+                    "contentBlockStart": {
+                        "contentBlockIndex": call.index,
+                        "start": {"toolUse": {"toolUseId": id, "name": name}}
+                    }
+                }));
+            }
+            return Ok(serde_json::json!({
+                "contentBlockDelta": {
+                    "contentBlockIndex": call.index,
+                    "delta": {"toolUse": {"input": function.arguments.unwrap_or_default()}}
+                }
+            }));
+        }
+
+        if let Some(content) = &choice.delta.content {
+            return Ok(serde_json::json!({
+                "contentBlockDelta": {
+                    "contentBlockIndex": choice.index,
+                    "delta": {"text": content}
+                }
+            }));
+        }
+
+        Ok(serde_json::json!({"messageStart": {"role": "assistant"}}))
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::AwsEventStream
+    }
+
+    /// Targets `/model/{modelId}/converse[-stream]` and signs the request with SigV4.
+    /// `api_base_url` may be left empty to use the regional `bedrock-runtime` endpoint.
+    fn build_upstream_request(&self, provider: &Provider, request: &ChatRequest) -> TransformerResult<UpstreamRequest> {
+        let region = self.region();
+        let base_url = if provider.api_base_url.is_empty() {
+            format!("https://bedrock-runtime.{}.amazonaws.com", region)
+        } else {
+            provider.api_base_url.trim_end_matches('/').to_string()
+        };
+        let action = if request.stream { "converse-stream" } else { "converse" };
+        let url = format!("{}/model/{}/{}", base_url, uri_encode(&request.model), action);
+
+        let mut upstream = UpstreamRequest::post(url, self.from_universal_request(request)?);
+        if request.stream {
+            upstream.set_header("accept", "application/vnd.amazon.eventstream");
+        }
+
+        let credentials = self.credentials()?;
+        SigV4Signer::new(&credentials, &region, "bedrock").sign(&mut upstream, chrono::Utc::now())?;
+        Ok(upstream)
+    }
+}
@@ -1,6 +1,12 @@
 use serde::{Deserialize, Serialize};
+use crate::config::types::Provider;
 use crate::transformers::error::{TransformerError, TransformerResult};
 use crate::transformers::providers::provider_trait::*;
+use crate::transformers::tool_names::ToolNameRules;
+use crate::transformers::upstream::UpstreamRequest;
+
+const JSON_MIME_TYPE: &str = "application/json";
+const PDF_MIME_TYPE: &str = "application/pdf";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiRequest {
@@ -10,6 +16,9 @@ struct GeminiRequest {
     tools: Option<Vec<GeminiTool>>,
     #[serde(rename = "toolConfig")]
     tool_config: Option<GeminiToolConfig>,
+    /// Fields not modeled above (`systemInstruction`, `safetySettings`, `cachedContent`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,11 +77,19 @@ struct GeminiGenerationConfig {
     max_output_tokens: Option<u32>,
     top_p: Option<f64>,
     top_k: Option<u32>,
+    #[serde(rename = "responseMimeType", default, skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
+    #[serde(rename = "responseSchema", default, skip_serializing_if = "Option::is_none")]
+    response_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiTool {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     function_declarations: Vec<GeminiFunctionDeclaration>,
+    /// Grounding with Google Search; the object carries no settings
+    #[serde(alias = "googleSearch", default, skip_serializing_if = "Option::is_none")]
+    google_search: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,12 +101,19 @@ struct GeminiFunctionDeclaration {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiToolConfig {
+    #[serde(rename = "functionCallingConfig", alias = "function_calling_config")]
     function_calling_config: GeminiFunctionCallingConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiFunctionCallingConfig {
     mode: String,
+    #[serde(
+        rename = "allowedFunctionNames",
+        alias = "allowed_function_names",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     allowed_function_names: Option<Vec<String>>,
 }
 
@@ -98,6 +122,9 @@ struct GeminiResponse {
     candidates: Vec<GeminiCandidate>,
     #[serde(rename = "usageMetadata")]
     usage_metadata: GeminiUsageMetadata,
+    /// Fields not modeled above (`modelVersion`, `responseId`, `promptFeedback`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,6 +134,51 @@ struct GeminiCandidate {
     finish_reason: Option<String>,
     index: u32,
     safety_ratings: Option<Vec<GeminiSafetyRating>>,
+    #[serde(rename = "groundingMetadata", default, skip_serializing_if = "Option::is_none")]
+    grounding_metadata: Option<GeminiGroundingMetadata>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGroundingMetadata {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    web_search_queries: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    grounding_chunks: Vec<GeminiGroundingChunk>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    grounding_supports: Vec<GeminiGroundingSupport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeminiGroundingChunk {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    web: Option<GeminiWebChunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeminiWebChunk {
+    uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGroundingSupport {
+    segment: GeminiSegment,
+    #[serde(default)]
+    grounding_chunk_indices: Vec<usize>,
+}
+
+/// Offsets are UTF-8 byte offsets into the candidate's text; `startIndex` is omitted when 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiSegment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_index: Option<usize>,
+    end_index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,11 +195,42 @@ struct GeminiUsageMetadata {
     candidates_token_count: u32,
     #[serde(rename = "totalTokenCount")]
     total_token_count: u32,
+    /// Part of `promptTokenCount`
+    #[serde(rename = "cachedContentTokenCount", default, skip_serializing_if = "Option::is_none")]
+    cached_content_token_count: Option<u32>,
+    /// Not part of `candidatesTokenCount`, but part of `totalTokenCount`
+    #[serde(rename = "thoughtsTokenCount", default, skip_serializing_if = "Option::is_none")]
+    thoughts_token_count: Option<u32>,
+}
+
+impl GeminiUsageMetadata {
+    fn to_universal(&self) -> Usage {
+        Usage {
+            prompt_tokens: self.prompt_token_count,
+            completion_tokens: self.candidates_token_count + self.thoughts_token_count.unwrap_or(0),
+            total_tokens: self.total_token_count,
+            cache_read_tokens: self.cached_content_token_count,
+            cache_creation_tokens: None,
+            reasoning_tokens: self.thoughts_token_count,
+        }
+    }
+
+    fn from_universal(usage: &Usage) -> Self {
+        Self {
+            prompt_token_count: usage.prompt_tokens,
+            candidates_token_count: usage.completion_tokens.saturating_sub(usage.reasoning_tokens.unwrap_or(0)),
+            total_token_count: usage.total_tokens,
+            cached_content_token_count: usage.cache_read_tokens,
+            thoughts_token_count: usage.reasoning_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct GeminiStreamChunk {
     candidates: Option<Vec<GeminiCandidate>>,
+    #[serde(rename = "usageMetadata", default, skip_serializing_if = "Option::is_none")]
+    usage_metadata: Option<GeminiUsageMetadata>,
 }
 
 pub struct GeminiTransformer;
@@ -146,6 +249,8 @@ impl GeminiTransformer {
                 tool_name: None,
                 tool_input: None,
                 image_url: None,
+                document: None,
+                cache_control: None,
             },
             GeminiPart::FunctionCall { function_call } => MessagePart {
                 part_type: "function_call".to_string(),
@@ -154,6 +259,8 @@ impl GeminiTransformer {
                 tool_name: Some(function_call.name.clone()),
                 tool_input: Some(function_call.args.clone()),
                 image_url: None,
+                document: None,
+                cache_control: None,
             },
             GeminiPart::FunctionResponse { function_response } => MessagePart {
                 part_type: "function_response".to_string(),
@@ -162,6 +269,26 @@ impl GeminiTransformer {
                 tool_name: Some(function_response.name.clone()),
                 tool_input: None,
                 image_url: None,
+                document: None,
+                cache_control: None,
+            },
+            GeminiPart::InlineData { inline_data } if inline_data.mime_type == PDF_MIME_TYPE => MessagePart {
+                part_type: "document".to_string(),
+                text: None,
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: None,
+                document: Some(Document {
+                    source: DocumentSource::Base64 {
+                        media_type: inline_data.mime_type.clone(),
+                        data: inline_data.data.clone(),
+                    },
+                    title: None,
+                    context: None,
+                    citations: None,
+                }),
+                cache_control: None,
             },
             GeminiPart::InlineData { inline_data } => MessagePart {
                 part_type: "image".to_string(),
@@ -173,6 +300,23 @@ impl GeminiTransformer {
                     url: format!("data:{};base64,{}", inline_data.mime_type, inline_data.data),
                     detail: None,
                 }),
+                document: None,
+                cache_control: None,
+            },
+            GeminiPart::FileData { file_data } if file_data.mime_type == PDF_MIME_TYPE => MessagePart {
+                part_type: "document".to_string(),
+                text: None,
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: None,
+                document: Some(Document {
+                    source: DocumentSource::Url { url: file_data.file_uri.clone() },
+                    title: None,
+                    context: None,
+                    citations: None,
+                }),
+                cache_control: None,
             },
             GeminiPart::FileData { file_data } => MessagePart {
                 part_type: "file".to_string(),
@@ -184,6 +328,8 @@ impl GeminiTransformer {
                     url: file_data.file_uri.clone(),
                     detail: None,
                 }),
+                document: None,
+                cache_control: None,
             },
             GeminiPart::Unknown => MessagePart {
                 part_type: "unknown".to_string(),
@@ -192,6 +338,8 @@ impl GeminiTransformer {
                 tool_name: None,
                 tool_input: None,
                 image_url: None,
+                document: None,
+                cache_control: None,
             },
         }).collect();
 
@@ -261,6 +409,26 @@ impl GeminiTransformer {
                             }
                             GeminiPart::Text { text: "[Image]".to_string() }
                         },
+                        "document" if part.document.is_some() => {
+                            let document = part.document.as_ref().unwrap();
+                            match &document.source {
+                                DocumentSource::Base64 { media_type, data } => GeminiPart::InlineData {
+                                    inline_data: GeminiInlineData {
+                                        mime_type: media_type.clone(),
+                                        data: data.clone(),
+                                    },
+                                },
+                                DocumentSource::Url { url } => GeminiPart::FileData {
+                                    file_data: GeminiFileData {
+                                        mime_type: PDF_MIME_TYPE.to_string(),
+                                        file_uri: url.clone(),
+                                    },
+                                },
+                                DocumentSource::Text { .. } | DocumentSource::Content { .. } => GeminiPart::Text {
+                                    text: document.fallback_text(),
+                                },
+                            }
+                        },
                         _ => GeminiPart::Text { 
                             text: part.text.clone().unwrap_or_default() 
                         },
@@ -299,34 +467,65 @@ impl GeminiTransformer {
 
         Ok(GeminiTool {
             function_declarations: function_declarations?,
+            google_search: None,
         })
     }
 
+    /// `ANY` restricted to a single function is how Gemini forces a specific tool. A longer
+    /// allow-list has no universal equivalent and becomes a plain `Required`.
     fn convert_tool_choice_to_universal(config: &GeminiToolConfig) -> TransformerResult<ToolChoice> {
-        match config.function_calling_config.mode.as_str() {
-            "AUTO" => Ok(ToolChoice::Auto("auto".to_string())),
-            "ANY" => Ok(ToolChoice::Required("any".to_string())),
-            "NONE" => Ok(ToolChoice::None("none".to_string())),
+        let config = &config.function_calling_config;
+        match (config.mode.as_str(), config.allowed_function_names.as_deref()) {
+            ("ANY", Some([name])) => Ok(ToolChoice::specific(name)),
+            ("ANY", _) => Ok(ToolChoice::Required("any".to_string())),
+            ("NONE", _) => Ok(ToolChoice::None("none".to_string())),
             _ => Ok(ToolChoice::Auto("auto".to_string())),
         }
     }
 
     fn convert_tool_choice_from_universal(choice: &ToolChoice) -> TransformerResult<GeminiToolConfig> {
-        let mode = match choice {
-            ToolChoice::Auto(_) => "AUTO",
-            ToolChoice::Required(_) => "ANY",
-            ToolChoice::None(_) => "NONE",
-            ToolChoice::Specific(_) => "AUTO",
+        let (mode, allowed_function_names) = match choice {
+            ToolChoice::Auto(_) => ("AUTO", None),
+            ToolChoice::Required(_) => ("ANY", None),
+            ToolChoice::None(_) => ("NONE", None),
+            ToolChoice::Specific(specific) => ("ANY", Some(vec![specific.function.name.clone()])),
         };
 
         Ok(GeminiToolConfig {
             function_calling_config: GeminiFunctionCallingConfig {
                 mode: mode.to_string(),
-                allowed_function_names: None,
+                allowed_function_names,
             },
         })
     }
 
+    /// `responseSchema` is an OpenAPI subset: JSON Schema keywords it does not know are
+    /// rejected, so they are dropped.
+    fn convert_schema_from_universal(schema: &serde_json::Value) -> serde_json::Value {
+        match schema {
+            serde_json::Value::Object(object) => object
+                .iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "$schema" | "$id" | "additionalProperties" | "strict"))
+                .map(|(key, value)| {
+                    let value = match key.as_str() {
+                        // Property names are not schema keywords
+                        "properties" => match value {
+                            serde_json::Value::Object(properties) => properties
+                                .iter()
+                                .map(|(name, property)| (name.clone(), Self::convert_schema_from_universal(property)))
+                                .collect(),
+                            other => other.clone(),
+                        },
+                        _ => Self::convert_schema_from_universal(value),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+            serde_json::Value::Array(items) => items.iter().map(Self::convert_schema_from_universal).collect(),
+            other => other.clone(),
+        }
+    }
+
     fn extract_tool_calls_from_parts(parts: &Vec<GeminiPart>) -> Vec<ToolCall> {
         parts.iter()
             .filter_map(|part| match part {
@@ -345,6 +544,99 @@ impl GeminiTransformer {
             .collect()
     }
 
+    fn convert_grounding_to_universal(metadata: &GeminiGroundingMetadata, text: &str) -> Option<WebSearchResults> {
+        let web_chunk = |index: usize| metadata.grounding_chunks.get(index).and_then(|chunk| chunk.web.as_ref());
+        let sources = metadata
+            .grounding_chunks
+            .iter()
+            .filter_map(|chunk| chunk.web.as_ref())
+            .map(|web| WebSource {
+                url: web.uri.clone(),
+                title: web.title.clone(),
+                page_age: None,
+            })
+            .collect();
+        let citations = metadata
+            .grounding_supports
+            .iter()
+            .flat_map(|support| {
+                let start_index = char_offset(text, support.segment.start_index.unwrap_or(0));
+                let end_index = char_offset(text, support.segment.end_index);
+                support
+                    .grounding_chunk_indices
+                    .iter()
+                    .filter_map(move |index| web_chunk(*index))
+                    .map(move |web| WebCitation {
+                        url: web.uri.clone(),
+                        title: web.title.clone(),
+                        start_index,
+                        end_index,
+                        cited_text: None,
+                        encrypted_index: None,
+                    })
+            })
+            .collect();
+        WebSearchResults {
+            queries: metadata.web_search_queries.clone(),
+            sources,
+            citations,
+        }
+        .non_empty()
+    }
+
+    /// Citations of the same span share one grounding support.
+    fn convert_grounding_from_universal(web_search: &WebSearchResults, text: &str) -> GeminiGroundingMetadata {
+        let mut grounding_chunks: Vec<GeminiGroundingChunk> = web_search
+            .sources
+            .iter()
+            .map(|source| GeminiGroundingChunk {
+                web: Some(GeminiWebChunk {
+                    uri: source.url.clone(),
+                    title: source.title.clone(),
+                }),
+            })
+            .collect();
+        let mut grounding_supports: Vec<GeminiGroundingSupport> = Vec::new();
+        for citation in &web_search.citations {
+            let chunk_index = match grounding_chunks
+                .iter()
+                .position(|chunk| chunk.web.as_ref().is_some_and(|web| web.uri == citation.url))
+            {
+                Some(index) => index,
+                None => {
+                    grounding_chunks.push(GeminiGroundingChunk {
+                        web: Some(GeminiWebChunk {
+                            uri: citation.url.clone(),
+                            title: citation.title.clone(),
+                        }),
+                    });
+                    grounding_chunks.len() - 1
+                }
+            };
+            let start = byte_offset(text, citation.start_index);
+            let end = byte_offset(text, citation.end_index);
+            match grounding_supports
+                .iter_mut()
+                .find(|support| support.segment.start_index.unwrap_or(0) == start && support.segment.end_index == end)
+            {
+                Some(support) => support.grounding_chunk_indices.push(chunk_index),
+                None => grounding_supports.push(GeminiGroundingSupport {
+                    segment: GeminiSegment {
+                        start_index: (start > 0).then_some(start),
+                        end_index: end,
+                        text: text.get(start..end).map(str::to_string),
+                    },
+                    grounding_chunk_indices: vec![chunk_index],
+                }),
+            }
+        }
+        GeminiGroundingMetadata {
+            web_search_queries: web_search.queries.clone(),
+            grounding_chunks,
+            grounding_supports,
+        }
+    }
+
     fn extract_text_from_parts(parts: &Vec<GeminiPart>) -> String {
         parts.iter()
             .filter_map(|part| match part {
@@ -355,6 +647,14 @@ impl GeminiTransformer {
     }
 }
 
+fn char_offset(text: &str, byte_offset: usize) -> usize {
+    text.get(..byte_offset).unwrap_or(text).chars().count()
+}
+
+fn byte_offset(text: &str, char_offset: usize) -> usize {
+    text.char_indices().nth(char_offset).map_or(text.len(), |(offset, _)| offset)
+}
+
 impl ProviderTransformer for GeminiTransformer {
     fn provider_name(&self) -> &'static str {
         "gemini"
@@ -371,10 +671,14 @@ impl ProviderTransformer for GeminiTransformer {
             .collect();
 
         let mut universal_tools = Vec::new();
+        let mut web_search = None;
         if let Some(gemini_tools) = &gemini_request.tools {
             for tool in gemini_tools {
                 let converted_tools = Self::convert_tool_to_universal(tool)?;
                 universal_tools.extend(converted_tools);
+                if tool.google_search.is_some() {
+                    web_search = Some(WebSearchOptions::default());
+                }
             }
         }
         let tools = if universal_tools.is_empty() { None } else { Some(universal_tools) };
@@ -389,17 +693,37 @@ impl ProviderTransformer for GeminiTransformer {
         max_output_tokens: None,
         top_p: None,
         top_k: None,
+        response_mime_type: None,
+        response_schema: None,
     });
 
+        let response_format = match generation_config.response_mime_type.as_deref() {
+            Some(JSON_MIME_TYPE) => Some(match &generation_config.response_schema {
+                Some(schema) => ResponseFormat::JsonSchema {
+                    name: "response".to_string(),
+                    description: None,
+                    schema: schema.clone(),
+                    strict: None,
+                },
+                None => ResponseFormat::JsonObject,
+            }),
+            _ => None,
+        };
+
         Ok(ChatRequest {
             model: "gemini".to_string(),
             messages: messages?,
             temperature: generation_config.temperature,
+            top_p: generation_config.top_p,
             max_tokens: generation_config.max_output_tokens,
             stream: false,
             tools,
             tool_choice,
-            provider_metadata: None,
+            parallel_tool_calls: None,
+            reasoning: None,
+            response_format,
+            web_search,
+            provider_metadata: passthrough_metadata(self.provider_name(), gemini_request.extra),
         })
     }
 
@@ -410,11 +734,17 @@ impl ProviderTransformer for GeminiTransformer {
             .map(|msg| Self::convert_content_from_universal(msg))
             .collect();
 
-        let tools = if let Some(universal_tools) = &request.tools {
+        let mut tools = if let Some(universal_tools) = &request.tools {
             Some(vec![Self::convert_tool_from_universal(universal_tools)?])
         } else {
             None
         };
+        if request.web_search.is_some() {
+            tools.get_or_insert_default().push(GeminiTool {
+                function_declarations: Vec::new(),
+                google_search: Some(serde_json::json!({})),
+            });
+        }
 
         let tool_config = request
             .tool_choice
@@ -425,8 +755,13 @@ impl ProviderTransformer for GeminiTransformer {
         let generation_config = GeminiGenerationConfig {
             temperature: request.temperature,
             max_output_tokens: request.max_tokens,
-            top_p: None,
+            top_p: request.top_p,
             top_k: None,
+            response_mime_type: request.response_format.as_ref().map(|_| JSON_MIME_TYPE.to_string()),
+            response_schema: match &request.response_format {
+                Some(ResponseFormat::JsonSchema { schema, .. }) => Some(Self::convert_schema_from_universal(schema)),
+                _ => None,
+            },
         };
 
         let gemini_request = GeminiRequest {
@@ -434,6 +769,7 @@ impl ProviderTransformer for GeminiTransformer {
             generation_config: Some(generation_config),
             tools,
             tool_config,
+            extra: passthrough_fields(&request.provider_metadata, self.provider_name()),
         };
 
         serde_json::to_value(gemini_request)
@@ -452,11 +788,16 @@ impl ProviderTransformer for GeminiTransformer {
 
         let finish_reason = candidate.finish_reason.clone().unwrap_or("STOP".to_string());
 
+        let web_search = candidate.grounding_metadata.as_ref().and_then(|metadata| {
+            Self::convert_grounding_to_universal(metadata, &Self::extract_text_from_parts(&candidate.content.parts))
+        });
+
         let choice = Choice {
             index: candidate.index,
             message,
             finish_reason,
             tool_calls,
+            web_search,
         };
 
         Ok(ChatResponse {
@@ -465,12 +806,8 @@ impl ProviderTransformer for GeminiTransformer {
             created: chrono::Utc::now().timestamp() as u64,
             model: "gemini".to_string(),
             choices: vec![choice],
-            usage: Usage {
-                prompt_tokens: gemini_response.usage_metadata.prompt_token_count,
-                completion_tokens: gemini_response.usage_metadata.candidates_token_count,
-                total_tokens: gemini_response.usage_metadata.total_token_count,
-            },
-            provider_metadata: None,
+            usage: gemini_response.usage_metadata.to_universal(),
+            provider_metadata: passthrough_metadata(self.provider_name(), gemini_response.extra),
         })
     }
 
@@ -483,20 +820,22 @@ impl ProviderTransformer for GeminiTransformer {
             _ => None,
         };
 
+        let grounding_metadata = response.choices[0].web_search.as_ref().map(|web_search| {
+            Self::convert_grounding_from_universal(web_search, &Self::extract_text_from_parts(&message.parts))
+        });
+
         let candidate = GeminiCandidate {
             content: message,
             finish_reason,
             index: response.choices[0].index,
             safety_ratings: None,
+            grounding_metadata,
         };
 
         let gemini_response = GeminiResponse {
             candidates: vec![candidate],
-            usage_metadata: GeminiUsageMetadata {
-                prompt_token_count: response.usage.prompt_tokens,
-                candidates_token_count: response.usage.completion_tokens,
-                total_token_count: response.usage.total_tokens,
-            },
+            usage_metadata: GeminiUsageMetadata::from_universal(&response.usage),
+            extra: passthrough_fields(&response.provider_metadata, self.provider_name()),
         };
 
         serde_json::to_value(gemini_response)
@@ -506,6 +845,7 @@ impl ProviderTransformer for GeminiTransformer {
     fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk> {
         let gemini_chunk: GeminiStreamChunk = serde_json::from_value(chunk.clone())
             .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+        let usage = gemini_chunk.usage_metadata.as_ref().map(GeminiUsageMetadata::to_universal);
 
         if let Some(candidates) = gemini_chunk.candidates {
             if !candidates.is_empty() {
@@ -532,6 +872,7 @@ impl ProviderTransformer for GeminiTransformer {
                     created: chrono::Utc::now().timestamp() as u64,
                     model: "gemini".to_string(),
                     choices: vec![choice],
+                    usage,
                     provider_metadata: None,
                 });
             }
@@ -543,14 +884,16 @@ impl ProviderTransformer for GeminiTransformer {
             created: chrono::Utc::now().timestamp() as u64,
             model: "gemini".to_string(),
             choices: vec![],
+            usage,
             provider_metadata: None,
         })
     }
 
     fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
-        let choice = &chunk.choices[0];
-        
-        if let Some(content) = &choice.delta.content {
+        // The final chunk may carry only usage
+        if let Some(choice) = chunk.choices.first()
+            && let Some(content) = &choice.delta.content
+        {
             let candidate = GeminiCandidate {
                 content: GeminiContent {
                     role: "model".to_string(),
@@ -559,10 +902,12 @@ impl ProviderTransformer for GeminiTransformer {
                 finish_reason: None,
                 index: choice.index,
                 safety_ratings: None,
+                grounding_metadata: None,
             };
 
             let gemini_chunk = GeminiStreamChunk {
                 candidates: Some(vec![candidate]),
+                usage_metadata: chunk.usage.as_ref().map(GeminiUsageMetadata::from_universal),
             };
 
             return serde_json::to_value(gemini_chunk)
@@ -571,9 +916,35 @@ impl ProviderTransformer for GeminiTransformer {
 
         let gemini_chunk = GeminiStreamChunk {
             candidates: None,
+            usage_metadata: chunk.usage.as_ref().map(GeminiUsageMetadata::from_universal),
         };
 
         serde_json::to_value(gemini_chunk)
             .map_err(|e| TransformerError::Serialization(e.to_string()))
     }
-}
\ No newline at end of file
+
+    /// The model and streaming mode are part of the URL; `api_base_url` may end before or
+    /// after `/models`, e.g. `https://generativelanguage.googleapis.com/v1beta`.
+    fn build_upstream_request(&self, provider: &Provider, request: &ChatRequest) -> TransformerResult<UpstreamRequest> {
+        let base_url = provider.api_base_url.trim_end_matches('/');
+        let base_url = base_url.strip_suffix("/models").unwrap_or(base_url);
+        let action = if request.stream { "streamGenerateContent?alt=sse" } else { "generateContent" };
+        let url = format!("{}/models/{}:{}", base_url, request.model, action);
+
+        let mut upstream = UpstreamRequest::post(url, self.from_universal_request(request)?);
+        upstream.set_header("x-goog-api-key", provider.api_key.clone());
+        Ok(upstream)
+    }
+
+    fn supports_documents(&self) -> bool {
+        true
+    }
+
+    fn tool_name_rules(&self) -> ToolNameRules {
+        ToolNameRules {
+            max_len: 64,
+            extra_chars: "_.-:",
+            letter_first: true,
+        }
+    }
+}
@@ -1,9 +1,19 @@
 pub mod openai;
 pub mod anthropic;
 pub mod gemini;
+pub mod openrouter;
+pub mod ollama;
+pub mod bedrock;
+pub mod azure;
+pub mod vertex;
 pub mod provider_trait;
 
 pub use openai::OpenAITransformer;
 pub use anthropic::AnthropicTransformer;
 pub use gemini::GeminiTransformer;
+pub use openrouter::OpenRouterTransformer;
+pub use ollama::OllamaTransformer;
+pub use bedrock::BedrockTransformer;
+pub use azure::AzureOpenAITransformer;
+pub use vertex::{VertexTokenProviders, VertexTransformer};
 pub use provider_trait::ProviderTransformer;
\ No newline at end of file
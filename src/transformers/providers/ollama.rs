@@ -0,0 +1,721 @@
+use serde::{Deserialize, Serialize};
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::identity;
+use crate::transformers::providers::provider_trait::*;
+use crate::transformers::stream::StreamFormat;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<OllamaTool>>,
+    stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaModelOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<bool>,
+    /// `"json"` or a JSON schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
+    /// Fields not modeled above (`system`, `template`, `raw`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OllamaMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    images: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<OllamaToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OllamaTool {
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: OllamaFunctionDefinition,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OllamaFunctionDefinition {
+    name: String,
+    #[serde(default)]
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct OllamaModelOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+/// Shape shared by the non-streaming response and every NDJSON stream line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OllamaResponse {
+    model: String,
+    created_at: Option<String>,
+    message: Option<OllamaMessage>,
+    done: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    done_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt_eval_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eval_count: Option<u32>,
+    /// Fields not modeled above (`total_duration`, `eval_duration`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Options accepted from `["ollama", {...}]` in a provider's `transformer.use` list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaOptions {
+    pub num_ctx: Option<u32>,
+    pub keep_alive: Option<serde_json::Value>,
+}
+
+pub struct OllamaTransformer {
+    options: OllamaOptions,
+}
+
+impl OllamaTransformer {
+    pub fn new() -> Self {
+        Self::with_options(OllamaOptions::default())
+    }
+
+    pub fn with_options(options: OllamaOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn from_options_value(options: &serde_json::Value) -> TransformerResult<Self> {
+        serde_json::from_value(options.clone())
+            .map(Self::with_options)
+            .map_err(|e| TransformerError::Configuration(e.to_string()))
+    }
+
+    /// Ollama does not assign ids to tool calls, but Anthropic clients need one to pair
+    /// each `tool_use` with its `tool_result`. `seed` is the response's `created_at`, or the
+    /// message's position in a request, so the same call always gets the same id.
+    fn tool_call_id(seed: &str, index: usize, name: &str) -> String {
+        identity::tool_call_id(seed, index, name)
+    }
+
+    fn parse_created(created_at: &Option<String>) -> u64 {
+        created_at
+            .as_ref()
+            .and_then(|ts| chrono::DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.timestamp() as u64)
+            .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64)
+    }
+
+    fn map_finish_reason(done_reason: Option<&str>, has_tool_calls: bool) -> String {
+        if has_tool_calls {
+            return "tool_calls".to_string();
+        }
+        match done_reason {
+            Some("length") => "length".to_string(),
+            _ => "stop".to_string(),
+        }
+    }
+
+    fn image_data_from_url(url: &str) -> Option<String> {
+        url.strip_prefix("data:")
+            .and_then(|rest| rest.split_once(";base64,"))
+            .map(|(_, data)| data.to_string())
+    }
+
+    /// Converts request messages. A `tool` message carries only `tool_name`, so it is paired
+    /// with the preceding assistant message's first unanswered call of that name.
+    fn convert_messages_to_universal(messages: &[OllamaMessage]) -> Vec<ChatMessage> {
+        let mut unanswered: Vec<(String, String)> = Vec::new();
+        messages
+            .iter()
+            .enumerate()
+            .map(|(position, msg)| {
+                if msg.role == "tool"
+                    && let Some(name) = &msg.tool_name
+                    && let Some(call) = unanswered.iter().position(|(call_name, _)| call_name == name)
+                {
+                    let (_, id) = unanswered.remove(call);
+                    return Self::convert_tool_result_to_universal(msg, id);
+                }
+
+                let message = Self::convert_message_to_universal(msg, &position.to_string());
+                if msg.role == "assistant" {
+                    unanswered = match &message.content {
+                        MessageContent::Parts(parts) => parts
+                            .iter()
+                            .filter(|part| part.part_type == "tool_use")
+                            .filter_map(|part| Some((part.tool_name.clone()?, part.tool_use_id.clone()?)))
+                            .collect(),
+                        MessageContent::Text(_) => Vec::new(),
+                    };
+                }
+                message
+            })
+            .collect()
+    }
+
+    fn convert_tool_result_to_universal(msg: &OllamaMessage, tool_use_id: String) -> ChatMessage {
+        ChatMessage {
+            role: "user".to_string(),
+            content: MessageContent::Parts(vec![MessagePart {
+                part_type: "tool_result".to_string(),
+                text: Some(msg.content.clone()),
+                tool_use_id: Some(tool_use_id),
+                tool_name: msg.tool_name.clone(),
+                tool_input: None,
+                image_url: None,
+                document: None,
+                cache_control: None,
+            }]),
+            name: None,
+        }
+    }
+
+    fn convert_message_to_universal(msg: &OllamaMessage, seed: &str) -> ChatMessage {
+        let images = msg.images.clone().unwrap_or_default();
+        let tool_calls = msg.tool_calls.clone().unwrap_or_default();
+
+        if images.is_empty() && tool_calls.is_empty() {
+            return ChatMessage {
+                role: msg.role.clone(),
+                content: MessageContent::Text(msg.content.clone()),
+                name: msg.tool_name.clone(),
+            };
+        }
+
+        let mut parts = Vec::new();
+        if !msg.content.is_empty() {
+            parts.push(MessagePart {
+                part_type: "text".to_string(),
+                text: Some(msg.content.clone()),
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: None,
+                document: None,
+                cache_control: None,
+            });
+        }
+        for image in images {
+            parts.push(MessagePart {
+                part_type: "image".to_string(),
+                text: None,
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: Some(ImageUrl {
+                    url: format!("data:image/png;base64,{}", image),
+                    detail: None,
+                }),
+                document: None,
+                cache_control: None,
+            });
+        }
+        for (index, call) in tool_calls.into_iter().enumerate() {
+            parts.push(MessagePart {
+                part_type: "tool_use".to_string(),
+                text: None,
+                tool_use_id: Some(Self::tool_call_id(seed, index, &call.function.name)),
+                tool_name: Some(call.function.name),
+                tool_input: Some(call.function.arguments),
+                image_url: None,
+                document: None,
+                cache_control: None,
+            });
+        }
+
+        ChatMessage {
+            role: msg.role.clone(),
+            content: MessageContent::Parts(parts),
+            name: None,
+        }
+    }
+
+    /// A single universal message may expand into several Ollama messages, because tool
+    /// results travel as separate `tool` role messages.
+    fn convert_message_from_universal(msg: &ChatMessage) -> Vec<OllamaMessage> {
+        let parts = match &msg.content {
+            MessageContent::Text(text) => {
+                return vec![OllamaMessage {
+                    role: msg.role.clone(),
+                    content: text.clone(),
+                    images: None,
+                    tool_calls: None,
+                    tool_name: msg.name.clone(),
+                    thinking: None,
+                }];
+            }
+            MessageContent::Parts(parts) => parts,
+        };
+
+        let mut messages = Vec::new();
+        let mut content = String::new();
+        let mut images = Vec::new();
+        let mut tool_calls = Vec::new();
+
+        for part in parts {
+            match part.part_type.as_str() {
+                "tool_use" | "function_call" => tool_calls.push(OllamaToolCall {
+                    function: OllamaFunctionCall {
+                        name: part.tool_name.clone().unwrap_or_default(),
+                        arguments: part.tool_input.clone().unwrap_or(serde_json::json!({})),
+                    },
+                }),
+                "tool_result" | "function_response" => messages.push(OllamaMessage {
+                    role: "tool".to_string(),
+                    content: part.text.clone().unwrap_or_default(),
+                    images: None,
+                    tool_calls: None,
+                    tool_name: part.tool_name.clone(),
+                    thinking: None,
+                }),
+                "image" => {
+                    if let Some(data) = part
+                        .image_url
+                        .as_ref()
+                        .and_then(|image| Self::image_data_from_url(&image.url))
+                    {
+                        images.push(data);
+                    }
+                }
+                _ => {
+                    if let Some(text) = &part.text {
+                        content.push_str(text);
+                    }
+                }
+            }
+        }
+
+        if !content.is_empty() || !images.is_empty() || !tool_calls.is_empty() || messages.is_empty() {
+            messages.push(OllamaMessage {
+                role: msg.role.clone(),
+                content,
+                images: if images.is_empty() { None } else { Some(images) },
+                tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+                tool_name: None,
+                thinking: None,
+            });
+        }
+
+        messages
+    }
+
+    fn convert_tool_from_universal(tool: &Tool) -> OllamaTool {
+        OllamaTool {
+            tool_type: "function".to_string(),
+            function: OllamaFunctionDefinition {
+                name: tool.function.name.clone(),
+                description: tool.function.description.clone(),
+                parameters: tool.function.parameters.clone(),
+            },
+        }
+    }
+
+    fn convert_tool_to_universal(tool: &OllamaTool) -> Tool {
+        Tool {
+            tool_type: tool.tool_type.clone(),
+            function: FunctionDefinition {
+                name: tool.function.name.clone(),
+                description: tool.function.description.clone(),
+                parameters: tool.function.parameters.clone(),
+            },
+        }
+    }
+
+    fn convert_tool_calls_to_universal(calls: &[OllamaToolCall], created_at: &Option<String>) -> Vec<ToolCall> {
+        let seed = created_at.as_deref().unwrap_or_default();
+        calls
+            .iter()
+            .enumerate()
+            .map(|(index, call)| ToolCall {
+                id: Some(Self::tool_call_id(seed, index, &call.function.name)),
+                tool_type: "function".to_string(),
+                function: FunctionCall {
+                    name: call.function.name.clone(),
+                    arguments: serde_json::to_string(&call.function.arguments).unwrap_or_default(),
+                },
+            })
+            .collect()
+    }
+
+    fn convert_tool_calls_from_universal(calls: &[ToolCall]) -> Vec<OllamaToolCall> {
+        calls
+            .iter()
+            .map(|call| OllamaToolCall {
+                function: OllamaFunctionCall {
+                    name: call.function.name.clone(),
+                    arguments: serde_json::from_str(&call.function.arguments)
+                        .unwrap_or(serde_json::json!({})),
+                },
+            })
+            .collect()
+    }
+}
+
+impl Default for OllamaTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderTransformer for OllamaTransformer {
+    fn provider_name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn to_universal_request(&self, request: &serde_json::Value) -> TransformerResult<ChatRequest> {
+        let ollama_request: OllamaRequest = serde_json::from_value(request.clone())
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+
+        let messages = Self::convert_messages_to_universal(&ollama_request.messages);
+
+        let tools = ollama_request
+            .tools
+            .map(|tools| tools.iter().map(Self::convert_tool_to_universal).collect());
+
+        let options = ollama_request.options.unwrap_or_default();
+
+        Ok(ChatRequest {
+            model: ollama_request.model,
+            messages,
+            temperature: options.temperature,
+            top_p: options.top_p,
+            max_tokens: options.num_predict,
+            // Ollama streams unless told otherwise
+            stream: ollama_request.stream.unwrap_or(true),
+            tools,
+            tool_choice: None,
+            parallel_tool_calls: None,
+            reasoning: ollama_request.think.map(|think| ReasoningConfig {
+                enabled: think,
+                budget_tokens: None,
+                effort: None,
+            }),
+            response_format: match ollama_request.format {
+                Some(serde_json::Value::String(format)) if format == "json" => Some(ResponseFormat::JsonObject),
+                Some(schema @ serde_json::Value::Object(_)) => Some(ResponseFormat::JsonSchema {
+                    name: "response".to_string(),
+                    description: None,
+                    schema,
+                    strict: None,
+                }),
+                _ => None,
+            },
+            web_search: None,
+            provider_metadata: passthrough_metadata(self.provider_name(), ollama_request.extra),
+        })
+    }
+
+    fn from_universal_request(&self, request: &ChatRequest) -> TransformerResult<serde_json::Value> {
+        let messages = request
+            .messages
+            .iter()
+            .flat_map(Self::convert_message_from_universal)
+            .collect();
+
+        let tools = request
+            .tools
+            .as_ref()
+            .map(|tools| tools.iter().map(Self::convert_tool_from_universal).collect());
+
+        let options = OllamaModelOptions {
+            num_ctx: self.options.num_ctx,
+            temperature: request.temperature,
+            top_p: request.top_p,
+            num_predict: request.max_tokens,
+        };
+
+        let ollama_request = OllamaRequest {
+            model: request.model.clone(),
+            messages,
+            tools,
+            stream: Some(request.stream),
+            options: Some(options),
+            keep_alive: self.options.keep_alive.clone(),
+            think: request.reasoning.as_ref().map(|reasoning| reasoning.enabled),
+            format: request.response_format.as_ref().map(|format| match format {
+                ResponseFormat::JsonObject => serde_json::json!("json"),
+                ResponseFormat::JsonSchema { schema, .. } => schema.clone(),
+            }),
+            extra: passthrough_fields(&request.provider_metadata, self.provider_name()),
+        };
+
+        serde_json::to_value(ollama_request)
+            .map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    fn to_universal_response(&self, response: &serde_json::Value) -> TransformerResult<ChatResponse> {
+        let ollama_response: OllamaResponse = serde_json::from_value(response.clone())
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+
+        let ollama_message = ollama_response.message.ok_or_else(|| {
+            TransformerError::InvalidFormat("Ollama response has no message".to_string())
+        })?;
+
+        let tool_calls = Self::convert_tool_calls_to_universal(
+            ollama_message.tool_calls.as_deref().unwrap_or_default(),
+            &ollama_response.created_at,
+        );
+        let finish_reason = Self::map_finish_reason(
+            ollama_response.done_reason.as_deref(),
+            !tool_calls.is_empty(),
+        );
+
+        // Rebuild the message from the same tool call ids so content and tool_calls agree
+        let mut parts = Vec::new();
+        if !ollama_message.content.is_empty() {
+            parts.push(MessagePart {
+                part_type: "text".to_string(),
+                text: Some(ollama_message.content.clone()),
+                tool_use_id: None,
+                tool_name: None,
+                tool_input: None,
+                image_url: None,
+                document: None,
+                cache_control: None,
+            });
+        }
+        for call in &tool_calls {
+            parts.push(MessagePart {
+                part_type: "tool_use".to_string(),
+                text: None,
+                tool_use_id: call.id.clone(),
+                tool_name: Some(call.function.name.clone()),
+                tool_input: serde_json::from_str(&call.function.arguments).ok(),
+                image_url: None,
+                document: None,
+                cache_control: None,
+            });
+        }
+
+        let prompt_tokens = ollama_response.prompt_eval_count.unwrap_or(0);
+        let completion_tokens = ollama_response.eval_count.unwrap_or(0);
+        let created = Self::parse_created(&ollama_response.created_at);
+
+        Ok(ChatResponse {
+            id: format!("ollama-{}", created),
+            object: "chat.completion".to_string(),
+            created,
+            model: ollama_response.model,
+            choices: vec![Choice {
+                index: 0,
+                message: ChatMessage {
+                    role: ollama_message.role,
+                    content: MessageContent::Parts(parts),
+                    name: None,
+                },
+                finish_reason,
+                tool_calls: if tool_calls.is_empty() { None } else { Some(tool_calls) },
+                web_search: None,
+            }],
+            usage: Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+                ..Default::default()
+            },
+            provider_metadata: passthrough_metadata(self.provider_name(), ollama_response.extra),
+        })
+    }
+
+    fn from_universal_response(&self, response: &ChatResponse) -> TransformerResult<serde_json::Value> {
+        let choice = &response.choices[0];
+        let mut message = Self::convert_message_from_universal(&choice.message)
+            .pop()
+            .unwrap_or(OllamaMessage {
+                role: "assistant".to_string(),
+                content: String::new(),
+                images: None,
+                tool_calls: None,
+                tool_name: None,
+                thinking: None,
+            });
+        if message.tool_calls.is_none() {
+            message.tool_calls = choice
+                .tool_calls
+                .as_ref()
+                .map(|calls| Self::convert_tool_calls_from_universal(calls));
+        }
+
+        let ollama_response = OllamaResponse {
+            model: response.model.clone(),
+            created_at: chrono::DateTime::from_timestamp(response.created as i64, 0)
+                .map(|ts| ts.to_rfc3339()),
+            message: Some(message),
+            done: true,
+            done_reason: Some(match choice.finish_reason.as_str() {
+                "length" | "max_tokens" => "length".to_string(),
+                _ => "stop".to_string(),
+            }),
+            prompt_eval_count: Some(response.usage.prompt_tokens),
+            eval_count: Some(response.usage.completion_tokens),
+            extra: passthrough_fields(&response.provider_metadata, self.provider_name()),
+        };
+
+        serde_json::to_value(ollama_response)
+            .map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk> {
+        let ollama_chunk: OllamaResponse = serde_json::from_value(chunk.clone())
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+
+        let message = ollama_chunk.message.as_ref();
+        let tool_calls = message
+            .and_then(|m| m.tool_calls.as_deref())
+            .map(|calls| Self::convert_tool_calls_to_universal(calls, &ollama_chunk.created_at))
+            .unwrap_or_default();
+
+        let delta = StreamDelta {
+            role: message.map(|m| m.role.clone()),
+            content: message
+                .map(|m| m.content.clone())
+                .filter(|content| !content.is_empty()),
+            tool_calls: if tool_calls.is_empty() {
+                None
+            } else {
+                Some(
+                    tool_calls
+                        .iter()
+                        .enumerate()
+                        .map(|(index, call)| StreamToolCall {
+                            index: index as u32,
+                            id: call.id.clone(),
+                            tool_type: Some("function".to_string()),
+                            function: Some(StreamFunctionCall {
+                                name: Some(call.function.name.clone()),
+                                arguments: Some(call.function.arguments.clone()),
+                            }),
+                        })
+                        .collect(),
+                )
+            },
+        };
+
+        let finish_reason = if ollama_chunk.done {
+            Some(Self::map_finish_reason(
+                ollama_chunk.done_reason.as_deref(),
+                !tool_calls.is_empty(),
+            ))
+        } else {
+            None
+        };
+
+        // Token counts are only reported on the final line
+        let usage = ollama_chunk.done.then(|| {
+            let prompt_tokens = ollama_chunk.prompt_eval_count.unwrap_or(0);
+            let completion_tokens = ollama_chunk.eval_count.unwrap_or(0);
+            Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens: prompt_tokens + completion_tokens,
+                cache_read_tokens: None,
+                cache_creation_tokens: None,
+                reasoning_tokens: None,
+            }
+        });
+
+        let created = Self::parse_created(&ollama_chunk.created_at);
+
+        Ok(ChatStreamChunk {
+            id: format!("ollama-{}", created),
+            object: "chat.completion.chunk".to_string(),
+            created,
+            model: ollama_chunk.model,
+            choices: vec![StreamChoice {
+                index: 0,
+                delta,
+                finish_reason,
+            }],
+            usage,
+            provider_metadata: None,
+        })
+    }
+
+    fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
+        let choice = chunk.choices.first();
+
+        let tool_calls = choice
+            .and_then(|c| c.delta.tool_calls.as_ref())
+            .map(|calls| {
+                calls
+                    .iter()
+                    .filter_map(|call| call.function.as_ref())
+                    .map(|function| OllamaToolCall {
+                        function: OllamaFunctionCall {
+                            name: function.name.clone().unwrap_or_default(),
+                            arguments: function
+                                .arguments
+                                .as_deref()
+                                .and_then(|args| serde_json::from_str(args).ok())
+                                .unwrap_or(serde_json::json!({})),
+                        },
+                    })
+                    .collect()
+            });
+
+        let finish_reason = choice.and_then(|c| c.finish_reason.clone());
+
+        let ollama_chunk = OllamaResponse {
+            model: chunk.model.clone(),
+            created_at: chrono::DateTime::from_timestamp(chunk.created as i64, 0)
+                .map(|ts| ts.to_rfc3339()),
+            message: Some(OllamaMessage {
+                role: "assistant".to_string(),
+                content: choice
+                    .and_then(|c| c.delta.content.clone())
+                    .unwrap_or_default(),
+                images: None,
+                tool_calls,
+                tool_name: None,
+                thinking: None,
+            }),
+            done: finish_reason.is_some(),
+            done_reason: finish_reason.map(|reason| match reason.as_str() {
+                "length" => "length".to_string(),
+                _ => "stop".to_string(),
+            }),
+            prompt_eval_count: chunk.usage.as_ref().map(|usage| usage.prompt_tokens),
+            eval_count: chunk.usage.as_ref().map(|usage| usage.completion_tokens),
+            extra: serde_json::Map::new(),
+        };
+
+        serde_json::to_value(ollama_chunk)
+            .map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Ndjson
+    }
+}
@@ -6,20 +6,112 @@ use crate::transformers::providers::provider_trait::*;
 struct OpenAIRequest {
     model: String,
     messages: Vec<OpenAIMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAITool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<OpenAIToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_tool_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<OpenAIResponseFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    web_search_options: Option<OpenAIWebSearchOptions>,
+    /// Fields not modeled above (`stop`, `seed`, `user`, `service_tier`, ...)
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAIResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: OpenAIJsonSchema },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIJsonSchema {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strict: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIWebSearchOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search_context_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_location: Option<OpenAIUserLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIUserLocation {
+    #[serde(rename = "type")]
+    location_type: String,
+    approximate: UserLocation,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct OpenAIMessage {
     role: String,
-    content: Option<String>,
+    content: Option<OpenAIContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tool_calls: Option<Vec<OpenAIToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    annotations: Option<Vec<OpenAIAnnotation>>,
+}
+
+/// A plain string, or an array of parts for messages with images
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum OpenAIContent {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAIContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIAnnotation {
+    #[serde(rename = "type")]
+    annotation_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url_citation: Option<OpenAIUrlCitation>,
+}
+
+/// `start_index` / `end_index` are character offsets into the message content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIUrlCitation {
+    start_index: usize,
+    end_index: usize,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    /// The cited page excerpt, added by OpenRouter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +171,8 @@ struct OpenAIResponse {
     model: String,
     choices: Vec<OpenAIChoice>,
     usage: OpenAIUsage,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +187,22 @@ struct OpenAIUsage {
     prompt_tokens: u32,
     completion_tokens: u32,
     total_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prompt_tokens_details: Option<OpenAIPromptTokensDetails>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completion_tokens_details: Option<OpenAICompletionTokensDetails>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAIPromptTokensDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cached_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpenAICompletionTokensDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +212,9 @@ struct OpenAIStreamChunk {
     created: u64,
     model: String,
     choices: Vec<OpenAIStreamChoice>,
+    /// Sent on a final chunk with no choices when `stream_options.include_usage` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage: Option<OpenAIUsage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,9 +253,56 @@ impl OpenAITransformer {
         Self
     }
 
+    fn convert_usage_to_universal(usage: &OpenAIUsage) -> Usage {
+        Usage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+            cache_read_tokens: usage.prompt_tokens_details.as_ref().and_then(|details| details.cached_tokens),
+            cache_creation_tokens: None,
+            reasoning_tokens: usage.completion_tokens_details.as_ref().and_then(|details| details.reasoning_tokens),
+        }
+    }
+
+    fn convert_usage_from_universal(usage: &Usage) -> OpenAIUsage {
+        OpenAIUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+            // OpenAI has no cache-write count: those tokens are already in prompt_tokens
+            prompt_tokens_details: usage
+                .cache_read_tokens
+                .map(|cached_tokens| OpenAIPromptTokensDetails { cached_tokens: Some(cached_tokens) }),
+            completion_tokens_details: usage
+                .reasoning_tokens
+                .map(|reasoning_tokens| OpenAICompletionTokensDetails { reasoning_tokens: Some(reasoning_tokens) }),
+        }
+    }
+
     fn convert_message_to_universal(msg: &OpenAIMessage) -> TransformerResult<ChatMessage> {
         let content = match msg.content.clone() {
-            Some(text) => MessageContent::Text(text),
+            Some(OpenAIContent::Text(text)) => MessageContent::Text(text),
+            Some(OpenAIContent::Parts(parts)) => MessageContent::Parts(
+                parts
+                    .into_iter()
+                    .map(|part| {
+                        let (part_type, text, image_url) = match part {
+                            OpenAIContentPart::Text { text } => ("text", Some(text), None),
+                            OpenAIContentPart::ImageUrl { image_url } => ("image", None, Some(image_url)),
+                        };
+                        MessagePart {
+                            part_type: part_type.to_string(),
+                            text,
+                            tool_use_id: None,
+                            tool_name: None,
+                            tool_input: None,
+                            image_url,
+                            document: None,
+                            cache_control: None,
+                        }
+                    })
+                    .collect(),
+            ),
             None => MessageContent::Text(String::new()),
         };
 
@@ -155,14 +315,18 @@ impl OpenAITransformer {
 
     fn convert_message_from_universal(msg: &ChatMessage) -> TransformerResult<OpenAIMessage> {
         let content = match &msg.content {
-            MessageContent::Text(text) => Some(text.clone()),
+            MessageContent::Text(text) => Some(OpenAIContent::Text(text.clone())),
+            // Images need the array form, with the text parts alongside them in order
+            MessageContent::Parts(parts) if parts.iter().any(|p| p.part_type == "image" && p.image_url.is_some()) => {
+                Some(OpenAIContent::Parts(parts.iter().filter_map(Self::convert_part_from_universal).collect()))
+            }
             MessageContent::Parts(parts) => {
                 let text_parts: Vec<String> = parts
                     .iter()
                     .filter(|p| p.part_type == "text" && p.text.is_some())
                     .map(|p| p.text.as_ref().unwrap().clone())
                     .collect();
-                Some(text_parts.join(""))
+                Some(OpenAIContent::Text(text_parts.join("")))
             }
         };
 
@@ -172,9 +336,19 @@ impl OpenAITransformer {
             name: msg.name.clone(),
             tool_calls: None,
             tool_call_id: None,
+            annotations: None,
         })
     }
 
+    /// The content-array entry for a universal part; `None` for parts OpenAI content cannot carry
+    fn convert_part_from_universal(part: &MessagePart) -> Option<OpenAIContentPart> {
+        match (part.part_type.as_str(), &part.text, &part.image_url) {
+            ("text", Some(text), _) => Some(OpenAIContentPart::Text { text: text.clone() }),
+            ("image", _, Some(image_url)) => Some(OpenAIContentPart::ImageUrl { image_url: image_url.clone() }),
+            _ => None,
+        }
+    }
+
     fn convert_tool_to_universal(tool: &OpenAITool) -> TransformerResult<Tool> {
         Ok(Tool {
             tool_type: tool.tool_type.clone(),
@@ -199,9 +373,10 @@ impl OpenAITransformer {
 
     fn convert_tool_choice_to_universal(choice: &OpenAIToolChoice) -> TransformerResult<ToolChoice> {
         match choice {
-            OpenAIToolChoice::Auto(_) => Ok(ToolChoice::Auto("auto".to_string())),
-            OpenAIToolChoice::None(_) => Ok(ToolChoice::None("none".to_string())),
-            OpenAIToolChoice::Required(_) => Ok(ToolChoice::Required("required".to_string())),
+            // Untagged: every mode string deserializes as `Auto`, so go by its value
+            OpenAIToolChoice::Auto(mode) | OpenAIToolChoice::None(mode) | OpenAIToolChoice::Required(mode) => {
+                Ok(ToolChoice::from_mode(mode))
+            }
             OpenAIToolChoice::Specific(spec) => Ok(ToolChoice::Specific(ToolChoiceSpecific {
                 choice_type: spec.choice_type.clone(),
                 function: FunctionChoice {
@@ -226,6 +401,100 @@ impl OpenAITransformer {
     }
 }
 
+impl OpenAITransformer {
+    fn convert_response_format_to_universal(format: OpenAIResponseFormat) -> Option<ResponseFormat> {
+        match format {
+            OpenAIResponseFormat::Text => None,
+            OpenAIResponseFormat::JsonObject => Some(ResponseFormat::JsonObject),
+            OpenAIResponseFormat::JsonSchema { json_schema } => Some(ResponseFormat::JsonSchema {
+                name: json_schema.name,
+                description: json_schema.description,
+                schema: json_schema.schema.unwrap_or_else(|| serde_json::json!({"type": "object"})),
+                strict: json_schema.strict,
+            }),
+        }
+    }
+
+    fn convert_response_format_from_universal(format: &ResponseFormat) -> OpenAIResponseFormat {
+        match format {
+            ResponseFormat::JsonObject => OpenAIResponseFormat::JsonObject,
+            ResponseFormat::JsonSchema { name, description, schema, strict } => OpenAIResponseFormat::JsonSchema {
+                json_schema: OpenAIJsonSchema {
+                    name: name.clone(),
+                    description: description.clone(),
+                    schema: Some(schema.clone()),
+                    strict: *strict,
+                },
+            },
+        }
+    }
+}
+
+impl OpenAITransformer {
+    fn convert_web_search_options_to_universal(options: OpenAIWebSearchOptions) -> WebSearchOptions {
+        WebSearchOptions {
+            user_location: options.user_location.map(|location| location.approximate),
+            search_context_size: options.search_context_size,
+            ..Default::default()
+        }
+    }
+
+    fn convert_web_search_options_from_universal(options: &WebSearchOptions) -> OpenAIWebSearchOptions {
+        OpenAIWebSearchOptions {
+            search_context_size: options.search_context_size.clone(),
+            user_location: options.user_location.clone().map(|approximate| OpenAIUserLocation {
+                location_type: "approximate".to_string(),
+                approximate,
+            }),
+        }
+    }
+
+    /// OpenAI reports neither queries nor a result list: the sources are the cited pages.
+    fn convert_annotations_to_universal(annotations: &[OpenAIAnnotation]) -> Option<WebSearchResults> {
+        let mut web_search = WebSearchResults::default();
+        for citation in annotations.iter().filter_map(|annotation| annotation.url_citation.as_ref()) {
+            if !web_search.sources.iter().any(|source| source.url == citation.url) {
+                web_search.sources.push(WebSource {
+                    url: citation.url.clone(),
+                    title: citation.title.clone(),
+                    page_age: None,
+                });
+            }
+            web_search.citations.push(WebCitation {
+                url: citation.url.clone(),
+                title: citation.title.clone(),
+                start_index: citation.start_index,
+                end_index: citation.end_index,
+                cited_text: citation.content.clone(),
+                encrypted_index: None,
+            });
+        }
+        web_search.non_empty()
+    }
+
+    fn convert_annotations_from_universal(web_search: &WebSearchResults) -> Option<Vec<OpenAIAnnotation>> {
+        if web_search.citations.is_empty() {
+            return None;
+        }
+        Some(
+            web_search
+                .citations
+                .iter()
+                .map(|citation| OpenAIAnnotation {
+                    annotation_type: "url_citation".to_string(),
+                    url_citation: Some(OpenAIUrlCitation {
+                        start_index: citation.start_index,
+                        end_index: citation.end_index,
+                        url: citation.url.clone(),
+                        title: citation.title.clone(),
+                        content: citation.cited_text.clone(),
+                    }),
+                })
+                .collect(),
+        )
+    }
+}
+
 impl ProviderTransformer for OpenAITransformer {
     fn provider_name(&self) -> &'static str {
         "openai"
@@ -260,11 +529,22 @@ impl ProviderTransformer for OpenAITransformer {
             model: openai_request.model,
             messages: messages?,
             temperature: openai_request.temperature,
+            top_p: openai_request.top_p,
             max_tokens: openai_request.max_tokens,
             stream: openai_request.stream.unwrap_or(false),
             tools,
             tool_choice,
-            provider_metadata: None,
+            parallel_tool_calls: openai_request.parallel_tool_calls,
+            reasoning: openai_request.reasoning_effort.map(|effort| ReasoningConfig {
+                enabled: effort != "none",
+                budget_tokens: None,
+                effort: Some(effort),
+            }),
+            response_format: openai_request.response_format.and_then(Self::convert_response_format_to_universal),
+            web_search: openai_request
+                .web_search_options
+                .map(Self::convert_web_search_options_to_universal),
+            provider_metadata: passthrough_metadata(self.provider_name(), openai_request.extra),
         })
     }
 
@@ -292,15 +572,37 @@ impl ProviderTransformer for OpenAITransformer {
             .map(|choice| Self::convert_tool_choice_from_universal(choice))
             .transpose()?;
 
-        let openai_request = OpenAIRequest {
+        let mut openai_request = OpenAIRequest {
             model: request.model.clone(),
             messages: messages?,
             temperature: request.temperature,
+            top_p: request.top_p,
             max_tokens: request.max_tokens,
             stream: Some(request.stream),
+            // OpenAI rejects `parallel_tool_calls` on requests without tools
+            parallel_tool_calls: request.parallel_tool_calls.filter(|_| tools.is_some()),
             tools,
             tool_choice,
+            reasoning_effort: request
+                .reasoning
+                .as_ref()
+                .and_then(|reasoning| reasoning.effort.clone()),
+            response_format: request
+                .response_format
+                .as_ref()
+                .map(Self::convert_response_format_from_universal),
+            web_search_options: request
+                .web_search
+                .as_ref()
+                .map(Self::convert_web_search_options_from_universal),
+            extra: passthrough_fields(&request.provider_metadata, self.provider_name()),
         };
+        // Streams only report token counts when asked to
+        if request.stream && !openai_request.extra.contains_key("stream_options") {
+            openai_request
+                .extra
+                .insert("stream_options".to_string(), serde_json::json!({"include_usage": true}));
+        }
 
         serde_json::to_value(openai_request)
             .map_err(|e| TransformerError::Serialization(e.to_string()))
@@ -331,6 +633,11 @@ impl ProviderTransformer for OpenAITransformer {
             message,
             finish_reason: openai_response.choices[0].finish_reason.clone(),
             tool_calls,
+            web_search: openai_response.choices[0]
+                .message
+                .annotations
+                .as_deref()
+                .and_then(Self::convert_annotations_to_universal),
         };
 
         Ok(ChatResponse {
@@ -339,19 +646,15 @@ impl ProviderTransformer for OpenAITransformer {
             created: openai_response.created,
             model: openai_response.model,
             choices: vec![choice],
-            usage: Usage {
-                prompt_tokens: openai_response.usage.prompt_tokens,
-                completion_tokens: openai_response.usage.completion_tokens,
-                total_tokens: openai_response.usage.total_tokens,
-            },
-            provider_metadata: None,
+            usage: Self::convert_usage_to_universal(&openai_response.usage),
+            provider_metadata: passthrough_metadata(self.provider_name(), openai_response.extra),
         })
     }
 
     fn from_universal_response(&self, response: &ChatResponse) -> TransformerResult<serde_json::Value> {
-        let message = Self::convert_message_from_universal(&response.choices[0].message.clone())?;
+        let mut message = Self::convert_message_from_universal(&response.choices[0].message.clone())?;
 
-        let _tool_calls = response.choices[0].tool_calls.clone().map(|calls| {
+        message.tool_calls = response.choices[0].tool_calls.clone().map(|calls| {
             calls
                 .into_iter()
                 .map(|call| OpenAIToolCall {
@@ -364,6 +667,10 @@ impl ProviderTransformer for OpenAITransformer {
                 })
                 .collect::<Vec<OpenAIToolCall>>()
         });
+        message.annotations = response.choices[0]
+            .web_search
+            .as_ref()
+            .and_then(Self::convert_annotations_from_universal);
 
         let choice = OpenAIChoice {
             index: response.choices[0].index,
@@ -377,11 +684,8 @@ impl ProviderTransformer for OpenAITransformer {
             created: response.created,
             model: response.model.clone(),
             choices: vec![choice],
-            usage: OpenAIUsage {
-                prompt_tokens: response.usage.prompt_tokens,
-                completion_tokens: response.usage.completion_tokens,
-                total_tokens: response.usage.total_tokens,
-            },
+            usage: Self::convert_usage_from_universal(&response.usage),
+            extra: passthrough_fields(&response.provider_metadata, self.provider_name()),
         };
 
         serde_json::to_value(openai_response)
@@ -392,75 +696,77 @@ impl ProviderTransformer for OpenAITransformer {
         let openai_chunk: OpenAIStreamChunk = serde_json::from_value(chunk.clone())
             .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
 
-        let choice = &openai_chunk.choices[0];
-        let delta = StreamDelta {
-            role: choice.delta.role.clone(),
-            content: choice.delta.content.clone(),
-            tool_calls: choice.delta.tool_calls.clone().map(|calls| {
-                calls
-                    .into_iter()
-                    .map(|call| StreamToolCall {
-                        index: call.index,
-                        id: call.id,
-                        tool_type: call.tool_type,
-                        function: call.function.map(|f| StreamFunctionCall {
-                            name: f.name,
-                            arguments: f.arguments,
-                        }),
-                    })
-                    .collect()
-            }),
-        };
-
-        let stream_choice = StreamChoice {
-            index: choice.index,
-            delta,
-            finish_reason: choice.finish_reason.clone(),
-        };
+        let choices = openai_chunk.choices.iter().map(|choice| {
+            let delta = StreamDelta {
+                role: choice.delta.role.clone(),
+                content: choice.delta.content.clone(),
+                tool_calls: choice.delta.tool_calls.clone().map(|calls| {
+                    calls
+                        .into_iter()
+                        .map(|call| StreamToolCall {
+                            index: call.index,
+                            id: call.id,
+                            tool_type: call.tool_type,
+                            function: call.function.map(|f| StreamFunctionCall {
+                                name: f.name,
+                                arguments: f.arguments,
+                            }),
+                        })
+                        .collect()
+                }),
+            };
+            StreamChoice {
+                index: choice.index,
+                delta,
+                finish_reason: choice.finish_reason.clone(),
+            }
+        });
 
         Ok(ChatStreamChunk {
             id: openai_chunk.id,
             object: openai_chunk.object,
             created: openai_chunk.created,
             model: openai_chunk.model,
-            choices: vec![stream_choice],
+            choices: choices.collect(),
+            usage: openai_chunk.usage.as_ref().map(Self::convert_usage_to_universal),
             provider_metadata: None,
         })
     }
 
     fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
-        let choice = &chunk.choices[0];
-        let delta = OpenAIStreamDelta {
-            role: choice.delta.role.clone(),
-            content: choice.delta.content.clone(),
-            tool_calls: choice.delta.tool_calls.clone().map(|calls| {
-                calls
-                    .into_iter()
-                    .map(|call| OpenAIStreamToolCall {
-                        index: call.index,
-                        id: call.id,
-                        tool_type: call.tool_type,
-                        function: call.function.map(|f| OpenAIStreamFunctionCall {
-                            name: f.name,
-                            arguments: f.arguments,
-                        }),
-                    })
-                    .collect()
-            }),
-        };
-
-        let openai_choice = OpenAIStreamChoice {
-            index: choice.index,
-            delta,
-            finish_reason: choice.finish_reason.clone(),
-        };
+        let choices = chunk.choices.iter().map(|choice| {
+            let delta = OpenAIStreamDelta {
+                role: choice.delta.role.clone(),
+                content: choice.delta.content.clone(),
+                tool_calls: choice.delta.tool_calls.clone().map(|calls| {
+                    calls
+                        .into_iter()
+                        .map(|call| OpenAIStreamToolCall {
+                            index: call.index,
+                            id: call.id,
+                            tool_type: call.tool_type,
+                            function: call.function.map(|f| OpenAIStreamFunctionCall {
+                                name: f.name,
+                                arguments: f.arguments,
+                            }),
+                        })
+                        .collect()
+                }),
+            };
+            OpenAIStreamChoice {
+                index: choice.index,
+                delta,
+                finish_reason: choice.finish_reason.clone(),
+            }
+        });
 
         let openai_chunk = OpenAIStreamChunk {
             id: chunk.id.clone(),
             object: chunk.object.clone(),
             created: chunk.created,
             model: chunk.model.clone(),
-            choices: vec![openai_choice],
+            choices: choices.collect(),
+            usage: chunk.usage.as_ref().map(Self::convert_usage_from_universal),
         };
 
         serde_json::to_value(openai_chunk)
@@ -0,0 +1,325 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::openai::OpenAITransformer;
+use crate::transformers::providers::provider_trait::*;
+
+const DEFAULT_REFERER: &str = "https://github.com/wyeeeee/CodeRoutic";
+const DEFAULT_TITLE: &str = "CodeRoutic";
+const WEB_PLUGIN: &str = "web";
+const ONLINE_SUFFIX: &str = ":online";
+
+/// Options accepted from `["openrouter", {...}]` in a provider's `transformer.use` list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenRouterOptions {
+    pub referer: Option<String>,
+    pub title: Option<String>,
+    pub provider: Option<OpenRouterProviderPreferences>,
+    pub reasoning: Option<OpenRouterReasoning>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenRouterProviderPreferences {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_fallbacks: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpenRouterReasoning {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRouterError {
+    pub code: serde_json::Value,
+    pub message: String,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OpenRouterErrorEnvelope {
+    error: OpenRouterError,
+}
+
+/// OpenRouter speaks the OpenAI chat completions format, so the body conversion is
+/// delegated to `OpenAITransformer` and only the OpenRouter extensions are layered on top.
+pub struct OpenRouterTransformer {
+    openai: OpenAITransformer,
+    options: OpenRouterOptions,
+}
+
+impl OpenRouterTransformer {
+    pub fn new() -> Self {
+        Self::with_options(OpenRouterOptions::default())
+    }
+
+    pub fn with_options(options: OpenRouterOptions) -> Self {
+        Self {
+            openai: OpenAITransformer::new(),
+            options,
+        }
+    }
+
+    pub fn from_options_value(options: &serde_json::Value) -> TransformerResult<Self> {
+        serde_json::from_value(options.clone())
+            .map(Self::with_options)
+            .map_err(|e| TransformerError::Configuration(e.to_string()))
+    }
+
+    pub fn parse_error(body: &serde_json::Value) -> Option<OpenRouterError> {
+        serde_json::from_value::<OpenRouterErrorEnvelope>(body.clone())
+            .ok()
+            .map(|envelope| envelope.error)
+    }
+
+    fn check_error(body: &serde_json::Value) -> TransformerResult<()> {
+        match Self::parse_error(body) {
+            Some(error) => Err(TransformerError::ProviderError(format!(
+                "OpenRouter error {}: {}",
+                error.code, error.message
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn is_anthropic_model(model: &str) -> bool {
+        model.starts_with("anthropic/")
+    }
+
+    fn reasoning_for(&self, request: &ChatRequest) -> Option<OpenRouterReasoning> {
+        if let Some(reasoning) = &self.options.reasoning {
+            return Some(reasoning.clone());
+        }
+
+        request.reasoning.as_ref().map(|reasoning| {
+            if !reasoning.enabled {
+                return OpenRouterReasoning {
+                    enabled: Some(false),
+                    ..Default::default()
+                };
+            }
+            match (&reasoning.effort, reasoning.budget_tokens) {
+                (Some(effort), _) => OpenRouterReasoning {
+                    effort: Some(effort.clone()),
+                    ..Default::default()
+                },
+                (None, Some(budget)) => OpenRouterReasoning {
+                    max_tokens: Some(budget),
+                    ..Default::default()
+                },
+                (None, None) => OpenRouterReasoning {
+                    enabled: Some(true),
+                    ..Default::default()
+                },
+            }
+        })
+    }
+
+    /// Anthropic-backed models honour `cache_control` only on array-form content. Messages
+    /// with cache breakpoints get the marker on their matching content-array entries; text-only
+    /// messages, which OpenAI sends as a string, are first split back into their text parts.
+    fn apply_cache_control(messages: &mut [serde_json::Value], request: &ChatRequest) {
+        for (message, source) in messages.iter_mut().zip(&request.messages) {
+            let MessageContent::Parts(parts) = &source.content else {
+                continue;
+            };
+            if parts.iter().all(|part| part.cache_control.is_none()) {
+                continue;
+            }
+
+            // The entries OpenAI content carries, in order: text and images
+            let carried = parts.iter().filter(|part| {
+                matches!(
+                    (part.part_type.as_str(), &part.text, &part.image_url),
+                    ("text", Some(_), _) | ("image", _, Some(_))
+                )
+            });
+            if let Some(entries) = message["content"].as_array_mut() {
+                for (entry, part) in entries.iter_mut().zip(carried) {
+                    if let Some(cache_control) = &part.cache_control {
+                        entry["cache_control"] = cache_control.clone();
+                    }
+                }
+                continue;
+            }
+
+            let content: Vec<serde_json::Value> = carried
+                .map(|part| {
+                    let mut block = serde_json::json!({
+                        "type": "text",
+                        "text": part.text.clone().unwrap_or_default(),
+                    });
+                    if let Some(cache_control) = &part.cache_control {
+                        block["cache_control"] = cache_control.clone();
+                    }
+                    block
+                })
+                .collect();
+            message["content"] = serde_json::Value::Array(content);
+        }
+    }
+
+    fn collect_metadata(body: &serde_json::Value) -> Option<HashMap<String, serde_json::Value>> {
+        let mut metadata = HashMap::new();
+        if let Some(cost) = body.pointer("/usage/cost") {
+            metadata.insert("cost".to_string(), cost.clone());
+        }
+        if let Some(cost_details) = body.pointer("/usage/cost_details") {
+            metadata.insert("cost_details".to_string(), cost_details.clone());
+        }
+        if let Some(provider) = body.get("provider") {
+            metadata.insert("provider".to_string(), provider.clone());
+        }
+        if metadata.is_empty() { None } else { Some(metadata) }
+    }
+}
+
+impl Default for OpenRouterTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderTransformer for OpenRouterTransformer {
+    fn provider_name(&self) -> &'static str {
+        "openrouter"
+    }
+
+    fn to_universal_request(&self, request: &serde_json::Value) -> TransformerResult<ChatRequest> {
+        let mut universal = self.openai.to_universal_request(request)?;
+        if universal.reasoning.is_none()
+            && let Some(reasoning) = request.get("reasoning")
+        {
+            let reasoning: OpenRouterReasoning = serde_json::from_value(reasoning.clone())
+                .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+            universal.reasoning = Some(ReasoningConfig {
+                enabled: reasoning.enabled.unwrap_or(true),
+                budget_tokens: reasoning.max_tokens,
+                effort: reasoning.effort,
+            });
+        }
+        let web_plugin = request
+            .get("plugins")
+            .and_then(|plugins| plugins.as_array())
+            .is_some_and(|plugins| plugins.iter().any(|plugin| plugin["id"] == WEB_PLUGIN));
+        if universal.web_search.is_none() && (web_plugin || universal.model.ends_with(ONLINE_SUFFIX)) {
+            universal.web_search = Some(WebSearchOptions::default());
+        }
+        // `reasoning` is modeled now; keeping it as a pass-through field would undo hooks
+        if let Some(fields) = universal
+            .provider_metadata
+            .as_mut()
+            .and_then(|metadata| metadata.get_mut("openai"))
+            .and_then(|fields| fields.as_object_mut())
+        {
+            fields.remove("reasoning");
+        }
+        Ok(universal)
+    }
+
+    fn from_universal_request(&self, request: &ChatRequest) -> TransformerResult<serde_json::Value> {
+        let mut body = self.openai.from_universal_request(request)?;
+
+        // OpenRouter takes a `reasoning` object instead of `reasoning_effort`, and enables
+        // search through the web plugin below rather than an empty `web_search_options`
+        if let Some(object) = body.as_object_mut() {
+            object.remove("reasoning_effort");
+            if object.get("web_search_options").is_some_and(|options| options.as_object().is_some_and(|options| options.is_empty())) {
+                object.remove("web_search_options");
+            }
+        }
+        if let Some(reasoning) = self.reasoning_for(request) {
+            body["reasoning"] = serde_json::to_value(reasoning)
+                .map_err(|e| TransformerError::Serialization(e.to_string()))?;
+        }
+        if let Some(provider) = &self.options.provider {
+            body["provider"] = serde_json::to_value(provider)
+                .map_err(|e| TransformerError::Serialization(e.to_string()))?;
+        }
+        // An `:online` model searches on its own; a pass-through web plugin keeps its settings
+        if request.web_search.is_some() && !request.model.ends_with(ONLINE_SUFFIX) {
+            let plugin = serde_json::json!({"id": WEB_PLUGIN});
+            match body.get_mut("plugins").and_then(|plugins| plugins.as_array_mut()) {
+                Some(plugins) if plugins.iter().any(|plugin| plugin["id"] == WEB_PLUGIN) => {}
+                Some(plugins) => plugins.push(plugin),
+                None => body["plugins"] = serde_json::json!([plugin]),
+            }
+        }
+        if Self::is_anthropic_model(&request.model)
+            && let Some(messages) = body.get_mut("messages").and_then(|m| m.as_array_mut())
+        {
+            Self::apply_cache_control(messages, request);
+        }
+
+        Ok(body)
+    }
+
+    fn to_universal_response(&self, response: &serde_json::Value) -> TransformerResult<ChatResponse> {
+        Self::check_error(response)?;
+        let mut universal = self.openai.to_universal_response(response)?;
+        if let Some(metadata) = Self::collect_metadata(response) {
+            universal.provider_metadata.get_or_insert_default().extend(metadata);
+        }
+        Ok(universal)
+    }
+
+    fn from_universal_response(&self, response: &ChatResponse) -> TransformerResult<serde_json::Value> {
+        self.openai.from_universal_response(response)
+    }
+
+    fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk> {
+        Self::check_error(chunk)?;
+
+        // The trailing usage chunk has an empty `choices` array; other chunks without choices carry nothing to map
+        let has_choices = chunk
+            .get("choices")
+            .and_then(|choices| choices.as_array())
+            .is_some_and(|choices| !choices.is_empty());
+        let has_usage = chunk.get("usage").is_some_and(|usage| !usage.is_null());
+        let mut universal = if has_choices || has_usage {
+            self.openai.to_universal_stream_chunk(chunk)?
+        } else {
+            ChatStreamChunk {
+                id: chunk["id"].as_str().unwrap_or_default().to_string(),
+                object: chunk["object"].as_str().unwrap_or("chat.completion.chunk").to_string(),
+                created: chunk["created"].as_u64().unwrap_or_default(),
+                model: chunk["model"].as_str().unwrap_or_default().to_string(),
+                choices: vec![],
+                usage: None,
+                provider_metadata: None,
+            }
+        };
+        if let Some(metadata) = Self::collect_metadata(chunk) {
+            universal.provider_metadata.get_or_insert_default().extend(metadata);
+        }
+        Ok(universal)
+    }
+
+    fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
+        self.openai.from_universal_stream_chunk(chunk)
+    }
+
+    fn request_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        headers.insert(
+            "HTTP-Referer".to_string(),
+            self.options.referer.clone().unwrap_or_else(|| DEFAULT_REFERER.to_string()),
+        );
+        headers.insert(
+            "X-Title".to_string(),
+            self.options.title.clone().unwrap_or_else(|| DEFAULT_TITLE.to_string()),
+        );
+        headers
+    }
+}
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::config::types::Provider;
 use crate::transformers::error::TransformerResult;
+use crate::transformers::stream::StreamFormat;
+use crate::transformers::tool_names::ToolNameRules;
+use crate::transformers::upstream::UpstreamRequest;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -26,14 +30,77 @@ pub struct MessagePart {
     pub tool_name: Option<String>,
     pub tool_input: Option<serde_json::Value>,
     pub image_url: Option<ImageUrl>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document: Option<Document>,
+    pub cache_control: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageUrl {
     pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
 }
 
+/// A document attached to a message (part type `document`), typically a PDF. Providers
+/// that cannot read documents get `fallback_text` instead, or reject the request, as set
+/// by the model's `document_fallback` capability.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub source: DocumentSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Whether the model should cite passages of the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub citations: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DocumentSource {
+    /// Encoded file contents, e.g. `application/pdf`
+    Base64 { media_type: String, data: String },
+    /// Plain text contents
+    Text { media_type: String, data: String },
+    Url { url: String },
+    /// Anthropic's custom content source: a string or a list of text blocks
+    Content { content: serde_json::Value },
+}
+
+impl Document {
+    /// Whether the source carries its own text, so `fallback_text` loses nothing.
+    pub fn has_text(&self) -> bool {
+        matches!(self.source, DocumentSource::Text { .. } | DocumentSource::Content { .. })
+    }
+
+    /// The document as plain text for models that cannot read it. Text sources are
+    /// inlined; binary sources are not extracted and become a placeholder.
+    pub fn fallback_text(&self) -> String {
+        let body = match &self.source {
+            DocumentSource::Text { data, .. } => data.clone(),
+            DocumentSource::Content { content } => match content {
+                serde_json::Value::String(text) => text.clone(),
+                serde_json::Value::Array(blocks) => blocks
+                    .iter()
+                    .filter_map(|block| block.get("text").and_then(|text| text.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => String::new(),
+            },
+            DocumentSource::Base64 { media_type, .. } => {
+                format!("[document omitted: model does not support {} input]", media_type)
+            }
+            DocumentSource::Url { url } => format!("[document: {}]", url),
+        };
+        match &self.title {
+            Some(title) => format!("{}\n\n{}", title, body),
+            None => body,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tool {
     #[serde(rename = "type")]
@@ -67,22 +134,139 @@ pub struct ChatRequest {
     pub model: String,
     pub messages: Vec<ChatMessage>,
     pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
     pub max_tokens: Option<u32>,
     pub stream: bool,
     pub tools: Option<Vec<Tool>>,
     pub tool_choice: Option<ToolChoice>,
+    /// `Some(false)` asks for at most one tool call per turn; `None` leaves the provider default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+    pub reasoning: Option<ReasoningConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_search: Option<WebSearchOptions>,
     pub provider_metadata: Option<HashMap<String, serde_json::Value>>,
 }
 
+/// Structured output requested by the client. Providers without a native mechanism
+/// emulate it (Anthropic forces a single tool whose input is the answer).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Any JSON object
+    JsonObject,
+    /// JSON matching `schema`
+    JsonSchema {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        schema: serde_json::Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        strict: Option<bool>,
+    },
+}
+
+impl ResponseFormat {
+    /// The schema the output must match; `JsonObject` accepts any object.
+    pub fn schema(&self) -> serde_json::Value {
+        match self {
+            ResponseFormat::JsonObject => serde_json::json!({"type": "object"}),
+            ResponseFormat::JsonSchema { schema, .. } => schema.clone(),
+        }
+    }
+}
+
+/// The provider's built-in web search (Anthropic's `web_search` server tool, OpenAI's
+/// `web_search_options`, Gemini's `google_search`, OpenRouter's web plugin). Options a
+/// provider has no equivalent for are dropped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebSearchOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_domains: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_domains: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_location: Option<UserLocation>,
+    /// OpenAI's `low` / `medium` / `high` hint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_context_size: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserLocation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReasoningConfig {
+    pub enabled: bool,
+    pub budget_tokens: Option<u32>,
+    pub effort: Option<String>,
+}
+
+/// Whether and which tools the model must call. The string carries the mode as the
+/// client sent it (`required` / `any`); deserialization picks the variant by mode, so
+/// `"none"` does not turn into `Auto`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
+#[serde(untagged, from = "RawToolChoice")]
 pub enum ToolChoice {
+    /// The model decides
     Auto(String),
+    /// At least one tool call, any tool
     Required(String),
+    /// No tool calls, although tools are defined
     None(String),
+    /// Exactly the named tool
     Specific(ToolChoiceSpecific),
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawToolChoice {
+    Mode(String),
+    Specific(ToolChoiceSpecific),
+}
+
+impl From<RawToolChoice> for ToolChoice {
+    fn from(raw: RawToolChoice) -> Self {
+        match raw {
+            RawToolChoice::Mode(mode) => ToolChoice::from_mode(&mode),
+            RawToolChoice::Specific(specific) => ToolChoice::Specific(specific),
+        }
+    }
+}
+
+impl ToolChoice {
+    /// Parses a mode string from any format (`auto`, `any`, `required`, `none`); unknown
+    /// modes fall back to `Auto`.
+    pub fn from_mode(mode: &str) -> Self {
+        match mode {
+            "any" | "required" => ToolChoice::Required(mode.to_string()),
+            "none" => ToolChoice::None(mode.to_string()),
+            _ => ToolChoice::Auto("auto".to_string()),
+        }
+    }
+
+    /// A choice forcing the tool `name`.
+    pub fn specific(name: &str) -> Self {
+        ToolChoice::Specific(ToolChoiceSpecific {
+            choice_type: "function".to_string(),
+            function: FunctionChoice { name: name.to_string() },
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolChoiceSpecific {
     #[serde(rename = "type")]
@@ -112,13 +296,73 @@ pub struct Choice {
     pub message: ChatMessage,
     pub finish_reason: String,
     pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_search: Option<WebSearchResults>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What the provider's web search did for a response: the queries it ran, the pages it
+/// found, and which spans of the answer cite them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebSearchResults {
+    pub queries: Vec<String>,
+    pub sources: Vec<WebSource>,
+    pub citations: Vec<WebCitation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebSource {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_age: Option<String>,
+}
+
+/// A span of the message text backed by a source. The indices are character (not byte)
+/// offsets into the message's text parts joined together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebCitation {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub start_index: usize,
+    pub end_index: usize,
+    /// The quoted source text, when the provider reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cited_text: Option<String>,
+    /// Anthropic's opaque reference, required when the citation is sent back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_index: Option<String>,
+}
+
+impl WebSearchResults {
+    /// `None` when nothing was searched or cited.
+    pub fn non_empty(self) -> Option<Self> {
+        if self.queries.is_empty() && self.sources.is_empty() && self.citations.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+/// Token counts, following OpenAI's accounting: `prompt_tokens` includes cached prompt
+/// tokens and `completion_tokens` includes reasoning tokens. The breakdowns are `None` when
+/// the provider does not report them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    /// Prompt tokens read from the provider's prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_tokens: Option<u32>,
+    /// Prompt tokens written to the provider's prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_tokens: Option<u32>,
+    /// Completion tokens spent on reasoning before the visible output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,6 +372,10 @@ pub struct ChatStreamChunk {
     pub created: u64,
     pub model: String,
     pub choices: Vec<StreamChoice>,
+    /// Token counts, on the chunks where the provider reports them. Anthropic splits them
+    /// over the first and last chunks; the others report them once, at the end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
     pub provider_metadata: Option<HashMap<String, serde_json::Value>>,
 }
 
@@ -160,6 +408,63 @@ pub struct StreamFunctionCall {
     pub arguments: Option<String>,
 }
 
+/// Records the top-level fields of a `provider` wire body that the universal format does
+/// not model (`service_tier`, `container`, ...) under `provider_metadata[provider]`.
+pub fn passthrough_metadata(provider: &str, fields: serde_json::Map<String, serde_json::Value>) -> Option<HashMap<String, serde_json::Value>> {
+    if fields.is_empty() {
+        return None;
+    }
+    Some(HashMap::from([(provider.to_string(), serde_json::Value::Object(fields))]))
+}
+
+/// The unmodeled fields recorded by `passthrough_metadata` for `provider`. Fields are only
+/// re-emitted into the format they came from; other formats get none.
+pub fn passthrough_fields(metadata: &Option<HashMap<String, serde_json::Value>>, provider: &str) -> serde_json::Map<String, serde_json::Value> {
+    metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(provider))
+        .and_then(|fields| fields.as_object())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Records the client headers in `names` under `provider_metadata["<provider>_headers"]`,
+/// next to the unmodeled body fields, so a same-format target can send them again.
+pub fn record_passthrough_headers(request: &mut ChatRequest, provider: &str, names: &[&str], headers: &HashMap<String, String>) {
+    let recorded: serde_json::Map<String, serde_json::Value> = names
+        .iter()
+        .filter_map(|name| Some((name.to_string(), serde_json::Value::String(headers.get(*name)?.clone()))))
+        .collect();
+    if !recorded.is_empty() {
+        request
+            .provider_metadata
+            .get_or_insert_with(HashMap::new)
+            .insert(format!("{}_headers", provider), serde_json::Value::Object(recorded));
+    }
+}
+
+/// The headers recorded by `record_passthrough_headers` for `provider`; other formats get none.
+pub fn passthrough_headers(metadata: &Option<HashMap<String, serde_json::Value>>, provider: &str) -> Vec<(String, String)> {
+    metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(&format!("{}_headers", provider)))
+        .and_then(|headers| headers.as_object())
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+        .collect()
+}
+
+/// Per-stream state for formats whose events depend on earlier chunks, such as Anthropic's
+/// numbered content blocks; see `ProviderTransformer::stream_encoder`.
+pub trait StreamEncoder: Send {
+    /// Like `from_universal_stream_chunk`, for the next chunk of this stream
+    fn encode(&mut self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value>;
+
+    /// The events still owed once the upstream stream ends, as an array
+    fn finish(&mut self) -> TransformerResult<serde_json::Value>;
+}
+
 pub trait ProviderTransformer: Send + Sync {
     fn provider_name(&self) -> &'static str;
     
@@ -173,11 +478,60 @@ pub trait ProviderTransformer: Send + Sync {
     
     fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk>;
     
+    /// Returns one event, or an array of events when the chunk maps to several in this
+    /// format (see `encode_sse_event`)
     fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value>;
     
+    fn request_headers(&self) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    /// Client request headers of this format that select upstream features, such as
+    /// `anthropic-beta`; they are re-sent only when the target has the same format.
+    fn passthrough_header_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Builds the HTTP request for `provider`. The default posts the converted body to
+    /// `api_base_url` with a bearer token; providers with their own URL scheme or
+    /// request signing override this.
+    fn build_upstream_request(&self, provider: &Provider, request: &ChatRequest) -> TransformerResult<UpstreamRequest> {
+        let mut upstream = UpstreamRequest::post(&provider.api_base_url, self.from_universal_request(request)?);
+        upstream.set_header("authorization", format!("Bearer {}", provider.api_key));
+        for (name, value) in self.request_headers() {
+            upstream.set_header(name, value);
+        }
+        for (name, value) in passthrough_headers(&request.provider_metadata, self.provider_name()) {
+            upstream.set_header(name, value);
+        }
+        Ok(upstream)
+    }
+
+    fn stream_format(&self) -> StreamFormat {
+        StreamFormat::Sse
+    }
+
+    /// State for encoding one client stream in this format; `None` when each chunk can be
+    /// encoded on its own by `from_universal_stream_chunk`.
+    fn stream_encoder(&self) -> Option<Box<dyn StreamEncoder>> {
+        None
+    }
+
+    /// Naming constraints for tool definitions; names that violate them are mangled per
+    /// request and restored in the response.
+    fn tool_name_rules(&self) -> ToolNameRules {
+        ToolNameRules::default()
+    }
+
     fn supports_tools(&self) -> bool {
         true
     }
+
+    /// Whether `document` parts are sent natively; otherwise they are replaced per the
+    /// model's `document_fallback` before conversion.
+    fn supports_documents(&self) -> bool {
+        false
+    }
     
     fn supports_streaming(&self) -> bool {
         true
@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use crate::config::types::Provider;
+use crate::transformers::auth::{ServiceAccountKey, ServiceAccountTokenProvider};
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::anthropic::AnthropicTransformer;
+use crate::transformers::providers::gemini::GeminiTransformer;
+use crate::transformers::providers::provider_trait::*;
+use crate::transformers::tool_names::ToolNameRules;
+use crate::transformers::upstream::UpstreamRequest;
+
+const DEFAULT_LOCATION: &str = "us-central1";
+const ANTHROPIC_VERTEX_VERSION: &str = "vertex-2023-10-16";
+const GOOGLE_PUBLISHER: &str = "google";
+const ANTHROPIC_PUBLISHER: &str = "anthropic";
+
+/// Options accepted from `["vertex", {...}]` in a provider's `transformer.use` list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VertexOptions {
+    /// Service-account JSON key; falls back to `GOOGLE_APPLICATION_CREDENTIALS`
+    pub credentials_path: Option<String>,
+    /// Defaults to the key file's `project_id`
+    pub project_id: Option<String>,
+    pub location: Option<String>,
+    /// Forces the publisher instead of inferring it from the model name
+    pub publisher: Option<String>,
+    /// Overrides the token endpoint from the key file
+    pub token_uri: Option<String>,
+}
+
+/// Credentials path and token endpoint override
+type TokenProviderKey = (String, Option<String>);
+
+/// Token providers shared by the Vertex instances a registry builds, keyed by credentials
+/// path and token endpoint. Chains are resolved per request, so without this every request
+/// would start with an empty token cache.
+#[derive(Default)]
+pub struct VertexTokenProviders {
+    providers: Mutex<HashMap<TokenProviderKey, Arc<ServiceAccountTokenProvider>>>,
+}
+
+impl VertexTokenProviders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The provider for the key at `credentials_path`, loading the key on first use.
+    pub fn get_or_load(&self, credentials_path: &str, token_uri: Option<&str>) -> TransformerResult<Arc<ServiceAccountTokenProvider>> {
+        let key = (credentials_path.to_string(), token_uri.map(str::to_string));
+        let mut providers = self.providers.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(provider) = providers.get(&key) {
+            return Ok(provider.clone());
+        }
+        let provider = ServiceAccountTokenProvider::new(ServiceAccountKey::from_file(credentials_path)?);
+        let provider = Arc::new(match token_uri {
+            Some(token_uri) => provider.with_token_uri(token_uri),
+            None => provider,
+        });
+        providers.insert(key, provider.clone());
+        Ok(provider)
+    }
+}
+
+/// Vertex AI hosts Gemini under the `google` publisher and Claude under `anthropic`.
+/// Bodies are produced by `GeminiTransformer` / `AnthropicTransformer`; this type adds
+/// the Vertex URL layout and service-account OAuth.
+pub struct VertexTransformer {
+    gemini: GeminiTransformer,
+    anthropic: AnthropicTransformer,
+    options: VertexOptions,
+    token_provider: Option<Arc<ServiceAccountTokenProvider>>,
+}
+
+impl VertexTransformer {
+    /// Uses the key named by `GOOGLE_APPLICATION_CREDENTIALS`, if it is set and loads.
+    pub fn new() -> Self {
+        Self::new_shared(&VertexTokenProviders::new())
+    }
+
+    /// Like `new`, with the token provider taken from `tokens`.
+    pub fn new_shared(tokens: &VertexTokenProviders) -> Self {
+        Self::with_shared_tokens(VertexOptions::default(), tokens).unwrap_or_else(|e| {
+            eprintln!("Ignoring GOOGLE_APPLICATION_CREDENTIALS: {}", e);
+            Self::build(VertexOptions::default(), None)
+        })
+    }
+
+    /// Loads the service-account key named by the options, if any.
+    pub fn with_options(options: VertexOptions) -> TransformerResult<Self> {
+        Self::with_shared_tokens(options, &VertexTokenProviders::new())
+    }
+
+    /// Like `with_options`, but reuses the token provider `tokens` holds for the same key.
+    pub fn with_shared_tokens(options: VertexOptions, tokens: &VertexTokenProviders) -> TransformerResult<Self> {
+        let credentials_path = options
+            .credentials_path
+            .clone()
+            .or_else(|| env::var("GOOGLE_APPLICATION_CREDENTIALS").ok());
+        let token_provider = credentials_path
+            .map(|path| tokens.get_or_load(&path, options.token_uri.as_deref()))
+            .transpose()?;
+        Ok(Self::build(options, token_provider))
+    }
+
+    pub fn from_options_value(options: &serde_json::Value) -> TransformerResult<Self> {
+        Self::from_options_value_shared(options, &VertexTokenProviders::new())
+    }
+
+    pub fn from_options_value_shared(options: &serde_json::Value, tokens: &VertexTokenProviders) -> TransformerResult<Self> {
+        let options: VertexOptions = serde_json::from_value(options.clone())
+            .map_err(|e| TransformerError::Configuration(e.to_string()))?;
+        Self::with_shared_tokens(options, tokens)
+    }
+
+    fn build(options: VertexOptions, token_provider: Option<Arc<ServiceAccountTokenProvider>>) -> Self {
+        Self {
+            gemini: GeminiTransformer::new(),
+            anthropic: AnthropicTransformer::new(),
+            options,
+            token_provider,
+        }
+    }
+
+    pub fn publisher_for<'a>(&'a self, model: &str) -> &'a str {
+        if let Some(publisher) = &self.options.publisher {
+            return publisher;
+        }
+        if model.starts_with("claude") || model.starts_with(ANTHROPIC_PUBLISHER) {
+            ANTHROPIC_PUBLISHER
+        } else {
+            GOOGLE_PUBLISHER
+        }
+    }
+
+    pub fn location(&self) -> &str {
+        self.options.location.as_deref().unwrap_or(DEFAULT_LOCATION)
+    }
+
+    pub fn project_id(&self) -> TransformerResult<&str> {
+        self.options
+            .project_id
+            .as_deref()
+            .or_else(|| self.token_provider.as_ref()?.key().project_id.as_deref())
+            .ok_or_else(|| TransformerError::Configuration("Vertex project_id is not configured".to_string()))
+    }
+
+    pub fn endpoint_url(&self, provider: &Provider, request: &ChatRequest) -> TransformerResult<String> {
+        let location = self.location();
+        let base_url = if !provider.api_base_url.is_empty() {
+            provider.api_base_url.trim_end_matches('/').to_string()
+        } else if location == "global" {
+            "https://aiplatform.googleapis.com".to_string()
+        } else {
+            format!("https://{}-aiplatform.googleapis.com", location)
+        };
+
+        let publisher = self.publisher_for(&request.model);
+        let action = match (publisher, request.stream) {
+            (ANTHROPIC_PUBLISHER, true) => "streamRawPredict",
+            (ANTHROPIC_PUBLISHER, false) => "rawPredict",
+            (_, true) => "streamGenerateContent?alt=sse",
+            (_, false) => "generateContent",
+        };
+
+        Ok(format!(
+            "{}/v1/projects/{}/locations/{}/publishers/{}/models/{}:{}",
+            base_url,
+            self.project_id()?,
+            location,
+            publisher,
+            request.model,
+            action
+        ))
+    }
+
+    fn token_provider(&self) -> TransformerResult<&ServiceAccountTokenProvider> {
+        self.token_provider.as_deref().ok_or_else(|| {
+            TransformerError::Configuration("Vertex service account credentials are not configured".to_string())
+        })
+    }
+
+    /// Returns a valid access token, minting a new one when the cached token is missing or
+    /// about to expire.
+    pub async fn access_token(&self, client: &reqwest::Client) -> TransformerResult<String> {
+        self.token_provider()?.access_token(client).await
+    }
+
+    /// Refreshes the access token if needed and builds the authenticated upstream request.
+    pub async fn prepare_request(
+        &self,
+        client: &reqwest::Client,
+        provider: &Provider,
+        request: &ChatRequest,
+    ) -> TransformerResult<UpstreamRequest> {
+        let token = self.access_token(client).await?;
+        self.build_request(provider, request, &token)
+    }
+
+    fn build_request(&self, provider: &Provider, request: &ChatRequest, token: &str) -> TransformerResult<UpstreamRequest> {
+        let mut upstream = UpstreamRequest::post(
+            self.endpoint_url(provider, request)?,
+            self.from_universal_request(request)?,
+        );
+        upstream.set_header("authorization", format!("Bearer {}", token));
+        Ok(upstream)
+    }
+
+    fn is_gemini_body(value: &serde_json::Value) -> bool {
+        value.get("contents").is_some() || value.get("candidates").is_some()
+    }
+}
+
+impl Default for VertexTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderTransformer for VertexTransformer {
+    fn provider_name(&self) -> &'static str {
+        "vertex"
+    }
+
+    fn to_universal_request(&self, request: &serde_json::Value) -> TransformerResult<ChatRequest> {
+        if Self::is_gemini_body(request) {
+            return self.gemini.to_universal_request(request);
+        }
+
+        // Vertex carries the model in the URL, so Claude bodies omit it
+        let mut request = request.clone();
+        if let Some(object) = request.as_object_mut() {
+            object.remove("anthropic_version");
+            object.entry("model").or_insert_with(|| serde_json::json!(""));
+        }
+        self.anthropic.to_universal_request(&request)
+    }
+
+    fn from_universal_request(&self, request: &ChatRequest) -> TransformerResult<serde_json::Value> {
+        if self.publisher_for(&request.model) != ANTHROPIC_PUBLISHER {
+            return self.gemini.from_universal_request(request);
+        }
+
+        let mut body = self.anthropic.from_universal_request(request)?;
+        if let Some(object) = body.as_object_mut() {
+            object.remove("model");
+            object.insert("anthropic_version".to_string(), serde_json::json!(ANTHROPIC_VERTEX_VERSION));
+        }
+        Ok(body)
+    }
+
+    fn to_universal_response(&self, response: &serde_json::Value) -> TransformerResult<ChatResponse> {
+        if Self::is_gemini_body(response) {
+            self.gemini.to_universal_response(response)
+        } else {
+            self.anthropic.to_universal_response(response)
+        }
+    }
+
+    fn from_universal_response(&self, response: &ChatResponse) -> TransformerResult<serde_json::Value> {
+        if self.publisher_for(&response.model) == ANTHROPIC_PUBLISHER {
+            self.anthropic.from_universal_response(response)
+        } else {
+            self.gemini.from_universal_response(response)
+        }
+    }
+
+    fn to_universal_stream_chunk(&self, chunk: &serde_json::Value) -> TransformerResult<ChatStreamChunk> {
+        if Self::is_gemini_body(chunk) {
+            self.gemini.to_universal_stream_chunk(chunk)
+        } else {
+            self.anthropic.to_universal_stream_chunk(chunk)
+        }
+    }
+
+    fn from_universal_stream_chunk(&self, chunk: &ChatStreamChunk) -> TransformerResult<serde_json::Value> {
+        if self.publisher_for(&chunk.model) == ANTHROPIC_PUBLISHER {
+            self.anthropic.from_universal_stream_chunk(chunk)
+        } else {
+            self.gemini.from_universal_stream_chunk(chunk)
+        }
+    }
+
+    fn supports_documents(&self) -> bool {
+        true
+    }
+
+    /// Claude and Gemini rules combined, since the target publisher is not known here.
+    fn tool_name_rules(&self) -> ToolNameRules {
+        ToolNameRules {
+            letter_first: true,
+            ..ToolNameRules::default()
+        }
+    }
+
+    /// Uses the cached access token; callers that may need a refresh should go through
+    /// [`VertexTransformer::prepare_request`].
+    fn build_upstream_request(&self, provider: &Provider, request: &ChatRequest) -> TransformerResult<UpstreamRequest> {
+        let token = self
+            .token_provider()?
+            .cached_token(chrono::Utc::now())
+            .ok_or_else(|| {
+                TransformerError::Configuration(
+                    "No valid Vertex access token cached; refresh it with prepare_request".to_string(),
+                )
+            })?;
+        self.build_request(provider, request, &token)
+    }
+}
@@ -0,0 +1,228 @@
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::transformers::chain::{HookFactory, TransformerHook};
+use crate::transformers::error::{TransformerError, TransformerResult};
+use crate::transformers::providers::provider_trait::*;
+
+const REQUEST_FN: &str = "transform_request";
+const RESPONSE_FN: &str = "transform_response";
+const STREAM_CHUNK_FN: &str = "transform_stream_chunk";
+const NAME_FN: &str = "name";
+
+/// Resource limits applied to every script call. Rhai has no I/O of its own, so these plus
+/// the disabled `eval`/`import` are what keep a script from hanging or bloating the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptLimits {
+    /// Upper bound on interpreter operations per call (the CPU limit)
+    pub max_operations: u64,
+    pub max_call_levels: usize,
+    pub max_string_size: usize,
+    pub max_array_size: usize,
+    pub max_map_size: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            max_call_levels: 32,
+            max_string_size: 4 * 1024 * 1024,
+            max_array_size: 10_000,
+            max_map_size: 10_000,
+        }
+    }
+}
+
+/// A compiled user script, shared by every chain that uses it.
+///
+/// A script defines any of `transform_request(request, options)`,
+/// `transform_response(response, options)` and `transform_stream_chunk(chunk, options)`.
+/// Each receives the universal JSON body as a map and returns the (possibly modified) map;
+/// returning nothing leaves the body unchanged. An optional `name()` names the transformer,
+/// otherwise the file stem is used. Top-level statements are never run.
+pub struct ScriptModule {
+    name: String,
+    path: PathBuf,
+    engine: Engine,
+    ast: AST,
+    options: Value,
+}
+
+impl ScriptModule {
+    pub fn load(path: impl AsRef<Path>, options: Option<Value>) -> TransformerResult<Self> {
+        Self::load_with_limits(path, options, ScriptLimits::default())
+    }
+
+    pub fn load_with_limits(path: impl AsRef<Path>, options: Option<Value>, limits: ScriptLimits) -> TransformerResult<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            TransformerError::Configuration(format!("Failed to read transformer script {}: {}", path.display(), e))
+        })?;
+        let fallback_name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("script")
+            .to_string();
+        Self::compile(&source, fallback_name, path.to_path_buf(), options, limits)
+    }
+
+    pub fn compile(
+        source: &str,
+        fallback_name: impl Into<String>,
+        path: PathBuf,
+        options: Option<Value>,
+        limits: ScriptLimits,
+    ) -> TransformerResult<Self> {
+        let engine = Self::sandboxed_engine(&limits);
+        let ast = engine.compile(source).map_err(|e| {
+            TransformerError::Configuration(format!("Failed to compile transformer script {}: {}", path.display(), e))
+        })?;
+
+        let mut module = Self {
+            name: fallback_name.into(),
+            path,
+            engine,
+            ast,
+            options: options.unwrap_or_else(|| Value::Object(Default::default())),
+        };
+
+        if ![REQUEST_FN, RESPONSE_FN, STREAM_CHUNK_FN].iter().any(|name| module.defines(name)) {
+            return Err(TransformerError::Configuration(format!(
+                "Transformer script {} defines none of {}, {} or {}",
+                module.path.display(),
+                REQUEST_FN,
+                RESPONSE_FN,
+                STREAM_CHUNK_FN
+            )));
+        }
+        if module.defines_with_arity(NAME_FN, 0) {
+            let name: String = module
+                .engine
+                .call_fn_with_options(CallFnOptions::new().eval_ast(false), &mut Scope::new(), &module.ast, NAME_FN, ())
+                .map_err(|e| module.error(NAME_FN, e))?;
+            module.name = name;
+        }
+
+        Ok(module)
+    }
+
+    /// An engine with `limits` applied, no module loading and `eval`/`import` disabled.
+    pub(crate) fn sandboxed_engine(limits: &ScriptLimits) -> Engine {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(limits.max_operations)
+            .set_max_call_levels(limits.max_call_levels)
+            .set_max_string_size(limits.max_string_size)
+            .set_max_array_size(limits.max_array_size)
+            .set_max_map_size(limits.max_map_size)
+            .set_max_modules(0)
+            .set_module_resolver(DummyModuleResolver::new());
+        engine.disable_symbol("eval");
+        engine.disable_symbol("import");
+        engine.on_print(|_| {});
+        engine.on_debug(|_, _, _| {});
+        engine
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn defines(&self, function: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == function)
+    }
+
+    fn defines_with_arity(&self, function: &str, arity: usize) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == function && f.params.len() == arity)
+    }
+
+    fn error(&self, function: &str, error: impl std::fmt::Display) -> TransformerError {
+        TransformerError::Script(format!("{} ({}): {}: {}", self.name, self.path.display(), function, error))
+    }
+
+    /// Runs `function` over `body`. Returns `None` if the script does not define it or
+    /// returned nothing, meaning the body is unchanged.
+    fn call<T: Serialize + DeserializeOwned>(&self, function: &str, body: &T, options: &Value) -> TransformerResult<Option<T>> {
+        if !self.defines_with_arity(function, 2) {
+            return Ok(None);
+        }
+
+        let input = rhai::serde::to_dynamic(body).map_err(|e| self.error(function, e))?;
+        let options = rhai::serde::to_dynamic(options).map_err(|e| self.error(function, e))?;
+        let output: Dynamic = self
+            .engine
+            .call_fn_with_options(
+                CallFnOptions::new().eval_ast(false),
+                &mut Scope::new(),
+                &self.ast,
+                function,
+                (input, options),
+            )
+            .map_err(|e| self.error(function, e))?;
+
+        if output.is_unit() {
+            return Ok(None);
+        }
+        let output: Value = rhai::serde::from_dynamic(&output).map_err(|e| self.error(function, e))?;
+        serde_json::from_value(output)
+            .map(Some)
+            .map_err(|e| self.error(function, format!("invalid body returned: {}", e)))
+    }
+}
+
+/// One use of a script in a chain, with the options that apply to it.
+pub struct ScriptTransformer {
+    module: Arc<ScriptModule>,
+    options: Value,
+}
+
+impl ScriptTransformer {
+    /// Options from the `use` entry replace the ones given in `Config.transformers`.
+    pub fn new(module: Arc<ScriptModule>, options: Option<&Value>) -> Self {
+        let options = options.cloned().unwrap_or_else(|| module.options.clone());
+        Self { module, options }
+    }
+
+    pub fn factory(module: Arc<ScriptModule>) -> HookFactory {
+        Box::new(move |options| Ok(Box::new(Self::new(module.clone(), options))))
+    }
+}
+
+impl TransformerHook for ScriptTransformer {
+    fn name(&self) -> &str {
+        self.module.name()
+    }
+
+    fn transform_request(&self, request: &mut ChatRequest) -> TransformerResult<()> {
+        if let Some(output) = self.module.call(REQUEST_FN, request, &self.options)? {
+            *request = output;
+        }
+        Ok(())
+    }
+
+    fn transform_response(&self, response: &mut ChatResponse) -> TransformerResult<()> {
+        if let Some(output) = self.module.call(RESPONSE_FN, response, &self.options)? {
+            *response = output;
+        }
+        Ok(())
+    }
+
+    fn transform_stream_chunk(&self, chunk: &mut ChatStreamChunk) -> TransformerResult<()> {
+        if let Some(output) = self.module.call(STREAM_CHUNK_FN, chunk, &self.options)? {
+            *chunk = output;
+        }
+        Ok(())
+    }
+}
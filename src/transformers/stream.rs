@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use crate::transformers::error::{TransformerError, TransformerResult};
+
+/// Framing used by an upstream provider for streamed responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// `text/event-stream` with `data:` lines
+    Sse,
+    /// One JSON document per line (Ollama's native API)
+    Ndjson,
+    /// AWS `application/vnd.amazon.eventstream` binary frames (Bedrock ConverseStream)
+    AwsEventStream,
+}
+
+/// Incremental decoder for newline-delimited JSON bodies.
+///
+/// Network chunks do not respect line boundaries, so partial lines are buffered until
+/// the terminating newline arrives.
+#[derive(Debug, Default)]
+pub struct NdjsonDecoder {
+    buffer: Vec<u8>,
+}
+
+impl NdjsonDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<TransformerResult<serde_json::Value>> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut values = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            if let Some(value) = Self::decode_line(&line) {
+                values.push(value);
+            }
+        }
+        values
+    }
+
+    /// Decodes whatever is left once the body ends without a trailing newline.
+    pub fn finish(&mut self) -> Option<TransformerResult<serde_json::Value>> {
+        let line = std::mem::take(&mut self.buffer);
+        Self::decode_line(&line)
+    }
+
+    fn decode_line(line: &[u8]) -> Option<TransformerResult<serde_json::Value>> {
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            return None;
+        }
+        Some(
+            serde_json::from_slice(line)
+                .map_err(|e| TransformerError::Deserialization(e.to_string())),
+        )
+    }
+}
+
+/// Encodes one client-bound SSE event. Anthropic events are named after their `type`;
+/// events without one are sent as bare `data:` lines, as OpenAI clients expect. An array,
+/// as returned for a stream chunk that maps to several events, is sent one event per element.
+pub fn encode_sse_event(event: &serde_json::Value) -> String {
+    if let Some(events) = event.as_array() {
+        return events.iter().map(encode_sse_event).collect();
+    }
+    match event.get("type").and_then(|t| t.as_str()) {
+        Some(name) => format!("event: {}\ndata: {}\n\n", name, event),
+        None => format!("data: {}\n\n", event),
+    }
+}
+
+/// Frames are `total_len | headers_len | prelude_crc | headers | payload | message_crc`,
+/// all integers big-endian.
+const EVENT_STREAM_PRELUDE_LEN: usize = 12;
+const EVENT_STREAM_TRAILER_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventStreamHeaderValue {
+    Bool(bool),
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Bytes(Vec<u8>),
+    String(String),
+    Timestamp(i64),
+    Uuid([u8; 16]),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventStreamMessage {
+    pub headers: HashMap<String, EventStreamHeaderValue>,
+    pub payload: Vec<u8>,
+}
+
+impl EventStreamMessage {
+    pub fn header_str(&self, name: &str) -> Option<&str> {
+        match self.headers.get(name) {
+            Some(EventStreamHeaderValue::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Encodes the message into a single frame; only string headers are emitted.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        let mut names: Vec<_> = self.headers.keys().collect();
+        names.sort();
+        for name in names {
+            if let Some(EventStreamHeaderValue::String(value)) = self.headers.get(name) {
+                header_bytes.push(name.len() as u8);
+                header_bytes.extend_from_slice(name.as_bytes());
+                header_bytes.push(7);
+                header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+                header_bytes.extend_from_slice(value.as_bytes());
+            }
+        }
+
+        let total_len = EVENT_STREAM_PRELUDE_LEN
+            + header_bytes.len()
+            + self.payload.len()
+            + EVENT_STREAM_TRAILER_LEN;
+
+        let mut frame = Vec::with_capacity(total_len);
+        frame.extend_from_slice(&(total_len as u32).to_be_bytes());
+        frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        let prelude_crc = crc32fast::hash(&frame);
+        frame.extend_from_slice(&prelude_crc.to_be_bytes());
+        frame.extend_from_slice(&header_bytes);
+        frame.extend_from_slice(&self.payload);
+        let message_crc = crc32fast::hash(&frame);
+        frame.extend_from_slice(&message_crc.to_be_bytes());
+        frame
+    }
+}
+
+/// Incremental decoder for the AWS event-stream binary framing.
+#[derive(Debug, Default)]
+pub struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<TransformerResult<EventStreamMessage>> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut messages = Vec::new();
+        while self.buffer.len() >= EVENT_STREAM_PRELUDE_LEN {
+            let total_len = read_u32(&self.buffer, 0) as usize;
+            if total_len < EVENT_STREAM_PRELUDE_LEN + EVENT_STREAM_TRAILER_LEN {
+                // A corrupt length cannot be resynchronised, so drop the buffer
+                self.buffer.clear();
+                messages.push(Err(TransformerError::InvalidFormat(format!(
+                    "Invalid event-stream frame length: {}",
+                    total_len
+                ))));
+                break;
+            }
+            if self.buffer.len() < total_len {
+                break;
+            }
+            let frame: Vec<u8> = self.buffer.drain(..total_len).collect();
+            messages.push(Self::decode_frame(&frame));
+        }
+        messages
+    }
+
+    /// Bytes left over at end of body indicate a truncated frame.
+    pub fn finish(&mut self) -> Option<TransformerResult<EventStreamMessage>> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let remaining = std::mem::take(&mut self.buffer).len();
+        Some(Err(TransformerError::InvalidFormat(format!(
+            "Truncated event-stream frame: {} trailing bytes",
+            remaining
+        ))))
+    }
+
+    fn decode_frame(frame: &[u8]) -> TransformerResult<EventStreamMessage> {
+        let total_len = frame.len();
+        let headers_len = read_u32(frame, 4) as usize;
+        let prelude_crc = read_u32(frame, 8);
+        if crc32fast::hash(&frame[..8]) != prelude_crc {
+            return Err(TransformerError::InvalidFormat(
+                "Event-stream prelude checksum mismatch".to_string(),
+            ));
+        }
+        let message_crc = read_u32(frame, total_len - EVENT_STREAM_TRAILER_LEN);
+        if crc32fast::hash(&frame[..total_len - EVENT_STREAM_TRAILER_LEN]) != message_crc {
+            return Err(TransformerError::InvalidFormat(
+                "Event-stream message checksum mismatch".to_string(),
+            ));
+        }
+
+        let headers_end = EVENT_STREAM_PRELUDE_LEN + headers_len;
+        if headers_end > total_len - EVENT_STREAM_TRAILER_LEN {
+            return Err(TransformerError::InvalidFormat(
+                "Event-stream headers exceed frame length".to_string(),
+            ));
+        }
+
+        let headers = Self::decode_headers(&frame[EVENT_STREAM_PRELUDE_LEN..headers_end])?;
+        let payload = frame[headers_end..total_len - EVENT_STREAM_TRAILER_LEN].to_vec();
+        Ok(EventStreamMessage { headers, payload })
+    }
+
+    fn decode_headers(mut bytes: &[u8]) -> TransformerResult<HashMap<String, EventStreamHeaderValue>> {
+        let truncated = || TransformerError::InvalidFormat("Truncated event-stream header".to_string());
+        let mut headers = HashMap::new();
+
+        while !bytes.is_empty() {
+            let name_len = bytes[0] as usize;
+            let name = bytes.get(1..1 + name_len).ok_or_else(truncated)?;
+            let name = String::from_utf8_lossy(name).to_string();
+            bytes = &bytes[1 + name_len..];
+
+            let value_type = *bytes.first().ok_or_else(truncated)?;
+            bytes = &bytes[1..];
+
+            let (value, consumed) = match value_type {
+                0 => (EventStreamHeaderValue::Bool(true), 0),
+                1 => (EventStreamHeaderValue::Bool(false), 0),
+                2 => (EventStreamHeaderValue::Byte(*bytes.first().ok_or_else(truncated)? as i8), 1),
+                3 => {
+                    let raw = bytes.get(..2).ok_or_else(truncated)?;
+                    (EventStreamHeaderValue::Short(i16::from_be_bytes([raw[0], raw[1]])), 2)
+                }
+                4 => {
+                    let raw = bytes.get(..4).ok_or_else(truncated)?;
+                    (EventStreamHeaderValue::Int(i32::from_be_bytes(raw.try_into().unwrap())), 4)
+                }
+                5 | 8 => {
+                    let raw = bytes.get(..8).ok_or_else(truncated)?;
+                    let value = i64::from_be_bytes(raw.try_into().unwrap());
+                    if value_type == 5 {
+                        (EventStreamHeaderValue::Long(value), 8)
+                    } else {
+                        (EventStreamHeaderValue::Timestamp(value), 8)
+                    }
+                }
+                6 | 7 => {
+                    let raw = bytes.get(..2).ok_or_else(truncated)?;
+                    let len = u16::from_be_bytes([raw[0], raw[1]]) as usize;
+                    let data = bytes.get(2..2 + len).ok_or_else(truncated)?;
+                    if value_type == 6 {
+                        (EventStreamHeaderValue::Bytes(data.to_vec()), 2 + len)
+                    } else {
+                        (EventStreamHeaderValue::String(String::from_utf8_lossy(data).to_string()), 2 + len)
+                    }
+                }
+                9 => {
+                    let raw = bytes.get(..16).ok_or_else(truncated)?;
+                    (EventStreamHeaderValue::Uuid(raw.try_into().unwrap()), 16)
+                }
+                other => {
+                    return Err(TransformerError::InvalidFormat(format!(
+                        "Unknown event-stream header type: {}",
+                        other
+                    )));
+                }
+            };
+            bytes = &bytes[consumed..];
+            headers.insert(name, value);
+        }
+
+        Ok(headers)
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use crate::transformers::providers::anthropic::STRUCTURED_OUTPUT_TOOL;
+use crate::transformers::providers::provider_trait::*;
+
+/// Unwraps the tool call that emulates `response_format` on providers without a native
+/// structured-output mode, so the client sees the JSON text it asked for rather than a
+/// tool call. Only created for requests that set `response_format`: otherwise a tool of
+/// the same name is an ordinary tool. One instance serves one response stream.
+#[derive(Debug, Default)]
+pub struct StructuredOutput {
+    /// Tool-call index of the structured-output call, per choice index
+    stream_calls: BTreeMap<u32, u32>,
+}
+
+impl StructuredOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the structured-output call with its arguments as the message text.
+    pub fn unwrap_response(&self, response: &mut ChatResponse) {
+        for choice in &mut response.choices {
+            let arguments = choice
+                .tool_calls
+                .iter()
+                .flatten()
+                .find(|call| call.function.name == STRUCTURED_OUTPUT_TOOL)
+                .map(|call| call.function.arguments.clone());
+            if let Some(arguments) = arguments {
+                choice.message.content = MessageContent::Text(arguments);
+                choice.tool_calls = None;
+                choice.finish_reason = "stop".to_string();
+            }
+        }
+    }
+
+    /// Turns the structured-output call's argument fragments into text deltas as they
+    /// arrive, and its finish into a plain stop.
+    pub fn unwrap_stream_chunk(&mut self, chunk: &mut ChatStreamChunk) {
+        for choice in &mut chunk.choices {
+            let mut calls = Vec::new();
+            let mut started = false;
+            for call in choice.delta.tool_calls.take().into_iter().flatten() {
+                let function = call.function.as_ref();
+                if function.and_then(|function| function.name.as_deref()) == Some(STRUCTURED_OUTPUT_TOOL) {
+                    self.stream_calls.insert(choice.index, call.index);
+                    started = true;
+                }
+                if self.stream_calls.get(&choice.index) != Some(&call.index) {
+                    calls.push(call);
+                    continue;
+                }
+                if let Some(arguments) = call.function.and_then(|function| function.arguments) {
+                    choice.delta.content.get_or_insert_with(String::new).push_str(&arguments);
+                }
+            }
+            // The call's opening delta becomes an empty text delta rather than an empty chunk
+            if started {
+                choice.delta.content.get_or_insert_with(String::new);
+            }
+            choice.delta.tool_calls = (!calls.is_empty()).then_some(calls);
+            if choice.finish_reason.is_some() && self.stream_calls.contains_key(&choice.index) {
+                choice.finish_reason = Some("stop".to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+use crate::transformers::providers::provider_trait::*;
+
+/// Length of the hash suffix appended to shortened names, excluding the `_` separator.
+const HASH_SUFFIX_LEN: usize = 8;
+
+/// Constraints a provider places on function/tool names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolNameRules {
+    pub max_len: usize,
+    /// Characters besides ASCII letters and digits that may appear in a name
+    pub extra_chars: &'static str,
+    /// Whether the first character must be a letter or `_`
+    pub letter_first: bool,
+}
+
+impl Default for ToolNameRules {
+    /// `^[a-zA-Z0-9_-]{1,64}$`, which OpenAI, Anthropic and Bedrock all accept.
+    fn default() -> Self {
+        Self {
+            max_len: 64,
+            extra_chars: "_-",
+            letter_first: false,
+        }
+    }
+}
+
+impl ToolNameRules {
+    pub fn is_valid(&self, name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= self.max_len
+            && name.chars().all(|c| self.is_allowed(c))
+            && (!self.letter_first || name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+    }
+
+    fn is_allowed(&self, c: char) -> bool {
+        c.is_ascii_alphanumeric() || self.extra_chars.contains(c)
+    }
+}
+
+/// Per-request bidirectional mapping between client tool names and the names sent upstream.
+///
+/// Names that already satisfy the provider's rules are left untouched and are not recorded.
+/// The client's parameter schemas are kept alongside, keyed by original name, so responses
+/// can be checked against the tools that were offered.
+#[derive(Debug, Clone, Default)]
+pub struct ToolNameMap {
+    to_upstream: HashMap<String, String>,
+    to_original: HashMap<String, String>,
+    schemas: HashMap<String, serde_json::Value>,
+}
+
+impl ToolNameMap {
+    /// Collects every tool name referenced by the request (definitions, prior tool calls and
+    /// `tool_choice`) and assigns an upstream-safe name to each one that needs it.
+    pub fn for_request(request: &ChatRequest, rules: &ToolNameRules) -> Self {
+        let mut map = Self::default();
+        let mut names = Vec::new();
+
+        for tool in request.tools.iter().flatten() {
+            names.push(tool.function.name.as_str());
+            map.schemas.insert(tool.function.name.clone(), tool.function.parameters.clone());
+        }
+        for message in &request.messages {
+            if let MessageContent::Parts(parts) = &message.content {
+                names.extend(parts.iter().filter_map(|part| part.tool_name.as_deref()));
+            }
+        }
+        if let Some(ToolChoice::Specific(choice)) = &request.tool_choice {
+            names.push(&choice.function.name);
+        }
+
+        // Names sent unchanged are taken, so no mangled name may reuse them
+        let valid: HashSet<&str> = names.iter().copied().filter(|name| rules.is_valid(name)).collect();
+        for name in names {
+            map.register(name, rules, &valid);
+        }
+
+        map
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.to_upstream.is_empty()
+    }
+
+    pub fn upstream_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.to_upstream.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    pub fn original_name<'a>(&'a self, name: &'a str) -> &'a str {
+        self.to_original.get(name).map(String::as_str).unwrap_or(name)
+    }
+
+    /// The parameter schema of the tool with this original name, if the request offered it.
+    pub fn schema(&self, name: &str) -> Option<&serde_json::Value> {
+        self.schemas.get(name)
+    }
+
+    fn register(&mut self, name: &str, rules: &ToolNameRules, valid: &HashSet<&str>) {
+        if rules.is_valid(name) || self.to_upstream.contains_key(name) {
+            return;
+        }
+
+        let mut mangled = Self::mangle(name, rules, false);
+        if self.to_original.contains_key(&mangled) || valid.contains(mangled.as_str()) {
+            mangled = Self::mangle(name, rules, true);
+        }
+        self.to_original.insert(mangled.clone(), name.to_string());
+        self.to_upstream.insert(name.to_string(), mangled);
+    }
+
+    /// Replaces disallowed characters with `_`; names that are still too long (or collide
+    /// with another tool's name) keep a prefix plus a short hash of the original.
+    fn mangle(name: &str, rules: &ToolNameRules, force_hash: bool) -> String {
+        let mut escaped: String = name
+            .chars()
+            .map(|c| if rules.is_allowed(c) { c } else { '_' })
+            .collect();
+        if rules.letter_first && !escaped.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            escaped.insert(0, '_');
+        }
+        if escaped.is_empty() {
+            escaped.push('_');
+        }
+
+        if !force_hash && escaped.len() <= rules.max_len {
+            return escaped;
+        }
+
+        let hash = hex::encode(Sha256::digest(name.as_bytes()));
+        let prefix_len = rules.max_len.saturating_sub(HASH_SUFFIX_LEN + 1);
+        let prefix: String = escaped.chars().take(prefix_len).collect();
+        format!("{}_{}", prefix, &hash[..HASH_SUFFIX_LEN])
+    }
+
+    /// Rewrites tool definitions, prior tool calls and `tool_choice` to upstream names.
+    pub fn apply_to_request(&self, request: &mut ChatRequest) {
+        if self.is_empty() {
+            return;
+        }
+
+        for tool in request.tools.iter_mut().flatten() {
+            tool.function.name = self.upstream_name(&tool.function.name).to_string();
+        }
+        for message in &mut request.messages {
+            if let MessageContent::Parts(parts) = &mut message.content {
+                for part in parts {
+                    if let Some(name) = &part.tool_name {
+                        part.tool_name = Some(self.upstream_name(name).to_string());
+                    }
+                }
+            }
+        }
+        if let Some(ToolChoice::Specific(choice)) = &mut request.tool_choice {
+            choice.function.name = self.upstream_name(&choice.function.name).to_string();
+        }
+    }
+
+    /// Restores original names in tool calls and `tool_use` parts of a response.
+    pub fn restore_response(&self, response: &mut ChatResponse) {
+        if self.is_empty() {
+            return;
+        }
+
+        for choice in &mut response.choices {
+            for call in choice.tool_calls.iter_mut().flatten() {
+                call.function.name = self.original_name(&call.function.name).to_string();
+            }
+            if let MessageContent::Parts(parts) = &mut choice.message.content {
+                for part in parts {
+                    if let Some(name) = &part.tool_name {
+                        part.tool_name = Some(self.original_name(name).to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Restores original names in streamed tool-call deltas. Providers send the name whole
+    /// in the first delta of a call, so no cross-chunk buffering is needed.
+    pub fn restore_stream_chunk(&self, chunk: &mut ChatStreamChunk) {
+        if self.is_empty() {
+            return;
+        }
+
+        for choice in &mut chunk.choices {
+            for call in choice.delta.tool_calls.iter_mut().flatten() {
+                if let Some(function) = &mut call.function
+                    && let Some(name) = &function.name
+                {
+                    function.name = Some(self.original_name(name).to_string());
+                }
+            }
+        }
+    }
+}
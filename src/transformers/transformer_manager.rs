@@ -1,220 +1,546 @@
-use crate::transformers::types::{Transformer, TransformerConfig};
+use crate::config::types::{Provider, TransformerConfig};
 use crate::transformers::error::{TransformerError, TransformerResult};
-use crate::transformers::providers::{OpenAITransformer, AnthropicTransformer, GeminiTransformer, ProviderTransformer};
-use crate::transformers::providers::provider_trait::{ChatRequest, ChatResponse, ChatStreamChunk};
-use std::collections::HashMap;
+use crate::transformers::providers::{OpenAITransformer, AnthropicTransformer, GeminiTransformer, OpenRouterTransformer, OllamaTransformer, BedrockTransformer, AzureOpenAITransformer, VertexTokenProviders, VertexTransformer, ProviderTransformer};
+use crate::transformers::providers::provider_trait::{record_passthrough_headers, ChatRequest, ChatResponse, ChatStreamChunk};
+use crate::transformers::json_repair::repair_response_tool_calls;
+use crate::transformers::tool_names::ToolNameMap;
+use crate::transformers::upstream::UpstreamRequest;
+use crate::transformers::hooks::builtin_hooks;
+use crate::transformers::script::{ScriptModule, ScriptTransformer};
+use crate::transformers::chain::{ChainEntry, ChainProvider, HookFactory, ProviderFactory, TransformerChain};
+use crate::config::capabilities::ModelCapabilities;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
 use serde_json::Value;
 
+/// Where a registered transformer came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TransformerSource {
+    Builtin,
+    Plugin { path: String },
+}
+
+/// Whether an entry converts to a provider's wire format or only rewrites the universal
+/// request/response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransformerKind {
+    Provider,
+    Hook,
+}
+
+/// One registry entry, as listed by `/api/transformers`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransformerInfo {
+    pub name: String,
+    pub kind: TransformerKind,
+    pub aliases: Vec<String>,
+    pub source: TransformerSource,
+}
+
+struct RegisteredTransformer {
+    transformer: Box<dyn ProviderTransformer>,
+    /// Builds a configured instance for `["name", {options}]` entries
+    factory: Option<ProviderFactory>,
+    source: TransformerSource,
+}
+
+struct RegisteredHook {
+    factory: HookFactory,
+    source: TransformerSource,
+}
+
+/// Registry of provider transformers keyed by name.
+///
+/// `new()` registers the built-ins; plugins are added at startup through `register`.
+/// Every lookup resolves aliases first, so `openai-compatible` reaches the `openai` entry.
 pub struct TransformerManager {
-    openai: OpenAITransformer,
-    anthropic: AnthropicTransformer,
-    gemini: GeminiTransformer,
+    transformers: BTreeMap<String, RegisteredTransformer>,
+    hooks: BTreeMap<String, RegisteredHook>,
+    aliases: HashMap<String, String>,
 }
 
 impl TransformerManager {
     pub fn new() -> Self {
+        let mut manager = Self::empty();
+        manager.register_builtin(Box::new(OpenAITransformer::new()));
+        manager.register_builtin(Box::new(AnthropicTransformer::new()));
+        manager.register_builtin(Box::new(GeminiTransformer::new()));
+        manager.register_builtin_with_options(Box::new(OpenRouterTransformer::new()), Box::new(|options| {
+            Ok(Box::new(OpenRouterTransformer::from_options_value(options)?))
+        }));
+        manager.register_builtin_with_options(Box::new(OllamaTransformer::new()), Box::new(|options| {
+            Ok(Box::new(OllamaTransformer::from_options_value(options)?))
+        }));
+        manager.register_builtin_with_options(Box::new(BedrockTransformer::new()), Box::new(|options| {
+            Ok(Box::new(BedrockTransformer::from_options_value(options)?))
+        }));
+        manager.register_builtin_with_options(Box::new(AzureOpenAITransformer::new()), Box::new(|options| {
+            Ok(Box::new(AzureOpenAITransformer::from_options_value(options)?))
+        }));
+        // Every Vertex instance shares one token cache per key, as chains are built per request
+        let vertex_tokens = Arc::new(VertexTokenProviders::new());
+        manager.register_builtin_with_options(Box::new(VertexTransformer::new_shared(&vertex_tokens)), Box::new(move |options| {
+            Ok(Box::new(VertexTransformer::from_options_value_shared(options, &vertex_tokens)?))
+        }));
+
+        for (name, factory) in builtin_hooks() {
+            manager.register_hook(name, factory, TransformerSource::Builtin);
+        }
+
+        manager.register_alias("openai-compatible", "openai");
+        manager.register_alias("claude", "anthropic");
+        manager.register_alias("google", "gemini");
+        manager.register_alias("azure-openai", "azure");
+        manager.register_alias("vertex-ai", "vertex");
+        manager
+    }
+
+    /// A registry with nothing registered.
+    pub fn empty() -> Self {
         Self {
-            openai: OpenAITransformer::new(),
-            anthropic: AnthropicTransformer::new(),
-            gemini: GeminiTransformer::new(),
+            transformers: BTreeMap::new(),
+            hooks: BTreeMap::new(),
+            aliases: HashMap::new(),
         }
     }
-    
-    pub fn get_all_transformers(&self) -> HashMap<String, TransformerConfig> {
-        let mut configs = HashMap::new();
-        configs.insert("openai".to_string(), TransformerConfig {
-            path: "transformers/providers/openai".to_string(),
-            options: None,
-        });
-        configs.insert("anthropic".to_string(), TransformerConfig {
-            path: "transformers/providers/anthropic".to_string(),
-            options: None,
-        });
-        configs.insert("gemini".to_string(), TransformerConfig {
-            path: "transformers/providers/gemini".to_string(),
-            options: None,
-        });
-        configs
-    }
-
-    pub fn apply_transformer(&self, transformer: &Transformer, data: &str) -> TransformerResult<String> {
-        // Find the first available transformer for the requested providers
-        for provider_name in &transformer.use_transformers {
-            match provider_name.as_str() {
-                "openai" => {
-                    // Parse the input data
-                    let input_value: Value = serde_json::from_str(data)
-                        .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
-                    
-                    // Convert to universal format and back to provider format
-                    let universal_request = self.openai.to_universal_request(&input_value)?;
-                    let provider_request = self.openai.from_universal_request(&universal_request)?;
-                    
-                    return serde_json::to_string(&provider_request)
-                        .map_err(|e| TransformerError::Serialization(e.to_string()));
-                }
-                "anthropic" => {
-                    // Parse the input data
-                    let input_value: Value = serde_json::from_str(data)
-                        .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
-                    
-                    // Convert to universal format and back to provider format
-                    let universal_request = self.anthropic.to_universal_request(&input_value)?;
-                    let provider_request = self.anthropic.from_universal_request(&universal_request)?;
-                    
-                    return serde_json::to_string(&provider_request)
-                        .map_err(|e| TransformerError::Serialization(e.to_string()));
+
+    /// Registers a transformer under `name`, replacing any entry or alias of that name.
+    pub fn register(&mut self, name: impl Into<String>, transformer: Box<dyn ProviderTransformer>, source: TransformerSource) {
+        self.insert_transformer(name.into(), transformer, None, source);
+    }
+
+    /// Like `register`, but `["name", {options}]` entries build their own instance with `factory`.
+    pub fn register_with_options(
+        &mut self,
+        name: impl Into<String>,
+        transformer: Box<dyn ProviderTransformer>,
+        factory: ProviderFactory,
+        source: TransformerSource,
+    ) {
+        self.insert_transformer(name.into(), transformer, Some(factory), source);
+    }
+
+    /// Registers a built-in transformer under its `provider_name()`.
+    pub fn register_builtin(&mut self, transformer: Box<dyn ProviderTransformer>) {
+        self.register(transformer.provider_name(), transformer, TransformerSource::Builtin);
+    }
+
+    pub fn register_builtin_with_options(&mut self, transformer: Box<dyn ProviderTransformer>, factory: ProviderFactory) {
+        self.register_with_options(transformer.provider_name(), transformer, factory, TransformerSource::Builtin);
+    }
+
+    /// Registers a hook that can appear in a provider's `use` chain.
+    pub fn register_hook(&mut self, name: impl Into<String>, factory: HookFactory, source: TransformerSource) {
+        let name = name.into();
+        self.aliases.remove(&name);
+        self.hooks.insert(name, RegisteredHook { factory, source });
+    }
+
+    /// Loads the scripts listed in `Config.transformers` and registers each under its name.
+    /// Relative paths are resolved against `plugins_dir`. Scripts that fail to load are
+    /// skipped and reported in the returned list; the rest stay registered.
+    pub fn load_scripts(&mut self, configs: &[TransformerConfig], plugins_dir: &Path) -> Vec<TransformerError> {
+        let mut errors = Vec::new();
+        for config in configs {
+            let path = plugins_dir.join(&config.path);
+            let module = match ScriptModule::load(&path, config.options.clone()) {
+                Ok(module) => module,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
                 }
-                "gemini" => {
-                    // Parse the input data
-                    let input_value: Value = serde_json::from_str(data)
-                        .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
-                    
-                    // Convert to universal format and back to provider format
-                    let universal_request = self.gemini.to_universal_request(&input_value)?;
-                    let provider_request = self.gemini.from_universal_request(&universal_request)?;
-                    
-                    return serde_json::to_string(&provider_request)
-                        .map_err(|e| TransformerError::Serialization(e.to_string()));
+            };
+
+            let name = module.name().to_string();
+            if let Err(e) = self.register_plugin(name, ScriptTransformer::factory(Arc::new(module)), &path) {
+                errors.push(e);
+            }
+        }
+        errors
+    }
+
+    /// Registers a hook loaded from `path`. Unlike `register_hook`, a plugin may not shadow
+    /// a provider, hook or alias that is already registered.
+    pub fn register_plugin(&mut self, name: impl Into<String>, factory: HookFactory, path: &Path) -> TransformerResult<()> {
+        let name = name.into();
+        if self.resolve_name(&name).is_some() || self.resolve_hook_name(&name).is_some() {
+            return Err(TransformerError::Configuration(format!(
+                "Plugin {} uses the name '{}', which is already registered",
+                path.display(),
+                name
+            )));
+        }
+        self.register_hook(name, factory, TransformerSource::Plugin { path: path.display().to_string() });
+        Ok(())
+    }
+
+    fn insert_transformer(
+        &mut self,
+        name: String,
+        transformer: Box<dyn ProviderTransformer>,
+        factory: Option<ProviderFactory>,
+        source: TransformerSource,
+    ) {
+        self.aliases.remove(&name);
+        self.transformers.insert(name, RegisteredTransformer { transformer, factory, source });
+    }
+
+    pub fn register_alias(&mut self, alias: impl Into<String>, target: impl Into<String>) {
+        self.aliases.insert(alias.into(), target.into());
+    }
+
+    /// Resolves `name` (or an alias of it) to the registered provider transformer name.
+    pub fn resolve_name<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        if self.transformers.contains_key(name) {
+            return Some(name);
+        }
+        self.aliases
+            .get(name)
+            .map(String::as_str)
+            .filter(|target| self.transformers.contains_key(*target))
+    }
+
+    fn resolve_hook_name<'a>(&'a self, name: &'a str) -> Option<&'a str> {
+        if self.hooks.contains_key(name) {
+            return Some(name);
+        }
+        self.aliases
+            .get(name)
+            .map(String::as_str)
+            .filter(|target| self.hooks.contains_key(*target))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ProviderTransformer> {
+        let name = self.resolve_name(name)?;
+        self.transformers.get(name).map(|entry| entry.transformer.as_ref())
+    }
+
+    pub fn transformer(&self, name: &str) -> TransformerResult<&dyn ProviderTransformer> {
+        self.get(name)
+            .ok_or_else(|| TransformerError::UnsupportedProvider(name.to_string()))
+    }
+
+    /// Registered provider transformers and hooks sorted by name, with aliases and origin.
+    pub fn list_transformers(&self) -> Vec<TransformerInfo> {
+        let providers = self
+            .transformers
+            .iter()
+            .map(|(name, entry)| self.info(name, TransformerKind::Provider, &entry.source));
+        let hooks = self
+            .hooks
+            .iter()
+            .map(|(name, entry)| self.info(name, TransformerKind::Hook, &entry.source));
+
+        let mut infos: Vec<TransformerInfo> = providers.chain(hooks).collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    fn info(&self, name: &str, kind: TransformerKind, source: &TransformerSource) -> TransformerInfo {
+        let mut aliases: Vec<String> = self
+            .aliases
+            .iter()
+            .filter(|(_, target)| *target == name)
+            .map(|(alias, _)| alias.clone())
+            .collect();
+        aliases.sort();
+        TransformerInfo {
+            name: name.to_string(),
+            kind,
+            aliases,
+            source: source.clone(),
+        }
+    }
+
+    /// Resolves `provider.transformer` for `model` into a runnable chain.
+    ///
+    /// The first provider transformer in the chain sets the wire format; without one, the
+    /// transformer registered under the provider's name is used, falling back to `openai`.
+    pub fn resolve_chain(&self, provider: &Provider, model: &str) -> TransformerResult<TransformerChain<'_>> {
+        let entries = match &provider.transformer {
+            Some(transformer) => ChainEntry::resolve(transformer, model)?,
+            None => Vec::new(),
+        };
+
+        let mut chain_provider = None;
+        let mut hooks = Vec::new();
+        for entry in &entries {
+            if let Some(name) = self.resolve_name(&entry.name) {
+                if chain_provider.is_some() {
+                    return Err(TransformerError::Configuration(format!(
+                        "Provider '{}' lists more than one provider transformer (second: '{}')",
+                        provider.name, entry.name
+                    )));
                 }
-                _ => continue,
+                chain_provider = Some(self.instantiate(name, entry.options.as_ref())?);
+            } else if let Some(name) = self.resolve_hook_name(&entry.name) {
+                hooks.push((self.hooks[name].factory)(entry.options.as_ref())?);
+            } else {
+                return Err(TransformerError::Configuration(format!(
+                    "Unknown transformer '{}' for provider '{}'",
+                    entry.name, provider.name
+                )));
             }
         }
-        
-        Err(TransformerError::UnsupportedProvider(
-            transformer.use_transformers.join(", ")
-        ))
+
+        let chain_provider = match chain_provider {
+            Some(chain_provider) => chain_provider,
+            None => match self.get(&provider.name) {
+                Some(transformer) => ChainProvider::Shared(transformer),
+                None => ChainProvider::Shared(self.transformer("openai")?),
+            },
+        };
+        Ok(TransformerChain::new(chain_provider, hooks))
+    }
+
+    fn instantiate(&self, name: &str, options: Option<&Value>) -> TransformerResult<ChainProvider<'_>> {
+        let entry = &self.transformers[name];
+        match (options, &entry.factory) {
+            (Some(options), Some(factory)) => Ok(ChainProvider::Configured(factory(options)?)),
+            (Some(_), None) => Err(TransformerError::Configuration(format!(
+                "Transformer '{}' does not accept options",
+                name
+            ))),
+            (None, _) => Ok(ChainProvider::Shared(entry.transformer.as_ref())),
+        }
+    }
+
+    /// Round-trips a provider-format request through the provider's chain for `model`.
+    pub fn apply_transformer(&self, provider: &Provider, model: &str, data: &str) -> TransformerResult<String> {
+        let chain = self.resolve_chain(provider, model)?;
+
+        // Parse the input data
+        let input_value: Value = serde_json::from_str(data)
+            .map_err(|e| TransformerError::Deserialization(e.to_string()))?;
+
+        // Convert to universal format, run the hooks and convert back to provider format
+        let mut universal_request = chain.provider().to_universal_request(&input_value)?;
+        chain.apply_request(&mut universal_request)?;
+        let provider_request = chain.provider().from_universal_request(&universal_request)?;
+
+        serde_json::to_string(&provider_request)
+            .map_err(|e| TransformerError::Serialization(e.to_string()))
     }
 
     pub fn transform_request(
-        &self, 
-        from_provider: &str, 
-        to_provider: &str, 
+        &self,
+        from_provider: &str,
+        to_provider: &str,
         request: &Value
     ) -> TransformerResult<Value> {
-        // Convert from source provider to universal format
-        let universal_request = match from_provider {
-            "openai" => self.openai.to_universal_request(request)?,
-            "anthropic" => self.anthropic.to_universal_request(request)?,
-            "gemini" => self.gemini.to_universal_request(request)?,
-            _ => return Err(TransformerError::UnsupportedProvider(from_provider.to_string())),
-        };
-        
-        // Convert from universal format to target provider
-        match to_provider {
-            "openai" => self.openai.from_universal_request(&universal_request),
-            "anthropic" => self.anthropic.from_universal_request(&universal_request),
-            "gemini" => self.gemini.from_universal_request(&universal_request),
-            _ => Err(TransformerError::UnsupportedProvider(to_provider.to_string())),
-        }
+        let universal_request = self.to_universal_request(from_provider, request)?;
+        self.from_universal_request(to_provider, &universal_request)
     }
 
     pub fn transform_response(
-        &self, 
-        from_provider: &str, 
-        to_provider: &str, 
+        &self,
+        from_provider: &str,
+        to_provider: &str,
         response: &Value
     ) -> TransformerResult<Value> {
-        // Convert from source provider to universal format
-        let universal_response = match from_provider {
-            "openai" => self.openai.to_universal_response(response)?,
-            "anthropic" => self.anthropic.to_universal_response(response)?,
-            "gemini" => self.gemini.to_universal_response(response)?,
-            _ => return Err(TransformerError::UnsupportedProvider(from_provider.to_string())),
-        };
-        
-        // Convert from universal format to target provider
-        match to_provider {
-            "openai" => self.openai.from_universal_response(&universal_response),
-            "anthropic" => self.anthropic.from_universal_response(&universal_response),
-            "gemini" => self.gemini.from_universal_response(&universal_response),
-            _ => Err(TransformerError::UnsupportedProvider(to_provider.to_string())),
-        }
+        let universal_response = self.to_universal_response(from_provider, response)?;
+        self.from_universal_response(to_provider, &universal_response)
     }
 
     pub fn transform_stream_chunk(
-        &self, 
-        from_provider: &str, 
-        to_provider: &str, 
+        &self,
+        from_provider: &str,
+        to_provider: &str,
         chunk: &Value
     ) -> TransformerResult<Value> {
-        // Convert from source provider to universal format
-        let universal_chunk = match from_provider {
-            "openai" => self.openai.to_universal_stream_chunk(chunk)?,
-            "anthropic" => self.anthropic.to_universal_stream_chunk(chunk)?,
-            "gemini" => self.gemini.to_universal_stream_chunk(chunk)?,
-            _ => return Err(TransformerError::UnsupportedProvider(from_provider.to_string())),
-        };
-        
-        // Convert from universal format to target provider
-        match to_provider {
-            "openai" => self.openai.from_universal_stream_chunk(&universal_chunk),
-            "anthropic" => self.anthropic.from_universal_stream_chunk(&universal_chunk),
-            "gemini" => self.gemini.from_universal_stream_chunk(&universal_chunk),
-            _ => Err(TransformerError::UnsupportedProvider(to_provider.to_string())),
+        let universal_chunk = self.to_universal_stream_chunk(from_provider, chunk)?;
+        self.from_universal_stream_chunk(to_provider, &universal_chunk)
+    }
+
+    /// Like `transform_request`, but tool names the target provider would reject are
+    /// rewritten. The returned map must be passed to the matching response/stream call.
+    pub fn transform_request_with_tool_names(
+        &self,
+        from_provider: &str,
+        to_provider: &str,
+        request: &Value
+    ) -> TransformerResult<(Value, ToolNameMap)> {
+        self.transform_request_for_model(from_provider, to_provider, request, &ModelCapabilities::unrestricted())
+    }
+
+    /// Adapts the request to the target model's capabilities (clamped `max_tokens`,
+    /// unsupported features dropped or rejected) before converting it and mangling tool names.
+    pub fn transform_request_for_model(
+        &self,
+        from_provider: &str,
+        to_provider: &str,
+        request: &Value,
+        capabilities: &ModelCapabilities
+    ) -> TransformerResult<(Value, ToolNameMap)> {
+        let target = self.transformer(to_provider)?;
+        let mut universal_request = self.to_universal_request(from_provider, request)?;
+        capabilities.for_transformer(target).apply_to_request(&mut universal_request)?;
+
+        let tool_names = ToolNameMap::for_request(&universal_request, &target.tool_name_rules());
+        tool_names.apply_to_request(&mut universal_request);
+
+        let provider_request = target.from_universal_request(&universal_request)?;
+        Ok((provider_request, tool_names))
+    }
+
+    /// Converts a client request for a resolved chain: capability clamping, request hooks
+    /// in order, tool-name mangling, then the chain provider's wire format.
+    pub fn transform_request_with_chain(
+        &self,
+        from_provider: &str,
+        chain: &TransformerChain<'_>,
+        request: &Value,
+        capabilities: &ModelCapabilities
+    ) -> TransformerResult<(Value, ToolNameMap)> {
+        let (universal_request, tool_names) = self.prepare_request_with_chain(from_provider, chain, request, capabilities)?;
+        let provider_request = chain.provider().from_universal_request(&universal_request)?;
+        Ok((provider_request, tool_names))
+    }
+
+    /// The HTTP request a client request becomes when routed to `model` on `provider`:
+    /// the `transform_request_with_chain` conversion with the routed model name, then the
+    /// chain provider's URL scheme and authentication. The client's feature headers (see
+    /// `passthrough_header_names`) are kept for a same-format target. Nothing is sent.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_upstream_request(
+        &self,
+        from_provider: &str,
+        chain: &TransformerChain<'_>,
+        provider: &Provider,
+        model: &str,
+        request: &Value,
+        headers: &HashMap<String, String>,
+        capabilities: &ModelCapabilities
+    ) -> TransformerResult<(UpstreamRequest, ToolNameMap)> {
+        let (mut universal_request, tool_names) =
+            self.prepare_request_with_chain(from_provider, chain, request, capabilities)?;
+        universal_request.model = model.to_string();
+        let source = self.transformer(from_provider)?;
+        record_passthrough_headers(&mut universal_request, source.provider_name(), source.passthrough_header_names(), headers);
+        let upstream = chain.provider().build_upstream_request(provider, &universal_request)?;
+        Ok((upstream, tool_names))
+    }
+
+    fn prepare_request_with_chain(
+        &self,
+        from_provider: &str,
+        chain: &TransformerChain<'_>,
+        request: &Value,
+        capabilities: &ModelCapabilities
+    ) -> TransformerResult<(ChatRequest, ToolNameMap)> {
+        let mut universal_request = self.to_universal_request(from_provider, request)?;
+        capabilities.for_transformer(chain.provider()).apply_to_request(&mut universal_request)?;
+        chain.apply_request(&mut universal_request)?;
+        chain.record_request(&universal_request);
+
+        let tool_names = ToolNameMap::for_request(&universal_request, &chain.provider().tool_name_rules());
+        tool_names.apply_to_request(&mut universal_request);
+        Ok((universal_request, tool_names))
+    }
+
+    /// Converts an upstream response back for the client: an emulated structured-output
+    /// call is unwrapped and original tool names and repaired tool-call arguments are
+    /// restored first, then response hooks run in reverse, and the chain's response
+    /// identity (if any) sets the reported id and model.
+    pub fn transform_response_with_chain(
+        &self,
+        chain: &TransformerChain<'_>,
+        to_provider: &str,
+        response: &Value,
+        tool_names: &ToolNameMap
+    ) -> TransformerResult<Value> {
+        let mut universal_response = chain.provider().to_universal_response(response)?;
+        chain.unwrap_structured_output(&mut universal_response);
+        tool_names.restore_response(&mut universal_response);
+        repair_response_tool_calls(&mut universal_response, tool_names);
+        chain.apply_response(&mut universal_response)?;
+        if let Some(identity) = chain.identity() {
+            identity.apply_to_response(&mut universal_response);
+        }
+        self.from_universal_response(to_provider, &universal_response)
+    }
+
+    /// Stream counterpart of `transform_response_with_chain`. Tool-call arguments and the
+    /// client format's stream state are kept in the chain, so each stream needs its own
+    /// chain; send `chain.finish_stream()` once the upstream stream ends.
+    pub fn transform_stream_chunk_with_chain(
+        &self,
+        chain: &TransformerChain<'_>,
+        to_provider: &str,
+        chunk: &Value,
+        tool_names: &ToolNameMap
+    ) -> TransformerResult<Value> {
+        let mut universal_chunk = chain.provider().to_universal_stream_chunk(chunk)?;
+        chain.unwrap_structured_output_chunk(&mut universal_chunk);
+        tool_names.restore_stream_chunk(&mut universal_chunk);
+        chain.accumulate_tool_calls(&mut universal_chunk, tool_names);
+        chain.apply_stream_chunk(&mut universal_chunk)?;
+        if let Some(identity) = chain.identity() {
+            identity.apply_to_stream_chunk(&mut universal_chunk);
         }
+        chain.encode_stream_chunk(self.transformer(to_provider)?, &universal_chunk)
+    }
+
+    pub fn transform_response_with_tool_names(
+        &self,
+        from_provider: &str,
+        to_provider: &str,
+        response: &Value,
+        tool_names: &ToolNameMap
+    ) -> TransformerResult<Value> {
+        let mut universal_response = self.to_universal_response(from_provider, response)?;
+        tool_names.restore_response(&mut universal_response);
+        repair_response_tool_calls(&mut universal_response, tool_names);
+        self.from_universal_response(to_provider, &universal_response)
+    }
+
+    /// Stateless: argument fragments pass through unrepaired. Use a chain to repair them.
+    pub fn transform_stream_chunk_with_tool_names(
+        &self,
+        from_provider: &str,
+        to_provider: &str,
+        chunk: &Value,
+        tool_names: &ToolNameMap
+    ) -> TransformerResult<Value> {
+        let mut universal_chunk = self.to_universal_stream_chunk(from_provider, chunk)?;
+        tool_names.restore_stream_chunk(&mut universal_chunk);
+        self.from_universal_stream_chunk(to_provider, &universal_chunk)
     }
 
     pub fn list_available_providers(&self) -> Vec<String> {
-        vec!["openai".to_string(), "anthropic".to_string(), "gemini".to_string()]
+        self.transformers.keys().cloned().collect()
     }
 
     pub fn is_provider_supported(&self, provider: &str) -> bool {
-        matches!(provider, "openai" | "anthropic" | "gemini")
+        self.resolve_name(provider).is_some()
     }
 
     pub fn to_universal_request(&self, from_provider: &str, request: &Value) -> TransformerResult<ChatRequest> {
-        match from_provider {
-            "openai" => self.openai.to_universal_request(request),
-            "anthropic" => self.anthropic.to_universal_request(request),
-            "gemini" => self.gemini.to_universal_request(request),
-            _ => Err(TransformerError::UnsupportedProvider(from_provider.to_string())),
-        }
+        self.transformer(from_provider)?.to_universal_request(request)
     }
 
     pub fn from_universal_request(&self, to_provider: &str, universal_request: &ChatRequest) -> TransformerResult<Value> {
-        match to_provider {
-            "openai" => self.openai.from_universal_request(universal_request),
-            "anthropic" => self.anthropic.from_universal_request(universal_request),
-            "gemini" => self.gemini.from_universal_request(universal_request),
-            _ => Err(TransformerError::UnsupportedProvider(to_provider.to_string())),
-        }
+        self.transformer(to_provider)?.from_universal_request(universal_request)
     }
 
     pub fn to_universal_response(&self, from_provider: &str, response: &Value) -> TransformerResult<ChatResponse> {
-        match from_provider {
-            "openai" => self.openai.to_universal_response(response),
-            "anthropic" => self.anthropic.to_universal_response(response),
-            "gemini" => self.gemini.to_universal_response(response),
-            _ => Err(TransformerError::UnsupportedProvider(from_provider.to_string())),
-        }
+        self.transformer(from_provider)?.to_universal_response(response)
     }
 
     pub fn from_universal_response(&self, to_provider: &str, universal_response: &ChatResponse) -> TransformerResult<Value> {
-        match to_provider {
-            "openai" => self.openai.from_universal_response(universal_response),
-            "anthropic" => self.anthropic.from_universal_response(universal_response),
-            "gemini" => self.gemini.from_universal_response(universal_response),
-            _ => Err(TransformerError::UnsupportedProvider(to_provider.to_string())),
-        }
+        self.transformer(to_provider)?.from_universal_response(universal_response)
     }
 
     pub fn to_universal_stream_chunk(&self, from_provider: &str, chunk: &Value) -> TransformerResult<ChatStreamChunk> {
-        match from_provider {
-            "openai" => self.openai.to_universal_stream_chunk(chunk),
-            "anthropic" => self.anthropic.to_universal_stream_chunk(chunk),
-            "gemini" => self.gemini.to_universal_stream_chunk(chunk),
-            _ => Err(TransformerError::UnsupportedProvider(from_provider.to_string())),
-        }
+        self.transformer(from_provider)?.to_universal_stream_chunk(chunk)
     }
 
     pub fn from_universal_stream_chunk(&self, to_provider: &str, universal_chunk: &ChatStreamChunk) -> TransformerResult<Value> {
-        match to_provider {
-            "openai" => self.openai.from_universal_stream_chunk(universal_chunk),
-            "anthropic" => self.anthropic.from_universal_stream_chunk(universal_chunk),
-            "gemini" => self.gemini.from_universal_stream_chunk(universal_chunk),
-            _ => Err(TransformerError::UnsupportedProvider(to_provider.to_string())),
-        }
+        self.transformer(to_provider)?.from_universal_stream_chunk(universal_chunk)
     }
 }
 
@@ -222,4 +548,4 @@ impl Default for TransformerManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,70 @@
+use crate::transformers::error::{TransformerError, TransformerResult};
+use serde::Serialize;
+
+/// Headers that carry credentials, masked by [`UpstreamRequest::redacted`].
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "x-api-key", "api-key", "x-goog-api-key", "x-amz-security-token"];
+const REDACTED: &str = "[redacted]";
+
+/// A fully prepared HTTP request for an upstream provider: target URL, headers
+/// (including any authentication) and the provider-format JSON body.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: serde_json::Value,
+}
+
+impl UpstreamRequest {
+    pub fn post(url: impl Into<String>, body: serde_json::Value) -> Self {
+        Self {
+            method: "POST".to_string(),
+            url: url.into(),
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body,
+        }
+    }
+
+    /// Sets a header, replacing any existing value case-insensitively.
+    pub fn set_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+        self.headers.push((name, value.into()));
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// A copy safe to show to users, with credential header values masked.
+    pub fn redacted(&self) -> Self {
+        let mut request = self.clone();
+        for (name, value) in &mut request.headers {
+            if CREDENTIAL_HEADERS.iter().any(|credential| name.eq_ignore_ascii_case(credential)) {
+                *value = REDACTED.to_string();
+            }
+        }
+        request
+    }
+
+    /// The exact bytes that are sent, which is also what request signing must hash.
+    pub fn body_bytes(&self) -> TransformerResult<Vec<u8>> {
+        serde_json::to_vec(&self.body).map_err(|e| TransformerError::Serialization(e.to_string()))
+    }
+
+    pub async fn send(&self, client: &reqwest::Client) -> TransformerResult<reqwest::Response> {
+        let method = reqwest::Method::from_bytes(self.method.as_bytes())
+            .map_err(|e| TransformerError::InvalidFormat(e.to_string()))?;
+        let mut builder = client.request(method, &self.url).body(self.body_bytes()?);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .send()
+            .await
+            .map_err(|e| TransformerError::ProviderError(e.to_string()))
+    }
+}
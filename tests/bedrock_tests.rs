@@ -0,0 +1,318 @@
+//! Bedrock 提供商测试模块
+//!
+//! 覆盖 SigV4 签名（使用 AWS 官方测试向量）、Converse 格式转换、
+//! event-stream 二进制帧解码，以及对本地桩服务的端到端签名请求。
+
+use axum::{body::Bytes, extract::Path, http::HeaderMap, routing::post, Router};
+use chrono::{TimeZone, Utc};
+use code_routic::config::types::Provider;
+use code_routic::transformers::{
+    auth::{AwsCredentials, SigV4Signer},
+    providers::{BedrockTransformer, ProviderTransformer},
+    stream::{EventStreamDecoder, EventStreamHeaderValue, EventStreamMessage, StreamFormat},
+    TransformerManager,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+fn test_vector_credentials() -> AwsCredentials {
+    AwsCredentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", None)
+}
+
+fn event(event_type: &str, payload: serde_json::Value) -> EventStreamMessage {
+    let mut headers = HashMap::new();
+    headers.insert(":message-type".to_string(), EventStreamHeaderValue::String("event".to_string()));
+    headers.insert(":event-type".to_string(), EventStreamHeaderValue::String(event_type.to_string()));
+    headers.insert(
+        ":content-type".to_string(),
+        EventStreamHeaderValue::String("application/json".to_string()),
+    );
+    EventStreamMessage {
+        headers,
+        payload: serde_json::to_vec(&payload).unwrap(),
+    }
+}
+
+fn bedrock_provider(api_base_url: &str) -> Provider {
+    Provider {
+        name: "bedrock".to_string(),
+        api_base_url: api_base_url.to_string(),
+        api_key: String::new(),
+        models: vec!["anthropic.claude-3-5-sonnet-20240620-v1:0".to_string()],
+        transformer: None,
+        capabilities: HashMap::new(),
+    }
+}
+
+#[test]
+fn test_sigv4_get_vanilla_vector() {
+    let credentials = test_vector_credentials();
+    let signer = SigV4Signer::new(&credentials, "us-east-1", "service");
+    let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+
+    let mut headers = vec![("Host".to_string(), "example.amazonaws.com".to_string())];
+    signer
+        .sign_parts("GET", "https://example.amazonaws.com/", &mut headers, b"", now)
+        .unwrap();
+
+    let authorization = &headers.iter().find(|(name, _)| name == "authorization").unwrap().1;
+    assert_eq!(
+        authorization,
+        "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+         SignedHeaders=host;x-amz-date, \
+         Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+    );
+}
+
+#[test]
+fn test_sigv4_query_order_and_post_vectors() {
+    let credentials = test_vector_credentials();
+    let signer = SigV4Signer::new(&credentials, "us-east-1", "service");
+    let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+
+    let mut headers = vec![("Host".to_string(), "example.amazonaws.com".to_string())];
+    signer
+        .sign_parts(
+            "GET",
+            "https://example.amazonaws.com/?Param2=value2&Param1=value1",
+            &mut headers,
+            b"",
+            now,
+        )
+        .unwrap();
+    let authorization = &headers.iter().find(|(name, _)| name == "authorization").unwrap().1;
+    assert!(authorization.ends_with(
+        "Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+    ));
+
+    let mut headers = vec![("Host".to_string(), "example.amazonaws.com".to_string())];
+    signer
+        .sign_parts("POST", "https://example.amazonaws.com/", &mut headers, b"", now)
+        .unwrap();
+    let authorization = &headers.iter().find(|(name, _)| name == "authorization").unwrap().1;
+    assert!(authorization.ends_with(
+        "Signature=5da7c1a2acd57cee7505fc6676e4e544621c30862966e37dddb68e92efbe5d6b"
+    ));
+}
+
+#[test]
+fn test_bedrock_converse_request_from_anthropic() {
+    let manager = TransformerManager::new();
+
+    let anthropic_request = json!({
+        "model": "anthropic.claude-3-5-sonnet-20240620-v1:0",
+        "max_tokens": 1024,
+        "temperature": 0.2,
+        "messages": [
+            {"role": "user", "content": [{"type": "text", "text": "Weather in Boston?", "cache_control": {"type": "ephemeral"}}]},
+            {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"location": "Boston"}}
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "Sunny"}
+            ]}
+        ],
+        "tools": [{
+            "name": "get_weather",
+            "description": "Get weather information for a location",
+            "input_schema": {"type": "object", "properties": {"location": {"type": "string"}}}
+        }],
+        "tool_choice": {"type": "any"}
+    });
+
+    let universal_request = manager.to_universal_request("anthropic", &anthropic_request).unwrap();
+    let converse_request = manager.from_universal_request("bedrock", &universal_request).unwrap();
+
+    assert!(converse_request.get("model").is_none());
+    assert_eq!(converse_request["inferenceConfig"]["maxTokens"], 1024);
+    assert_eq!(converse_request["inferenceConfig"]["temperature"], 0.2);
+
+    let messages = converse_request["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[0]["content"][0]["text"], "Weather in Boston?");
+    assert_eq!(messages[0]["content"][1]["cachePoint"]["type"], "default");
+    assert_eq!(messages[1]["content"][0]["toolUse"]["toolUseId"], "toolu_1");
+    assert_eq!(messages[1]["content"][0]["toolUse"]["input"]["location"], "Boston");
+    assert_eq!(messages[2]["content"][0]["toolResult"]["content"][0]["text"], "Sunny");
+
+    let tool_config = &converse_request["toolConfig"];
+    assert_eq!(tool_config["tools"][0]["toolSpec"]["name"], "get_weather");
+    assert_eq!(tool_config["tools"][0]["toolSpec"]["inputSchema"]["json"]["type"], "object");
+    assert!(tool_config["toolChoice"]["any"].is_object());
+}
+
+#[test]
+fn test_bedrock_converse_response_to_anthropic() {
+    let manager = TransformerManager::new();
+
+    let converse_response = json!({
+        "output": {
+            "message": {
+                "role": "assistant",
+                "content": [
+                    {"text": "Checking."},
+                    {"toolUse": {"toolUseId": "tooluse_abc", "name": "get_weather", "input": {"location": "Boston"}}}
+                ]
+            }
+        },
+        "stopReason": "tool_use",
+        "usage": {"inputTokens": 30, "outputTokens": 12, "totalTokens": 42},
+        "metrics": {"latencyMs": 512}
+    });
+
+    let universal_response = manager.to_universal_response("bedrock", &converse_response).unwrap();
+    assert_eq!(universal_response.choices[0].finish_reason, "tool_calls");
+    assert_eq!(universal_response.usage.total_tokens, 42);
+
+    let anthropic_response = manager.from_universal_response("anthropic", &universal_response).unwrap();
+    assert_eq!(anthropic_response["stop_reason"], "tool_use");
+    assert_eq!(anthropic_response["content"][1]["id"], "tooluse_abc");
+    assert_eq!(anthropic_response["content"][1]["input"]["location"], "Boston");
+}
+
+#[test]
+fn test_event_stream_decoding() {
+    let transformer = BedrockTransformer::new();
+    assert_eq!(transformer.stream_format(), StreamFormat::AwsEventStream);
+
+    let mut body = Vec::new();
+    body.extend(event("messageStart", json!({"role": "assistant"})).encode());
+    body.extend(event("contentBlockDelta", json!({"contentBlockIndex": 0, "delta": {"text": "Hel"}})).encode());
+    body.extend(event("contentBlockDelta", json!({"contentBlockIndex": 0, "delta": {"text": "lo"}})).encode());
+    body.extend(event("contentBlockStart", json!({
+        "contentBlockIndex": 1,
+        "start": {"toolUse": {"toolUseId": "tooluse_1", "name": "get_weather"}}
+    })).encode());
+    body.extend(event("contentBlockDelta", json!({
+        "contentBlockIndex": 1,
+        "delta": {"toolUse": {"input": "{\"location\":\"Boston\"}"}}
+    })).encode());
+    body.extend(event("messageStop", json!({"stopReason": "tool_use"})).encode());
+    body.extend(event("metadata", json!({
+        "usage": {"inputTokens": 5, "outputTokens": 7, "totalTokens": 1036, "cacheReadInputTokens": 1000, "cacheWriteInputTokens": 24}
+    })).encode());
+
+    // Feed the body in small pieces so frames straddle chunk boundaries
+    let mut decoder = EventStreamDecoder::new();
+    let mut messages = Vec::new();
+    for piece in body.chunks(7) {
+        messages.extend(decoder.feed(piece));
+    }
+    assert!(decoder.finish().is_none());
+    assert_eq!(messages.len(), 7);
+
+    let chunks: Vec<_> = messages
+        .into_iter()
+        .map(|message| {
+            let event = BedrockTransformer::event_to_json(&message.unwrap()).unwrap();
+            transformer.to_universal_stream_chunk(&event).unwrap()
+        })
+        .collect();
+
+    let text: String = chunks
+        .iter()
+        .filter_map(|chunk| chunk.choices.first())
+        .filter_map(|choice| choice.delta.content.clone())
+        .collect();
+    assert_eq!(text, "Hello");
+
+    let tool_start = chunks[3].choices[0].delta.tool_calls.as_ref().unwrap();
+    assert_eq!(tool_start[0].id.as_deref(), Some("tooluse_1"));
+    assert_eq!(tool_start[0].index, 1);
+    assert_eq!(chunks[5].choices[0].finish_reason.as_deref(), Some("tool_calls"));
+    // The closing metadata event carries the token counts, cached prompt tokens included
+    let usage = chunks[6].usage.as_ref().unwrap();
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (1029, 7, 1036));
+    assert_eq!((usage.cache_read_tokens, usage.cache_creation_tokens), (Some(1000), Some(24)));
+    assert!(chunks[6].provider_metadata.is_none());
+
+    // A corrupted checksum is reported rather than silently decoded
+    let mut corrupted = event("messageStart", json!({"role": "assistant"})).encode();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0xff;
+    let results = EventStreamDecoder::new().feed(&corrupted);
+    assert!(results[0].is_err());
+
+    // Exceptions surface as provider errors
+    let mut exception = event("messageStart", json!({"message": "Rate exceeded"}));
+    exception.headers.insert(
+        ":message-type".to_string(),
+        EventStreamHeaderValue::String("exception".to_string()),
+    );
+    exception.headers.insert(
+        ":exception-type".to_string(),
+        EventStreamHeaderValue::String("throttlingException".to_string()),
+    );
+    let error = BedrockTransformer::event_to_json(&exception).unwrap_err();
+    assert!(error.to_string().contains("throttlingException"));
+}
+
+#[tokio::test]
+async fn test_bedrock_signed_request_against_stub() {
+    // 本地桩服务：校验签名头并返回 event-stream 帧
+    let app = Router::new().route(
+        "/model/{model}/converse-stream",
+        post(|Path(model): Path<String>, headers: HeaderMap, body: Bytes| async move {
+            let authorization = headers.get("authorization").unwrap().to_str().unwrap();
+            assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDSTUB/"));
+            assert!(authorization.contains("/eu-west-1/bedrock/aws4_request"));
+            assert!(authorization.contains("x-amz-security-token"));
+            assert_eq!(headers.get("x-amz-security-token").unwrap(), "session-token");
+            assert_eq!(model, "anthropic.claude-3-5-sonnet-20240620-v1:0");
+
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(request["messages"][0]["content"][0]["text"], "Hello");
+
+            let mut frames = Vec::new();
+            frames.extend(event("messageStart", json!({"role": "assistant"})).encode());
+            frames.extend(event("contentBlockDelta", json!({"contentBlockIndex": 0, "delta": {"text": "Hi!"}})).encode());
+            frames.extend(event("messageStop", json!({"stopReason": "end_turn"})).encode());
+            ([("content-type", "application/vnd.amazon.eventstream")], frames)
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let transformer = BedrockTransformer::from_options_value(&json!({
+        "region": "eu-west-1",
+        "access_key_id": "AKIDSTUB",
+        "secret_access_key": "stub-secret",
+        "session_token": "session-token"
+    }))
+    .unwrap();
+
+    let manager = TransformerManager::new();
+    let mut universal_request = manager
+        .to_universal_request(
+            "anthropic",
+            &json!({
+                "model": "anthropic.claude-3-5-sonnet-20240620-v1:0",
+                "max_tokens": 256,
+                "stream": true,
+                "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]
+            }),
+        )
+        .unwrap();
+    universal_request.stream = true;
+
+    let provider = bedrock_provider(&format!("http://{}", addr));
+    let upstream = transformer.build_upstream_request(&provider, &universal_request).unwrap();
+    assert!(upstream.url.ends_with("/model/anthropic.claude-3-5-sonnet-20240620-v1%3A0/converse-stream"));
+
+    let response = upstream.send(&reqwest::Client::new()).await.unwrap();
+    assert!(response.status().is_success());
+    let bytes = response.bytes().await.unwrap();
+
+    let mut decoder = EventStreamDecoder::new();
+    let text: String = decoder
+        .feed(&bytes)
+        .into_iter()
+        .map(|message| BedrockTransformer::event_to_json(&message.unwrap()).unwrap())
+        .map(|event| transformer.to_universal_stream_chunk(&event).unwrap())
+        .filter_map(|chunk| chunk.choices.first().and_then(|c| c.delta.content.clone()))
+        .collect();
+    assert_eq!(text, "Hi!");
+}
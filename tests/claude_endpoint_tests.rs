@@ -307,4 +307,102 @@ mod claude_endpoint_tests {
             response.status()
         );
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_transformers_endpoint_lists_registry() {
+        use code_routic::transformers::TransformerManager;
+        use code_routic::transformers::providers::OpenAITransformer;
+        use code_routic::transformers::transformer_manager::TransformerSource;
+
+        // 注册一个插件转换器
+        let mut transformers = TransformerManager::new();
+        transformers.register(
+            "my-gateway",
+            Box::new(OpenAITransformer::new()),
+            TransformerSource::Plugin { path: "/plugins/my-gateway.wasm".to_string() },
+        );
+        let app = ServerSetup::create_server_with_transformers(create_test_config_without_api_key(), transformers).await;
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/api/transformers")
+            .header("host", "127.0.0.1:3456")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let transformers = body["transformers"].as_array().unwrap();
+
+        // 列表与实际注册一致，包括内置转换器、别名和插件来源
+        let openai = transformers.iter().find(|t| t["name"] == "openai").unwrap();
+        assert_eq!(openai["source"]["type"], "builtin");
+        assert_eq!(openai["kind"], "provider");
+        assert!(openai["aliases"].as_array().unwrap().contains(&json!("openai-compatible")));
+        let plugin = transformers.iter().find(|t| t["name"] == "my-gateway").unwrap();
+        assert_eq!(plugin["source"], json!({"type": "plugin", "path": "/plugins/my-gateway.wasm"}));
+        assert!(transformers.iter().all(|t| t["name"] != "openai-compatible"));
+    }
+
+    #[tokio::test]
+    async fn test_route_preview_endpoint() {
+        use code_routic::config::types::Provider;
+
+        let mut config = create_test_config_without_api_key();
+        config.router.default = "deepseek,deepseek-chat".to_string();
+        config.router.think = Some("missing,reasoner".to_string());
+        config.providers.push(Provider {
+            name: "deepseek".to_string(),
+            api_base_url: "https://api.deepseek.com/chat/completions".to_string(),
+            api_key: "sk-secret".to_string(),
+            models: vec!["deepseek-chat".to_string()],
+            transformer: None,
+            capabilities: std::collections::HashMap::new(),
+        });
+        let app = ServerSetup::create_server(config).await;
+
+        let preview = |body: serde_json::Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/route/preview")
+                .header("content-type", "application/json")
+                .header("host", "127.0.0.1:3456")
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                .unwrap()
+        };
+        let request_body = json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 1000,
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]
+        });
+
+        let response = app.clone().oneshot(preview(request_body.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-ccr-route-reason"], "default; tokens=0");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        // 返回路由决定与将要发送的 OpenAI 格式请求，认证头被隐去
+        assert_eq!(body["decision"]["target"], "deepseek,deepseek-chat");
+        assert_eq!(body["decision"]["source"], "default");
+        let upstream = &body["upstream"];
+        assert_eq!(upstream["url"], "https://api.deepseek.com/chat/completions");
+        assert_eq!(upstream["body"]["model"], "deepseek-chat");
+        assert_eq!(upstream["body"]["messages"][0]["content"], "Hello");
+        assert!(upstream["headers"].to_string().contains("[redacted]"));
+        assert!(!body.to_string().contains("sk-secret"));
+
+        // 目标提供商未配置时仍返回决定，并附带错误
+        let mut request_body = request_body;
+        request_body["thinking"] = json!({"type": "enabled", "budget_tokens": 1024});
+        let response = app.oneshot(preview(request_body)).await.unwrap();
+        assert_eq!(response.headers()["x-ccr-route-reason"], "rule:think; tokens=0");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["decision"]["target"], "missing,reasoner");
+        assert!(body["upstream"].is_null());
+        assert!(body["error"].as_str().unwrap().contains("Provider 'missing' is not configured"));
+    }
+}
@@ -38,6 +38,8 @@ mod config_types_tests {
             non_interactive_mode: Some(false),
             api_timeout_ms: Some(30000),
             custom_router_path: Some("/custom/path".to_string()),
+            response_model: None,
+            project_roots: vec![],
             providers: vec![Provider {
                 name: "test_provider".to_string(),
                 api_base_url: "http://api.test".to_string(),
@@ -53,6 +55,7 @@ mod config_types_tests {
                         map
                     },
                 }),
+                capabilities: HashMap::new(),
             }],
             router: RouterConfig {
                 default: "test_provider,test_model".to_string(),
@@ -61,6 +64,7 @@ mod config_types_tests {
                 long_context: Some("long_context_provider".to_string()),
                 long_context_threshold: Some(50000),
                 web_search: Some("web_search_provider".to_string()),
+                rules: vec![],
             },
             transformers: Some(vec![TransformerConfig {
                 path: "/path/to/transformer".to_string(),
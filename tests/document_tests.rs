@@ -0,0 +1,141 @@
+//! 文档内容块测试模块
+//!
+//! 验证 Anthropic `document` 块（base64 PDF 与文本来源）在 Anthropic 与 Gemini 之间的原生映射，
+//! 以及不支持文档的目标（OpenAI 兼容等）按 `document_fallback` 回退为文本或拒绝请求。
+
+use code_routic::config::capabilities::{CapabilityRegistry, DocumentFallback, ModelCapabilities, ModelCapabilityOverrides};
+use code_routic::transformers::providers::provider_trait::{Document, DocumentSource};
+use code_routic::transformers::{GatewayError, GatewayErrorKind, TransformerManager};
+use serde_json::{json, Value};
+
+fn create_anthropic_request() -> Value {
+    json!({
+        "model": "claude-sonnet-4",
+        "max_tokens": 1024,
+        "messages": [{
+            "role": "user",
+            "content": [
+                {
+                    "type": "document",
+                    "source": {"type": "base64", "media_type": "application/pdf", "data": "JVBERi0xLjQK"},
+                    "title": "spec.pdf",
+                    "citations": {"enabled": true},
+                    "cache_control": {"type": "ephemeral"}
+                },
+                {
+                    "type": "document",
+                    "source": {"type": "text", "media_type": "text/plain", "data": "Rule one: be brief."},
+                    "title": "Style guide"
+                },
+                {"type": "text", "text": "Summarize these"}
+            ]
+        }]
+    })
+}
+
+#[test]
+fn test_anthropic_document_round_trip() {
+    let manager = TransformerManager::new();
+    let request = create_anthropic_request();
+    let universal = manager.to_universal_request("anthropic", &request).unwrap();
+    let value = serde_json::to_value(&universal.messages[0].content).unwrap();
+    assert_eq!(value[0]["type"], "document");
+    assert_eq!(value[0]["document"]["citations"], true);
+
+    let output = manager.transform_request("anthropic", "anthropic", &request).unwrap();
+    assert_eq!(output["messages"], request["messages"]);
+}
+
+#[test]
+fn test_documents_map_to_gemini_inline_data() {
+    let manager = TransformerManager::new();
+    let capabilities = CapabilityRegistry::builtin("gemini-2.5-pro").unwrap();
+    let (output, _) = manager
+        .transform_request_for_model("anthropic", "gemini", &create_anthropic_request(), &capabilities)
+        .unwrap();
+    let parts = &output["contents"][0]["parts"];
+    assert_eq!(parts[0], json!({"inline_data": {"mime_type": "application/pdf", "data": "JVBERi0xLjQK"}}));
+    assert_eq!(parts[1], json!({"text": "Style guide\n\nRule one: be brief."}));
+    assert_eq!(parts[2], json!({"text": "Summarize these"}));
+
+    // A PDF in a Gemini request comes back as a document part
+    let universal = manager.to_universal_request("gemini", &output).unwrap();
+    let value = serde_json::to_value(&universal.messages[0].content).unwrap();
+    assert_eq!(value[0]["type"], "document");
+    assert_eq!(
+        value[0]["document"]["source"],
+        json!({"type": "base64", "media_type": "application/pdf", "data": "JVBERi0xLjQK"})
+    );
+}
+
+#[test]
+fn test_openai_target_falls_back_to_text() {
+    let manager = TransformerManager::new();
+    // Unknown models are unrestricted, but the OpenAI format has no document block. By
+    // default a PDF is rejected, since its text is not extracted
+    let error = manager
+        .transform_request_with_tool_names("anthropic", "openai", &create_anthropic_request())
+        .unwrap_err();
+    assert!(error.to_string().contains("does not support document input"), "{}", error);
+
+    // Text documents are inlined
+    let mut request = create_anthropic_request();
+    request["messages"][0]["content"].as_array_mut().unwrap().remove(0);
+    let (output, _) = manager.transform_request_with_tool_names("anthropic", "openai", &request).unwrap();
+    let text = serde_json::to_string(&output["messages"][0]["content"]).unwrap();
+    assert!(text.contains("Style guide\\n\\nRule one: be brief."), "{}", text);
+
+    // The `text` fallback replaces binary documents with a placeholder
+    let capabilities = ModelCapabilities::unrestricted().with_overrides(&ModelCapabilityOverrides {
+        document_fallback: Some(DocumentFallback::Text),
+        ..Default::default()
+    });
+    let (output, _) = manager
+        .transform_request_for_model("anthropic", "openai", &create_anthropic_request(), &capabilities)
+        .unwrap();
+    let content = &output["messages"][0]["content"];
+    let text = serde_json::to_string(content).unwrap();
+    assert!(text.contains("spec.pdf\\n\\n[document omitted: model does not support application/pdf input]"), "{}", text);
+    assert!(text.contains("Style guide\\n\\nRule one: be brief."), "{}", text);
+    assert!(text.contains("Summarize these"), "{}", text);
+
+    let document = Document {
+        source: DocumentSource::Content { content: json!([{"type": "text", "text": "a"}, {"type": "text", "text": "b"}]) },
+        title: None,
+        context: None,
+        citations: None,
+    };
+    assert_eq!(document.fallback_text(), "a\nb");
+    let document = Document { source: DocumentSource::Url { url: "https://example.com/a.pdf".to_string() }, ..document };
+    assert_eq!(document.fallback_text(), "[document: https://example.com/a.pdf]");
+}
+
+#[test]
+fn test_reject_fallback() {
+    let manager = TransformerManager::new();
+    let capabilities = ModelCapabilities::unrestricted().with_overrides(&ModelCapabilityOverrides {
+        document_fallback: Some(DocumentFallback::Reject),
+        ..Default::default()
+    });
+    let error = manager
+        .transform_request_for_model("anthropic", "openai", &create_anthropic_request(), &capabilities)
+        .unwrap_err();
+    let error = GatewayError::from(error);
+    assert_eq!(error.kind, GatewayErrorKind::InvalidRequestError);
+    assert!(error.message.contains("does not support document input"), "{}", error.message);
+
+    // Native targets are unaffected, and requests without documents pass
+    assert!(manager
+        .transform_request_for_model("anthropic", "anthropic", &create_anthropic_request(), &capabilities)
+        .is_ok());
+    let request = json!({"model": "gpt-4o", "max_tokens": 10, "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}]});
+    assert!(manager.transform_request_for_model("anthropic", "openai", &request, &capabilities).is_ok());
+
+    let overrides: ModelCapabilityOverrides =
+        serde_json::from_value(json!({"documents": false, "document_fallback": "reject"})).unwrap();
+    let capabilities = CapabilityRegistry::builtin("claude-sonnet-4").unwrap().with_overrides(&overrides);
+    assert!(!capabilities.documents);
+    assert_eq!(capabilities.document_fallback, DocumentFallback::Reject);
+    assert!(CapabilityRegistry::builtin("claude-3-5-sonnet").unwrap().documents);
+    assert!(!CapabilityRegistry::builtin("gpt-4o").unwrap().documents);
+}
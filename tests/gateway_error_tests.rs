@@ -0,0 +1,145 @@
+//! 网关错误测试模块
+//!
+//! 验证上游 HTTP 错误（OpenAI、Gemini、Anthropic 及非 JSON 响应）到 Anthropic 错误格式与
+//! HTTP 状态码的映射、转换错误按请求侧与响应侧区分、流式响应中途的 `event: error`，以及认证失败时的错误响应。
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Method, Request, StatusCode};
+use axum::response::IntoResponse;
+use code_routic::config::types::Config;
+use code_routic::server::server::ServerSetup;
+use code_routic::transformers::stream::EventStreamDecoder;
+use code_routic::transformers::{GatewayError, GatewayErrorKind, TransformerError, TransformerManager};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn upstream(status: u16, body: Value) -> GatewayError {
+    GatewayError::from_upstream(status, body.to_string().as_bytes())
+}
+
+#[test]
+fn test_status_mapping() {
+    let cases = [
+        (400, GatewayErrorKind::InvalidRequestError, 400),
+        (401, GatewayErrorKind::AuthenticationError, 401),
+        (403, GatewayErrorKind::PermissionError, 403),
+        (404, GatewayErrorKind::NotFoundError, 404),
+        (413, GatewayErrorKind::RequestTooLarge, 413),
+        (422, GatewayErrorKind::InvalidRequestError, 400),
+        (429, GatewayErrorKind::RateLimitError, 429),
+        (500, GatewayErrorKind::ApiError, 500),
+        (502, GatewayErrorKind::ApiError, 500),
+        (503, GatewayErrorKind::OverloadedError, 529),
+        (529, GatewayErrorKind::OverloadedError, 529),
+    ];
+    for (status, kind, anthropic_status) in cases {
+        assert_eq!(GatewayErrorKind::from_status(status), kind, "{}", status);
+        assert_eq!(kind.status(), anthropic_status);
+    }
+}
+
+#[test]
+fn test_upstream_error_bodies() {
+    // OpenAI
+    let error = upstream(429, json!({"error": {"message": "Rate limit reached", "type": "requests", "code": "rate_limit_exceeded"}}));
+    assert_eq!((error.kind, error.message.as_str(), error.upstream_status), (GatewayErrorKind::RateLimitError, "Rate limit reached", Some(429)));
+    // Billing failures are not reported as retryable rate limits
+    let error = upstream(429, json!({"error": {"message": "You exceeded your current quota", "type": "insufficient_quota", "code": "insufficient_quota"}}));
+    assert_eq!((error.kind, error.status()), (GatewayErrorKind::PermissionError, 403));
+    let error = upstream(400, json!({"error": {"message": "Too long", "type": "invalid_request_error", "code": "context_length_exceeded"}}));
+    assert_eq!(error.kind, GatewayErrorKind::InvalidRequestError);
+    // The body's code wins over a generic status
+    let error = upstream(500, json!({"error": {"message": "Engine overloaded", "type": "server_error", "code": "engine_overloaded"}}));
+    assert_eq!(error.kind, GatewayErrorKind::OverloadedError);
+
+    // Gemini, including the array-wrapped form
+    let error = upstream(429, json!([{"error": {"code": 429, "message": "Quota exceeded", "status": "RESOURCE_EXHAUSTED"}}]));
+    assert_eq!((error.kind, error.message.as_str()), (GatewayErrorKind::RateLimitError, "Quota exceeded"));
+    let error = upstream(503, json!({"error": {"code": 503, "message": "The model is overloaded", "status": "UNAVAILABLE"}}));
+    assert_eq!(error.kind, GatewayErrorKind::OverloadedError);
+    assert_eq!(error.status(), 529);
+    let error = upstream(400, json!({"error": {"code": 400, "message": "Bad schema", "status": "INVALID_ARGUMENT"}}));
+    assert_eq!(error.kind, GatewayErrorKind::InvalidRequestError);
+
+    // Anthropic passes through unchanged
+    let error = upstream(529, json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}));
+    assert_eq!(error.to_anthropic(), json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}));
+
+    // Unknown codes and non-JSON bodies fall back to the status
+    let error = upstream(401, json!({"error": {"message": "Bad key", "code": "weird"}}));
+    assert_eq!(error.kind, GatewayErrorKind::AuthenticationError);
+    let error = GatewayError::from_upstream(502, b"<html>Bad Gateway</html>");
+    assert_eq!((error.kind, error.message.as_str()), (GatewayErrorKind::ApiError, "<html>Bad Gateway</html>"));
+    let error = GatewayError::from_upstream(502, b"");
+    assert_eq!(error.message, "Upstream returned HTTP 502");
+}
+
+#[test]
+fn test_stream_errors() {
+    let error = GatewayError::from_stream_event(&json!({"error": {"message": "Overloaded", "type": "server_error", "code": "overloaded"}})).unwrap();
+    assert_eq!(error.kind, GatewayErrorKind::OverloadedError);
+    assert_eq!(
+        error.to_sse_event(),
+        "event: error\ndata: {\"error\":{\"message\":\"Overloaded\",\"type\":\"overloaded_error\"},\"type\":\"error\"}\n\n"
+    );
+
+    let error = GatewayError::from_stream_event(&json!({"type": "error", "error": {"type": "rate_limit_error", "message": "Slow down"}})).unwrap();
+    assert_eq!(error.kind, GatewayErrorKind::RateLimitError);
+    let error = GatewayError::from_stream_event(&json!({"error": {"code": 500, "message": "Internal"}})).unwrap();
+    assert_eq!(error.kind, GatewayErrorKind::ApiError);
+
+    let chunk = json!({"id": "chatcmpl-1", "choices": [{"index": 0, "delta": {"content": "Hi"}}]});
+    assert!(GatewayError::from_stream_event(&chunk).is_none());
+}
+
+#[test]
+fn test_transformer_errors() {
+    let error = GatewayError::from(TransformerError::Deserialization("missing field `messages`".to_string()));
+    assert_eq!(error.kind, GatewayErrorKind::InvalidRequestError);
+    assert_eq!(error.message, "Deserialization error: missing field `messages`");
+    let error = GatewayError::from(TransformerError::Plugin("trap".to_string()));
+    assert_eq!(error.kind, GatewayErrorKind::ApiError);
+
+    // An upstream body that does not parse is the upstream's fault, not the client's
+    let manager = TransformerManager::new();
+    let error = manager
+        .to_universal_response("openai", &json!({"id": "chatcmpl-1", "choices": "not a list"}))
+        .unwrap_err();
+    assert!(matches!(error, TransformerError::Deserialization(_)), "{:?}", error);
+    let error = GatewayError::from_response_error(error);
+    assert_eq!((error.kind, error.status()), (GatewayErrorKind::ApiError, 500));
+
+    // So is a truncated Bedrock event stream
+    let mut decoder = EventStreamDecoder::new();
+    assert!(decoder.feed(&[0, 0, 0, 64, 0, 0, 0, 0]).is_empty());
+    let error = GatewayError::from_response_error(decoder.finish().unwrap().unwrap_err());
+    assert_eq!(error.kind, GatewayErrorKind::ApiError);
+    assert_eq!(error.message, "Invalid format: Truncated event-stream frame: 8 trailing bytes");
+}
+
+#[tokio::test]
+async fn test_error_responses() {
+    let response = upstream(503, json!({"error": {"status": "UNAVAILABLE", "message": "Try later"}})).into_response();
+    assert_eq!(response.status().as_u16(), 529);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, json!({"type": "error", "error": {"type": "overloaded_error", "message": "Try later"}}));
+
+    // Authentication failures use the same format
+    let config = Config { api_key: Some("secret".to_string()), ..Config::default() };
+    let app = ServerSetup::create_server(config).await;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/messages")
+        .header("content-type", "application/json")
+        .header("x-api-key", "wrong")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"]["type"], "authentication_error");
+    assert_eq!(body["error"]["message"], "Invalid API key");
+}
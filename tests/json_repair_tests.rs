@@ -0,0 +1,268 @@
+//! 工具调用参数修复测试模块
+//!
+//! 验证对弱模型输出的畸形 JSON 参数的容错修复、修复失败时基于工具 schema 的回退，
+//! 以及 OpenAI 兼容响应（完整响应与流式分片）转换为 Anthropic `tool_use` 时的修复。
+
+use code_routic::config::capabilities::ModelCapabilities;
+use code_routic::config::types::Provider;
+use code_routic::transformers::json_repair::{matches_schema, parse_tool_arguments, repair_json};
+use code_routic::transformers::stream::encode_sse_event;
+use code_routic::transformers::TransformerManager;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn create_anthropic_request() -> Value {
+    json!({
+        "model": "deepseek-chat",
+        "max_tokens": 1024,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "List the src directory"}]}],
+        "tools": [
+            {
+                "name": "Bash",
+                "description": "Run a command",
+                "input_schema": {
+                    "type": "object",
+                    "properties": {"command": {"type": "string"}, "timeout": {"type": "number"}},
+                    "required": ["command"]
+                }
+            },
+            {
+                "name": "Read",
+                "description": "Read a file",
+                "input_schema": {
+                    "type": "object",
+                    "properties": {"file_path": {"type": "string"}, "limit": {"type": "integer"}},
+                    "required": ["file_path"]
+                }
+            }
+        ]
+    })
+}
+
+fn create_openai_tool_response(arguments: &[(&str, &str)]) -> Value {
+    let tool_calls: Vec<Value> = arguments
+        .iter()
+        .enumerate()
+        .map(|(index, (name, arguments))| {
+            json!({"id": format!("call_{}", index), "type": "function", "function": {"name": name, "arguments": arguments}})
+        })
+        .collect();
+    json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "deepseek-chat",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": null, "tool_calls": tool_calls},
+            "finish_reason": "tool_calls"
+        }],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+    })
+}
+
+fn create_openai_stream_chunk(tool_calls: Value, finish_reason: Option<&str>) -> Value {
+    json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "deepseek-chat",
+        "choices": [{"index": 0, "delta": {"tool_calls": tool_calls}, "finish_reason": finish_reason}]
+    })
+}
+
+#[test]
+fn test_repair_json_common_model_mistakes() {
+    let cases = [
+        (r#"{"command": "ls",}"#, json!({"command": "ls"})),
+        (r#"{'command': 'ls -la', 'timeout': 10}"#, json!({"command": "ls -la", "timeout": 10})),
+        ("```json\n{\"command\": \"ls\"}\n```", json!({"command": "ls"})),
+        ("Here you go: {\"command\": \"ls\"} hope that helps", json!({"command": "ls"})),
+        (r#"{command: "ls", recursive: True, depth: None}"#, json!({"command": "ls", "recursive": true, "depth": null})),
+        (r#"{"command": "echo hi"#, json!({"command": "echo hi"})),
+        (r#"{"paths": ["a", "b""#, json!({"paths": ["a", "b"]})),
+        (r#"{"a": 1 "b": 2}"#, json!({"a": 1, "b": 2})),
+        (r#"{"a": .5, "b": +3, "c": 2.}"#, json!({"a": 0.5, "b": 3, "c": 2.0})),
+        ("{\"text\": \"line one\nline two\"}", json!({"text": "line one\nline two"})),
+        (r#"{'quote': 'say "hi"', 'it': 'it\'s'}"#, json!({"quote": "say \"hi\"", "it": "it's"})),
+        ("{\"a\": 1, // comment\n \"b\": /* inline */ 2}", json!({"a": 1, "b": 2})),
+        (r#"{"a": {"b": [1, 2}, "c": 3}"#, json!({"a": {"b": [1, 2]}, "c": 3})),
+        (r#"{"key":"#, json!({"key": null})),
+        (r#"{"key""#, json!({"key": null})),
+        (r#"{"escaped": "tab\there é"}"#, json!({"escaped": "tab\there é"})),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(repair_json(input), Some(expected), "{}", input);
+    }
+
+    assert_eq!(repair_json("no json here"), None);
+    assert_eq!(repair_json(""), None);
+    assert_eq!(repair_json("[1, 2,]"), Some(json!([1, 2])));
+}
+
+#[test]
+fn test_parse_tool_arguments_falls_back_to_schema() {
+    let bash = json!({
+        "type": "object",
+        "properties": {"command": {"type": "string"}, "timeout": {"type": "number"}},
+        "required": ["command"]
+    });
+
+    // Valid arguments are returned as is, even if they do not match the schema
+    assert_eq!(parse_tool_arguments(r#"{"cmd": "ls"}"#, Some(&bash)), json!({"cmd": "ls"}));
+    // Repaired arguments that match the schema
+    assert_eq!(parse_tool_arguments("{'command': 'ls'", Some(&bash)), json!({"command": "ls"}));
+
+    // Unrepairable text becomes the single required string parameter
+    assert_eq!(parse_tool_arguments("ls -la src", Some(&bash)), json!({"command": "ls -la src"}));
+    assert_eq!(parse_tool_arguments("```\nls -la\n```", Some(&bash)), json!({"command": "ls -la"}));
+    assert_eq!(parse_tool_arguments(r#""git status""#, Some(&bash)), json!({"command": "git status"}));
+
+    // A repaired object is kept even when it misses required fields
+    assert_eq!(parse_tool_arguments("{'timeout': 5", Some(&bash)), json!({"timeout": 5}));
+
+    // Without a usable schema the last resort is an empty object
+    let multi = json!({"type": "object", "properties": {"a": {"type": "string"}, "b": {"type": "string"}}, "required": ["a", "b"]});
+    assert_eq!(parse_tool_arguments("garbage", Some(&multi)), json!({}));
+    assert_eq!(parse_tool_arguments("garbage", None), json!({}));
+    assert_eq!(parse_tool_arguments("", None), json!({}));
+
+    assert!(matches_schema(&json!({"command": "ls", "timeout": 1}), &bash));
+    assert!(!matches_schema(&json!({"command": 1}), &bash));
+    assert!(!matches_schema(&json!({"timeout": 1}), &bash));
+    assert!(matches_schema(&json!({"n": 3.0}), &json!({"properties": {"n": {"type": "integer"}}})));
+    assert!(!matches_schema(&json!({"mode": "x"}), &json!({"properties": {"mode": {"enum": ["a", "b"]}}})));
+    assert!(!matches_schema(&json!({"items": [1, "a"]}), &json!({"properties": {"items": {"type": "array", "items": {"type": "integer"}}}})));
+}
+
+#[test]
+fn test_openai_tool_call_arguments_repaired_for_anthropic_response() {
+    let manager = TransformerManager::new();
+    let (_, tool_names) = manager
+        .transform_request_with_tool_names("anthropic", "openai", &create_anthropic_request())
+        .unwrap();
+
+    let response = create_openai_tool_response(&[
+        ("Bash", "{'command': 'ls src',}"),
+        ("Read", "src/main.rs"),
+        ("Bash", r#"{"command": "pwd"}"#),
+    ]);
+    let output = manager
+        .transform_response_with_tool_names("openai", "anthropic", &response, &tool_names)
+        .unwrap();
+
+    assert_eq!(output["stop_reason"], "tool_use");
+    let content = output["content"].as_array().unwrap();
+    // The empty text block from a null content is dropped
+    assert_eq!(content.len(), 3, "{}", output);
+    assert_eq!(content[0]["type"], "tool_use");
+    assert_eq!(content[0]["id"], "call_0");
+    assert_eq!(content[0]["name"], "Bash");
+    assert_eq!(content[0]["input"], json!({"command": "ls src"}));
+    assert_eq!(content[1]["input"], json!({"file_path": "src/main.rs"}));
+    assert_eq!(content[2]["input"], json!({"command": "pwd"}));
+
+    // Without the request's schemas the conversion still yields an object
+    let output = manager.transform_response("openai", "anthropic", &response).unwrap();
+    assert_eq!(output["content"][0]["input"], json!({"command": "ls src"}));
+    assert_eq!(output["content"][1]["input"], json!({}));
+}
+
+#[test]
+fn test_streamed_tool_call_fragments_repaired_when_complete() {
+    let manager = TransformerManager::new();
+    let provider = Provider {
+        name: "deepseek".to_string(),
+        api_base_url: "https://api.deepseek.com/chat/completions".to_string(),
+        api_key: "key".to_string(),
+        models: vec!["deepseek-chat".to_string()],
+        transformer: Some(serde_json::from_value(json!({"use": ["openai"]})).unwrap()),
+        capabilities: HashMap::new(),
+    };
+    let chain = manager.resolve_chain(&provider, "deepseek-chat").unwrap();
+    let (_, tool_names) = manager
+        .transform_request_with_chain("anthropic", &chain, &create_anthropic_request(), &ModelCapabilities::unrestricted())
+        .unwrap();
+
+    let chunks = [
+        create_openai_stream_chunk(
+            json!([{"index": 0, "id": "call_0", "type": "function", "function": {"name": "Bash", "arguments": "{'comm"}}]),
+            None,
+        ),
+        create_openai_stream_chunk(json!([{"index": 0, "function": {"arguments": "and': 'ls',"}}]), None),
+        // The second call starts: the first call's arguments are released whole
+        create_openai_stream_chunk(
+            json!([{"index": 1, "id": "call_1", "type": "function", "function": {"name": "Read", "arguments": "src/"}}]),
+            None,
+        ),
+        create_openai_stream_chunk(json!([{"index": 1, "function": {"arguments": "lib.rs"}}]), Some("tool_calls")),
+    ];
+    let outputs: Vec<Value> = chunks
+        .iter()
+        .map(|chunk| {
+            manager
+                .transform_stream_chunk_with_chain(&chain, "openai", chunk, &tool_names)
+                .unwrap()
+        })
+        .collect();
+
+    // Fragments are held back; names and ids pass through immediately
+    let first = &outputs[0]["choices"][0]["delta"]["tool_calls"][0];
+    assert_eq!(first["function"]["name"], "Bash");
+    assert!(first["function"]["arguments"].is_null());
+    assert!(outputs[1]["choices"][0]["delta"]["tool_calls"].is_null());
+
+    let released = outputs[2]["choices"][0]["delta"]["tool_calls"].as_array().unwrap();
+    assert_eq!(released[0]["index"], 0);
+    assert_eq!(released[0]["function"]["arguments"], r#"{"command":"ls"}"#);
+    assert_eq!(released[1]["function"]["name"], "Read");
+
+    let released = &outputs[3]["choices"][0]["delta"]["tool_calls"][0];
+    assert_eq!(released["index"], 1);
+    assert_eq!(released["function"]["arguments"], r#"{"file_path":"src/lib.rs"}"#);
+    assert_eq!(outputs[3]["choices"][0]["finish_reason"], "tool_calls");
+
+    // Anthropic clients receive the repaired arguments as an input_json_delta
+    let chain = manager.resolve_chain(&provider, "deepseek-chat").unwrap();
+    let events: Vec<Value> = chunks
+        .iter()
+        .map(|chunk| {
+            manager
+                .transform_stream_chunk_with_chain(&chain, "anthropic", chunk, &tool_names)
+                .unwrap()
+        })
+        .collect();
+    // The first event opens the message, then the first call's block
+    let opened = events[0].as_array().unwrap();
+    assert_eq!(opened[0]["type"], "message_start");
+    assert_eq!(opened[1]["type"], "content_block_start");
+    assert_eq!(opened[1]["index"], 0);
+    assert_eq!(opened[1]["content_block"], json!({"type": "tool_use", "id": "call_0", "name": "Bash", "input": {}}));
+    assert_eq!(events[1], json!([]));
+
+    // The released arguments, the first block's stop and the next call's start arrive together
+    let released = events[2].as_array().unwrap();
+    let types: Vec<&str> = released.iter().map(|event| event["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["content_block_delta", "content_block_stop", "content_block_start"]);
+    assert_eq!(released[0]["index"], 0);
+    assert_eq!(released[0]["delta"], json!({"type": "input_json_delta", "partial_json": "{\"command\":\"ls\"}"}));
+    assert_eq!(released[1]["index"], 0);
+    assert_eq!(released[2]["index"], 1);
+    assert_eq!(released[2]["content_block"]["name"], "Read");
+
+    // The finish closes the last block; without usage, the stop waits for the stream's end
+    let last = events[3].as_array().unwrap();
+    let types: Vec<&str> = last.iter().map(|event| event["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["content_block_delta", "content_block_stop"]);
+    assert_eq!(last[0]["index"], 1);
+    assert_eq!(last[0]["delta"]["partial_json"], "{\"file_path\":\"src/lib.rs\"}");
+    assert_eq!(last[1]["index"], 1);
+    let mut events = events;
+    events.push(chain.finish_stream().unwrap());
+    let closing = events[4].as_array().unwrap();
+    assert_eq!(closing[0]["type"], "message_delta");
+    assert_eq!(closing[0]["delta"]["stop_reason"], "tool_use");
+    assert_eq!(closing[1]["type"], "message_stop");
+    let body: String = events.iter().map(encode_sse_event).collect();
+    assert!(body.ends_with("event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"));
+}
@@ -0,0 +1,197 @@
+//! 项目级配置测试模块
+//!
+//! 验证项目目录树中的 `.code-routic.json` 覆盖 `Router` 字段、阈值与规则并新增提供商，
+//! 项目目录限于 `PROJECT_ROOTS`，以及服务器按 `x-ccr-project` 请求头逐请求解析生效的配置。
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Method, Request, StatusCode};
+use code_routic::config::project::{ProjectConfig, ProjectConfigCache};
+use code_routic::config::types::{Config, Provider};
+use code_routic::router::route_logic::{RouteLogic, RouteRequest};
+use code_routic::server::server::ServerSetup;
+use serde_json::json;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tower::ServiceExt;
+
+fn create_provider(name: &str, api_base_url: &str) -> Provider {
+    Provider {
+        name: name.to_string(),
+        api_base_url: api_base_url.to_string(),
+        api_key: "test_key".to_string(),
+        models: vec![],
+        transformer: None,
+        capabilities: HashMap::new(),
+    }
+}
+
+fn create_config() -> Config {
+    let mut config = Config::default();
+    config.router.default = "openrouter,anthropic/claude-sonnet-4".to_string();
+    config.router.think = Some("openrouter,anthropic/claude-opus-4".to_string());
+    config.router.long_context = Some("gemini,gemini-2.5-pro".to_string());
+    config.router.rules = serde_json::from_value(json!([
+        {"name": "global", "tokens_below": 100, "target": "openrouter,anthropic/claude-3-5-haiku"}
+    ]))
+    .unwrap();
+    config.providers = vec![
+        create_provider("openrouter", "https://openrouter.ai/api/v1/chat/completions"),
+        create_provider("gemini", "https://generativelanguage.googleapis.com/v1beta/models"),
+    ];
+    config
+}
+
+fn create_project_config() -> ProjectConfig {
+    serde_json::from_value(json!({
+        "Router": {
+            "default": "deepseek,deepseek-chat",
+            "long_context_threshold": 20000,
+            "rules": [{"name": "project", "tokens_below": 200, "target": "deepseek,deepseek-coder"}]
+        },
+        "Providers": [
+            {"name": "deepseek", "api_base_url": "https://api.deepseek.com/chat/completions", "api_key": "sk-project", "models": ["deepseek-chat"]},
+            {"name": "gemini", "api_base_url": "https://gemini.internal/v1beta/models", "api_key": "sk-gemini", "models": []}
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_project_config_overrides_router_and_providers() {
+    let config = create_config();
+    let effective = create_project_config().apply(&config);
+
+    // Set slots and thresholds are replaced, unset ones keep the global value
+    assert_eq!(effective.router.default, "deepseek,deepseek-chat");
+    assert_eq!(effective.router.think.as_deref(), Some("openrouter,anthropic/claude-opus-4"));
+    assert_eq!(effective.router.long_context_threshold, Some(20000));
+    let rules: Vec<&str> = effective.router.rules.iter().map(|rule| rule.label()).collect();
+    assert_eq!(rules, ["project", "global"]);
+
+    // New providers are added; one with a global name cannot replace the global definition
+    let names: Vec<&str> = effective.providers.iter().map(|provider| provider.name.as_str()).collect();
+    assert_eq!(names, ["openrouter", "gemini", "deepseek"]);
+    assert_eq!(effective.providers[1].api_base_url, "https://generativelanguage.googleapis.com/v1beta/models");
+    assert_eq!(effective.providers[1].api_key, "test_key");
+
+    let req = RouteRequest::from_anthropic_body(&json!({"model": "claude-sonnet-4"}), HashMap::new());
+    assert_eq!(RouteLogic::get_use_model(&req, 50, &effective, None), "deepseek,deepseek-coder");
+    assert_eq!(RouteLogic::get_use_model(&req, 30000, &effective, None), "gemini,gemini-2.5-pro");
+    assert_eq!(RouteLogic::get_use_model(&req, 1000, &effective, None), "deepseek,deepseek-chat");
+    // The global config itself is untouched
+    assert_eq!(RouteLogic::get_use_model(&req, 30000, &config, None), "openrouter,anthropic/claude-sonnet-4");
+}
+
+#[test]
+fn test_project_config_lookup() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("services/api/src");
+    std::fs::create_dir_all(&nested).unwrap();
+    let path = dir.path().join("services/.code-routic.json");
+    std::fs::write(&path, json!({"Router": {"default": "deepseek,deepseek-chat"}}).to_string()).unwrap();
+
+    // The nearest file up the tree applies
+    assert_eq!(ProjectConfig::find(&nested), Some(path.clone()));
+    assert_eq!(ProjectConfig::find(dir.path()), None);
+
+    // Only directories under PROJECT_ROOTS may name a project
+    let mut config = create_config();
+    assert!(matches!(ProjectConfig::resolve(&config, nested.to_str()), Cow::Borrowed(_)));
+    config.project_roots = vec![dir.path().join("services/api").to_string_lossy().to_string()];
+    assert_eq!(ProjectConfig::find_allowed(&config, &nested), None);
+    let escaped = dir.path().join("services/api/..");
+    assert_eq!(ProjectConfig::find_allowed(&config, &escaped), None);
+    config.project_roots = vec![dir.path().to_string_lossy().to_string()];
+    assert_eq!(ProjectConfig::find_allowed(&config, &escaped).unwrap(), path.canonicalize().unwrap());
+
+    let resolved = ProjectConfig::resolve(&config, nested.to_str());
+    assert!(matches!(resolved, Cow::Owned(_)));
+    assert_eq!(resolved.router.default, "deepseek,deepseek-chat");
+    assert!(matches!(ProjectConfig::resolve(&config, None), Cow::Borrowed(_)));
+    assert!(matches!(ProjectConfig::resolve(&config, dir.path().to_str()), Cow::Borrowed(_)));
+
+    // A broken project file is ignored rather than failing the request
+    std::fs::write(&path, "{\"Router\": ").unwrap();
+    assert!(ProjectConfig::load(&path).is_err());
+    let resolved = ProjectConfig::resolve(&config, nested.to_str());
+    assert_eq!(resolved.router.default, "openrouter,anthropic/claude-sonnet-4");
+}
+
+#[test]
+fn test_project_config_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(".code-routic.json");
+    let write = |default: &str, age: u64| {
+        std::fs::write(&path, json!({"Router": {"default": default}}).to_string()).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    };
+    let cache = ProjectConfigCache::new();
+
+    // An unchanged file is parsed once
+    write("deepseek,deepseek-chat", 20);
+    let first = cache.load(&path).unwrap();
+    assert!(Arc::ptr_eq(&first, &cache.load(&path).unwrap()));
+
+    // A new modification time reloads it
+    write("deepseek,deepseek-coder", 10);
+    let mut config = create_config();
+    config.project_roots = vec![dir.path().to_string_lossy().to_string()];
+    let resolved = cache.resolve(&config, dir.path().to_str());
+    assert_eq!(resolved.router.default, "deepseek,deepseek-coder");
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(cache.load(&path).is_err());
+    assert!(matches!(cache.resolve(&config, dir.path().to_str()), Cow::Borrowed(_)));
+}
+
+#[tokio::test]
+async fn test_project_header_selects_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join(".code-routic.json"),
+        serde_json::to_string(&create_project_config()).unwrap(),
+    )
+    .unwrap();
+    let mut config = create_config();
+    config.project_roots = vec![dir.path().to_string_lossy().to_string()];
+    let app = ServerSetup::create_server(config).await;
+
+    let preview = |project: Option<&str>| {
+        let mut request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/route/preview")
+            .header("content-type", "application/json")
+            .header("host", "127.0.0.1:3456");
+        if let Some(project) = project {
+            request = request.header("x-ccr-project", project);
+        }
+        let body = json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 1000,
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]
+        });
+        request.body(Body::from(body.to_string())).unwrap()
+    };
+
+    let response = app.clone().oneshot(preview(dir.path().to_str())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["decision"]["target"], "deepseek,deepseek-coder");
+    assert_eq!(body["decision"]["matched"], "project");
+    assert_eq!(body["upstream"]["url"], "https://api.deepseek.com/chat/completions");
+    assert_eq!(body["upstream"]["body"]["model"], "deepseek-coder");
+
+    // Without the header, or with a directory outside PROJECT_ROOTS, the global config applies
+    let outside = tempfile::tempdir().unwrap();
+    std::fs::copy(dir.path().join(".code-routic.json"), outside.path().join(".code-routic.json")).unwrap();
+    for project in [None, outside.path().to_str()] {
+        let response = app.clone().oneshot(preview(project)).await.unwrap();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["decision"]["target"], "openrouter,anthropic/claude-3-5-haiku");
+    }
+}
@@ -1,9 +1,13 @@
+use code_routic::config::types::Provider;
 use code_routic::transformers::{
-    providers::{OpenAITransformer, AnthropicTransformer, GeminiTransformer, ProviderTransformer},
+    providers::{OpenAITransformer, AnthropicTransformer, GeminiTransformer, OpenRouterTransformer, OllamaTransformer, AzureOpenAITransformer, ProviderTransformer},
     TransformerManager,
     error::TransformerResult,
+    stream::{NdjsonDecoder, StreamFormat},
 };
+use code_routic::transformers::providers::provider_trait::MessageContent;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 
 fn create_test_tool_definition() -> Value {
     json!({
@@ -586,4 +590,1608 @@ fn test_error_handling() {
     
     let result = manager.to_universal_request("nonexistent", &test_request);
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_openrouter_provider_preferences_and_reasoning() {
+    let transformer = OpenRouterTransformer::from_options_value(&json!({
+        "provider": {
+            "order": ["anthropic", "amazon-bedrock"],
+            "allow_fallbacks": false
+        },
+        "reasoning": {"effort": "high"}
+    })).unwrap();
+    
+    let request = json!({
+        "model": "anthropic/claude-sonnet-4",
+        "messages": create_test_messages(),
+        "max_tokens": 1000
+    });
+    
+    let universal_request = transformer.to_universal_request(&request).unwrap();
+    let openrouter_request = transformer.from_universal_request(&universal_request).unwrap();
+    
+    assert_eq!(openrouter_request["provider"]["order"], json!(["anthropic", "amazon-bedrock"]));
+    assert_eq!(openrouter_request["provider"]["allow_fallbacks"], json!(false));
+    assert!(openrouter_request["provider"].get("only").is_none());
+    assert_eq!(openrouter_request["reasoning"]["effort"], "high");
+    
+    let headers = transformer.request_headers();
+    assert!(headers.contains_key("HTTP-Referer"));
+    assert_eq!(headers.get("X-Title"), Some(&"CodeRoutic".to_string()));
+}
+
+#[test]
+fn test_openrouter_maps_anthropic_thinking_and_cache_control() {
+    let manager = TransformerManager::new();
+    
+    let anthropic_request = json!({
+        "model": "anthropic/claude-sonnet-4",
+        "max_tokens": 2048,
+        "thinking": {"type": "enabled", "budget_tokens": 1024},
+        "messages": [{
+            "role": "user",
+            "content": [
+                {"type": "tool_result", "tool_use_id": "toolu_1", "content": "42"},
+                {"type": "text", "text": "Long shared context"},
+                {"type": "text", "text": "Question", "cache_control": {"type": "ephemeral"}}
+            ]
+        }]
+    });
+    
+    let universal_request = manager.to_universal_request("anthropic", &anthropic_request).unwrap();
+    let openrouter_request = manager.from_universal_request("openrouter", &universal_request).unwrap();
+    
+    assert_eq!(openrouter_request["reasoning"]["max_tokens"], 1024);
+    // Only text parts become text blocks, as for plain OpenAI content
+    let content = openrouter_request["messages"][0]["content"].as_array().unwrap();
+    assert_eq!(content.len(), 2);
+    assert_eq!(content[0]["text"], "Long shared context");
+    assert!(content[0].get("cache_control").is_none());
+    assert_eq!(content[1]["cache_control"]["type"], "ephemeral");
+    
+    // Non-Anthropic models keep plain string content
+    let mut universal_request = universal_request;
+    universal_request.model = "openai/gpt-4o".to_string();
+    let openrouter_request = manager.from_universal_request("openrouter", &universal_request).unwrap();
+    assert!(openrouter_request["messages"][0]["content"].is_string());
+
+    
+    // Images stay in place; the marker goes on the cached entry only
+    let openai_request = json!({
+        "model": "anthropic/claude-sonnet-4",
+        "messages": [{
+            "role": "user",
+            "content": [
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}},
+                {"type": "text", "text": "What is in this picture?"}
+            ]
+        }]
+    });
+    let mut universal_request = manager.to_universal_request("openai", &openai_request).unwrap();
+    let MessageContent::Parts(parts) = &mut universal_request.messages[0].content else {
+        panic!("expected content parts");
+    };
+    parts[1].cache_control = Some(json!({"type": "ephemeral"}));
+    let openrouter_request = manager.from_universal_request("openrouter", &universal_request).unwrap();
+    assert_eq!(openrouter_request["messages"][0]["content"], json!([
+        {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}},
+        {"type": "text", "text": "What is in this picture?", "cache_control": {"type": "ephemeral"}}
+    ]));
+}
+
+#[test]
+fn test_openrouter_response_cost_and_errors() {
+    let transformer = OpenRouterTransformer::new();
+    
+    let mut response = create_test_tool_call_response();
+    response["provider"] = json!("Anthropic");
+    response["usage"]["cost"] = json!(0.00042);
+    
+    let universal_response = transformer.to_universal_response(&response).unwrap();
+    let metadata = universal_response.provider_metadata.unwrap();
+    assert_eq!(metadata["cost"], json!(0.00042));
+    assert_eq!(metadata["provider"], "Anthropic");
+    
+    let error_body = json!({
+        "error": {
+            "code": 402,
+            "message": "Insufficient credits",
+            "metadata": {"provider_name": null}
+        }
+    });
+    let error = OpenRouterTransformer::parse_error(&error_body).unwrap();
+    assert_eq!(error.code, json!(402));
+    assert_eq!(error.message, "Insufficient credits");
+    assert!(transformer.to_universal_response(&error_body).is_err());
+    
+    // Trailing usage chunk has no choices
+    let usage_chunk = json!({
+        "id": "gen-1",
+        "object": "chat.completion.chunk",
+        "created": 1677652288,
+        "model": "anthropic/claude-sonnet-4",
+        "choices": [],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15, "cost": 0.0001}
+    });
+    let universal_chunk = transformer.to_universal_stream_chunk(&usage_chunk).unwrap();
+    assert!(universal_chunk.choices.is_empty());
+    assert_eq!(universal_chunk.provider_metadata.unwrap()["cost"], json!(0.0001));
+}
+
+#[test]
+fn test_ollama_request_from_anthropic() {
+    let manager = TransformerManager::new();
+    let transformer = OllamaTransformer::from_options_value(&json!({
+        "num_ctx": 32768,
+        "keep_alive": "10m"
+    })).unwrap();
+    
+    let anthropic_request = json!({
+        "model": "qwen2.5-coder:14b",
+        "max_tokens": 512,
+        "stream": true,
+        "messages": [
+            {
+                "role": "assistant",
+                "content": [
+                    {"type": "text", "text": "Let me check."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"location": "Boston"}}
+                ]
+            },
+            {
+                "role": "user",
+                "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "Sunny, 22C"},
+                    {"type": "text", "text": "Thanks, and tomorrow?"}
+                ]
+            }
+        ]
+    });
+    
+    let universal_request = manager.to_universal_request("anthropic", &anthropic_request).unwrap();
+    let ollama_request = transformer.from_universal_request(&universal_request).unwrap();
+    
+    assert_eq!(ollama_request["options"]["num_ctx"], 32768);
+    assert_eq!(ollama_request["options"]["num_predict"], 512);
+    assert_eq!(ollama_request["keep_alive"], "10m");
+    assert_eq!(ollama_request["stream"], true);
+    
+    let messages = ollama_request["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[0]["tool_calls"][0]["function"]["name"], "get_weather");
+    assert_eq!(messages[0]["tool_calls"][0]["function"]["arguments"]["location"], "Boston");
+    assert_eq!(messages[1]["role"], "tool");
+    assert_eq!(messages[1]["content"], "Sunny, 22C");
+    assert_eq!(messages[2]["role"], "user");
+    assert_eq!(messages[2]["content"], "Thanks, and tomorrow?");
+}
+
+#[test]
+fn test_ollama_tool_call_response_to_anthropic() {
+    let manager = TransformerManager::new();
+    
+    let ollama_response = json!({
+        "model": "qwen2.5-coder:14b",
+        "created_at": "2024-07-22T20:33:28.123648Z",
+        "message": {
+            "role": "assistant",
+            "content": "",
+            "tool_calls": [{
+                "function": {"name": "get_weather", "arguments": {"location": "Boston"}}
+            }]
+        },
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 26,
+        "eval_count": 12
+    });
+    
+    let universal_response = manager.to_universal_response("ollama", &ollama_response).unwrap();
+    assert_eq!(universal_response.choices[0].finish_reason, "tool_calls");
+    assert_eq!(universal_response.usage.total_tokens, 38);
+    
+    let anthropic_response = manager.from_universal_response("anthropic", &universal_response).unwrap();
+    assert_eq!(anthropic_response["stop_reason"], "tool_use");
+    let content = anthropic_response["content"].as_array().unwrap();
+    assert_eq!(content.len(), 1);
+    assert_eq!(content[0]["type"], "tool_use");
+    assert_eq!(content[0]["name"], "get_weather");
+    assert_eq!(content[0]["input"]["location"], "Boston");
+    assert!(content[0]["id"].as_str().unwrap().starts_with("call_"));
+    
+    // Ids derive from the response itself, so converting it again gives the same id
+    let again = manager.to_universal_response("ollama", &ollama_response).unwrap();
+    assert_eq!(again.choices[0].tool_calls.as_ref().unwrap()[0].id.as_deref(), content[0]["id"].as_str());
+    let mut later = ollama_response.clone();
+    later["created_at"] = json!("2024-07-22T20:33:29.000000Z");
+    let later = manager.to_universal_response("ollama", &later).unwrap();
+    assert_ne!(later.choices[0].tool_calls.as_ref().unwrap()[0].id.as_deref(), content[0]["id"].as_str());
+}
+
+#[test]
+fn test_ollama_tool_results_pair_with_calls_by_name_and_position() {
+    let manager = TransformerManager::new();
+    let ollama_request = json!({
+        "model": "qwen2.5-coder:14b",
+        "messages": [
+            {"role": "user", "content": "Weather in Boston and Paris, and the time?"},
+            {"role": "assistant", "content": "", "tool_calls": [
+                {"function": {"name": "get_weather", "arguments": {"location": "Boston"}}},
+                {"function": {"name": "get_time", "arguments": {}}},
+                {"function": {"name": "get_weather", "arguments": {"location": "Paris"}}}
+            ]},
+            {"role": "tool", "tool_name": "get_time", "content": "12:00"},
+            {"role": "tool", "tool_name": "get_weather", "content": "Sunny"},
+            {"role": "tool", "tool_name": "get_weather", "content": "Rainy"}
+        ]
+    });
+    
+    let anthropic_request = manager.transform_request("ollama", "anthropic", &ollama_request).unwrap();
+    let messages = anthropic_request["messages"].as_array().unwrap();
+    let calls: Vec<&str> = messages[1]["content"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| block["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(calls.len(), 3);
+    assert!(calls[0] != calls[2]);
+    
+    // Each result goes to the first unanswered call of its name
+    let results: Vec<(&str, &str)> = messages[2..]
+        .iter()
+        .map(|message| {
+            assert_eq!(message["role"], "user");
+            let block = &message["content"][0];
+            assert_eq!(block["type"], "tool_result");
+            (block["tool_use_id"].as_str().unwrap(), block["content"].as_str().unwrap())
+        })
+        .collect();
+    assert_eq!(results, [(calls[1], "12:00"), (calls[0], "Sunny"), (calls[2], "Rainy")]);
+    
+    // The same conversation always gets the same ids
+    let again = manager.transform_request("ollama", "anthropic", &ollama_request).unwrap();
+    assert_eq!(again["messages"], anthropic_request["messages"]);
+}
+
+#[test]
+fn test_ollama_ndjson_stream_decoding() {
+    let transformer = OllamaTransformer::new();
+    assert_eq!(transformer.stream_format(), StreamFormat::Ndjson);
+    
+    let body = concat!(
+        "{\"model\":\"llama3.2\",\"created_at\":\"2024-07-22T20:33:28Z\",\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+        "{\"model\":\"llama3.2\",\"created_at\":\"2024-07-22T20:33:28Z\",\"message\":{\"role\":\"assistant\",\"content\":\"lo\"},\"done\":false}\n",
+        "{\"model\":\"llama3.2\",\"created_at\":\"2024-07-22T20:33:29Z\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":5,\"eval_count\":2}"
+    );
+    
+    // Split the body at arbitrary byte boundaries to simulate network chunks
+    let mut decoder = NdjsonDecoder::new();
+    let mut values = Vec::new();
+    for piece in body.as_bytes().chunks(17) {
+        values.extend(decoder.feed(piece));
+    }
+    values.extend(decoder.finish());
+    
+    let chunks: Vec<_> = values
+        .into_iter()
+        .map(|value| transformer.to_universal_stream_chunk(&value.unwrap()).unwrap())
+        .collect();
+    assert_eq!(chunks.len(), 3);
+    
+    let text: String = chunks
+        .iter()
+        .filter_map(|chunk| chunk.choices[0].delta.content.clone())
+        .collect();
+    assert_eq!(text, "Hello");
+    assert!(chunks[0].choices[0].finish_reason.is_none());
+    assert_eq!(chunks[2].choices[0].finish_reason, Some("stop".to_string()));
+    let usage = chunks[2].usage.as_ref().unwrap();
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (5, 2, 7));
+    assert!(chunks[2].provider_metadata.is_none());
+    assert!(chunks[0].usage.is_none());
+}
+
+#[test]
+fn test_azure_deployment_url_and_auth_header() {
+    let transformer = AzureOpenAITransformer::from_options_value(&json!({
+        "api_version": "2024-06-01",
+        "deployments": {"gpt-4o": "prod-gpt4o"}
+    })).unwrap();
+    let provider = Provider {
+        name: "azure".to_string(),
+        api_base_url: "https://example.openai.azure.com/".to_string(),
+        api_key: "azure-key".to_string(),
+        models: vec!["gpt-4o".to_string(), "gpt-4o-mini".to_string()],
+        transformer: None,
+        capabilities: HashMap::new(),
+    };
+    
+    let manager = TransformerManager::new();
+    let mut request = manager.to_universal_request("anthropic", &json!({
+        "model": "gpt-4o",
+        "max_tokens": 100,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]
+    })).unwrap();
+    
+    let upstream = transformer.build_upstream_request(&provider, &request).unwrap();
+    assert_eq!(
+        upstream.url,
+        "https://example.openai.azure.com/openai/deployments/prod-gpt4o/chat/completions?api-version=2024-06-01"
+    );
+    assert_eq!(upstream.header("api-key"), Some("azure-key"));
+    assert!(upstream.header("authorization").is_none());
+    assert!(upstream.body.get("model").is_none());
+    assert_eq!(upstream.body["messages"][0]["content"], "Hello");
+    
+    // Unmapped models are used as the deployment name directly
+    request.model = "gpt-4o-mini".to_string();
+    let upstream = transformer.build_upstream_request(&provider, &request).unwrap();
+    assert!(upstream.url.contains("/deployments/gpt-4o-mini/"));
+}
+
+#[test]
+fn test_anthropic_and_gemini_upstream_url_and_auth_headers() {
+    let create_provider = |name: &str, api_base_url: &str| Provider {
+        name: name.to_string(),
+        api_base_url: api_base_url.to_string(),
+        api_key: format!("{}-key", name),
+        models: vec![],
+        transformer: None,
+        capabilities: HashMap::new(),
+    };
+    let manager = TransformerManager::new();
+    let mut request = manager.to_universal_request("anthropic", &json!({
+        "model": "claude-sonnet-4",
+        "max_tokens": 100,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]
+    })).unwrap();
+
+    let provider = create_provider("anthropic", "https://api.anthropic.com/v1/messages");
+    let upstream = AnthropicTransformer::new().build_upstream_request(&provider, &request).unwrap();
+    assert_eq!(upstream.url, "https://api.anthropic.com/v1/messages");
+    assert_eq!(upstream.header("x-api-key"), Some("anthropic-key"));
+    assert_eq!(upstream.header("anthropic-version"), Some("2023-06-01"));
+    assert!(upstream.header("authorization").is_none());
+
+    // The model and stream mode go in the URL, with or without `/models` in the base
+    request.model = "gemini-2.5-pro".to_string();
+    let gemini = GeminiTransformer::new();
+    let provider = create_provider("gemini", "https://generativelanguage.googleapis.com/v1beta/models/");
+    let upstream = gemini.build_upstream_request(&provider, &request).unwrap();
+    assert_eq!(
+        upstream.url,
+        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:generateContent"
+    );
+    assert_eq!(upstream.header("x-goog-api-key"), Some("gemini-key"));
+    assert!(upstream.header("authorization").is_none());
+    assert!(upstream.body.get("contents").is_some());
+
+    request.stream = true;
+    let provider = create_provider("gemini", "https://generativelanguage.googleapis.com/v1beta");
+    let upstream = gemini.build_upstream_request(&provider, &request).unwrap();
+    assert_eq!(
+        upstream.url,
+        "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+    );
+}
+
+#[test]
+fn test_azure_content_filter_handling() {
+    let transformer = AzureOpenAITransformer::new();
+    
+    let blocked_prompt = json!({
+        "error": {
+            "message": "The response was filtered due to the prompt triggering Azure OpenAI's content management policy.",
+            "type": null,
+            "param": "prompt",
+            "code": "content_filter",
+            "status": 400,
+            "innererror": {
+                "code": "ResponsibleAIPolicyViolation",
+                "content_filter_result": {
+                    "hate": {"filtered": false, "severity": "safe"},
+                    "violence": {"filtered": true, "severity": "high"}
+                }
+            }
+        }
+    });
+    let error = AzureOpenAITransformer::parse_error(&blocked_prompt).unwrap();
+    assert!(error.is_content_filter());
+    assert_eq!(error.filtered_categories(), vec!["violence".to_string()]);
+    let message = transformer.to_universal_response(&blocked_prompt).unwrap_err().to_string();
+    assert!(message.contains("content filter"));
+    assert!(message.contains("violence"));
+    
+    // A completion cut off by the output filter maps to an Anthropic refusal
+    let filtered_completion = json!({
+        "id": "chatcmpl-azure",
+        "object": "chat.completion",
+        "created": 1700000000,
+        "model": "gpt-4o-2024-05-13",
+        "prompt_filter_results": [{"prompt_index": 0, "content_filter_results": {}}],
+        "choices": [{
+            "index": 0,
+            "finish_reason": "content_filter",
+            "message": {"role": "assistant", "content": null},
+            "content_filter_results": {"violence": {"filtered": true, "severity": "medium"}}
+        }],
+        "usage": {"prompt_tokens": 12, "completion_tokens": 0, "total_tokens": 12}
+    });
+    let universal = transformer.to_universal_response(&filtered_completion).unwrap();
+    assert_eq!(universal.choices[0].finish_reason, "content_filter");
+    let metadata = universal.provider_metadata.as_ref().unwrap();
+    assert_eq!(metadata["content_filter_results"]["violence"]["filtered"], true);
+    
+    let manager = TransformerManager::new();
+    let anthropic = manager.from_universal_response("anthropic", &universal).unwrap();
+    assert_eq!(anthropic["stop_reason"], "refusal");
+    
+    // The leading stream chunk only carries prompt annotations
+    let first_chunk = json!({
+        "id": "",
+        "object": "",
+        "created": 0,
+        "model": "",
+        "choices": [],
+        "prompt_filter_results": [{"prompt_index": 0, "content_filter_results": {}}]
+    });
+    let chunk = transformer.to_universal_stream_chunk(&first_chunk).unwrap();
+    assert!(chunk.choices.is_empty());
+    assert!(chunk.provider_metadata.unwrap().contains_key("prompt_filter_results"));
+}
+
+#[test]
+fn test_tool_name_mangling_round_trip() {
+    let manager = TransformerManager::new();
+    let long_name = "mcp__github_enterprise_server__list_pull_request_review_comments_for_repository";
+    let dotted_name = "mcp__fs__read.file";
+    assert!(long_name.len() > 64);
+    
+    let anthropic_request = json!({
+        "model": "gpt-4o",
+        "max_tokens": 256,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "List comments"}]}],
+        "tools": [
+            {"name": long_name, "description": "List review comments", "input_schema": {"type": "object"}},
+            {"name": dotted_name, "description": "Read a file", "input_schema": {"type": "object"}},
+            {"name": "get_weather", "description": "Weather", "input_schema": {"type": "object"}}
+        ]
+    });
+    
+    let (openai_request, tool_names) = manager
+        .transform_request_with_tool_names("anthropic", "openai", &anthropic_request)
+        .unwrap();
+    let upstream_long = openai_request["tools"][0]["function"]["name"].as_str().unwrap().to_string();
+    let upstream_dotted = openai_request["tools"][1]["function"]["name"].as_str().unwrap();
+    assert!(upstream_long.len() <= 64);
+    assert!(upstream_long.starts_with("mcp__github_enterprise_server__"));
+    assert!(upstream_long.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+    assert_eq!(upstream_dotted, "mcp__fs__read_file");
+    assert_eq!(openai_request["tools"][2]["function"]["name"], "get_weather");
+    assert_eq!(tool_names.original_name(&upstream_long), long_name);
+    
+    // The upstream answers with the shortened name; the client sees the original
+    let openai_response = json!({
+        "id": "chatcmpl-123",
+        "object": "chat.completion",
+        "created": 1677652288,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": upstream_long, "arguments": "{\"repo\":\"a/b\"}"}
+                }]
+            },
+            "finish_reason": "tool_calls"
+        }],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+    });
+    let universal = manager.to_universal_response("openai", &openai_response).unwrap();
+    let mut restored = universal.clone();
+    tool_names.restore_response(&mut restored);
+    assert_eq!(restored.choices[0].tool_calls.as_ref().unwrap()[0].function.name, long_name);
+    
+    let openai_chunk = json!({
+        "id": "chatcmpl-123",
+        "object": "chat.completion.chunk",
+        "created": 1677652288,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": 0,
+            "delta": {
+                "tool_calls": [{
+                    "index": 0,
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": upstream_long, "arguments": ""}
+                }]
+            },
+            "finish_reason": null
+        }]
+    });
+    let chunk = manager
+        .transform_stream_chunk_with_tool_names("openai", "openai", &openai_chunk, &tool_names)
+        .unwrap();
+    assert_eq!(chunk["choices"][0]["delta"]["tool_calls"][0]["function"]["name"], long_name);
+}
+
+#[test]
+fn test_tool_name_mangling_rules() {
+    use code_routic::transformers::providers::provider_trait::{FunctionChoice, ToolChoice, ToolChoiceSpecific};
+    use code_routic::transformers::tool_names::{ToolNameMap, ToolNameRules};
+    
+    let manager = TransformerManager::new();
+    let prefix = format!("mcp__server__{}", "x".repeat(60));
+    let anthropic_request = json!({
+        "model": "gemini-1.5-pro",
+        "max_tokens": 256,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}],
+        "tools": [
+            {"name": format!("{}_alpha", prefix), "description": "a", "input_schema": {"type": "object"}},
+            {"name": format!("{}_beta", prefix), "description": "b", "input_schema": {"type": "object"}},
+            {"name": "1password.lookup", "description": "c", "input_schema": {"type": "object"}}
+        ]
+    });
+    
+    // Names sharing a long prefix still map to distinct upstream names
+    let mut universal = manager.to_universal_request("anthropic", &anthropic_request).unwrap();
+    universal.tool_choice = Some(ToolChoice::Specific(ToolChoiceSpecific {
+        choice_type: "function".to_string(),
+        function: FunctionChoice { name: format!("{}_alpha", prefix) },
+    }));
+    let map = ToolNameMap::for_request(&universal, &ToolNameRules::default());
+    let alpha = map.upstream_name(&format!("{}_alpha", prefix)).to_string();
+    let beta = map.upstream_name(&format!("{}_beta", prefix)).to_string();
+    assert_ne!(alpha, beta);
+    assert!(alpha.len() <= 64 && beta.len() <= 64);
+    assert_eq!(map.upstream_name("1password.lookup"), "1password_lookup");
+    map.apply_to_request(&mut universal);
+    match &universal.tool_choice {
+        Some(ToolChoice::Specific(choice)) => assert_eq!(choice.function.name, alpha),
+        other => panic!("unexpected tool choice: {:?}", other),
+    }
+    
+    // Gemini allows dots but requires a leading letter or underscore
+    let (gemini_request, tool_names) = manager
+        .transform_request_with_tool_names("anthropic", "gemini", &anthropic_request)
+        .unwrap();
+    let declarations = &gemini_request["tools"][0]["function_declarations"];
+    assert_eq!(declarations[2]["name"], "_1password.lookup");
+    assert_eq!(tool_names.original_name("_1password.lookup"), "1password.lookup");
+    
+    // Requests whose names are already valid are passed through untouched
+    let plain = json!({
+        "model": "gpt-4o",
+        "max_tokens": 16,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}],
+        "tools": [{"name": "get_weather", "description": "Weather", "input_schema": {"type": "object"}}]
+    });
+    let (_, tool_names) = manager.transform_request_with_tool_names("anthropic", "openai", &plain).unwrap();
+    assert!(tool_names.is_empty());
+
+    // A mangled name never takes the name of a valid tool in the same request
+    let clashing = json!({
+        "model": "gpt-4o",
+        "max_tokens": 16,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}],
+        "tools": [
+            {"name": "fs.read", "description": "a", "input_schema": {"type": "object"}},
+            {"name": "fs_read", "description": "b", "input_schema": {"type": "object"}}
+        ]
+    });
+    let (openai_request, tool_names) = manager.transform_request_with_tool_names("anthropic", "openai", &clashing).unwrap();
+    let mangled = tool_names.upstream_name("fs.read").to_string();
+    assert_ne!(mangled, "fs_read");
+    assert_eq!(openai_request["tools"][0]["function"]["name"], mangled);
+    assert_eq!(openai_request["tools"][1]["function"]["name"], "fs_read");
+    assert_eq!(tool_names.original_name("fs_read"), "fs_read");
+    assert_eq!(tool_names.original_name(&mangled), "fs.read");
+}
+
+#[test]
+fn test_capabilities_clamp_and_drop_features() {
+    use code_routic::config::capabilities::CapabilityRegistry;
+    
+    let manager = TransformerManager::new();
+    let anthropic_request = json!({
+        "model": "claude-3-5-sonnet-20241022",
+        "max_tokens": 32000,
+        "thinking": {"type": "enabled", "budget_tokens": 4096},
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Weather?"}]}],
+        "tools": [{"name": "get_weather", "description": "Weather", "input_schema": {"type": "object"}}]
+    });
+    
+    // claude-3-5-sonnet caps output at 8192 and has no extended thinking
+    let capabilities = CapabilityRegistry::default().lookup("anthropic", "claude-3-5-sonnet-20241022");
+    let (request, _) = manager
+        .transform_request_for_model("anthropic", "anthropic", &anthropic_request, &capabilities)
+        .unwrap();
+    assert_eq!(request["max_tokens"], 8192);
+    assert!(request.get("thinking").is_none());
+    assert_eq!(request["tools"][0]["name"], "get_weather");
+    
+    // deepseek-reasoner takes no tools
+    let capabilities = CapabilityRegistry::default().lookup("deepseek", "deepseek-reasoner");
+    let (request, _) = manager
+        .transform_request_for_model("anthropic", "openai", &anthropic_request, &capabilities)
+        .unwrap();
+    assert_eq!(request["max_tokens"], 32000);
+    assert!(request["tools"].is_null());
+}
+
+#[test]
+fn test_registry_aliases_and_custom_registration() {
+    use code_routic::transformers::transformer_manager::TransformerSource;
+    
+    let mut manager = TransformerManager::new();
+    
+    // Aliases resolve to the registered built-in
+    assert!(manager.is_provider_supported("openai-compatible"));
+    assert_eq!(manager.resolve_name("openai-compatible"), Some("openai"));
+    assert_eq!(manager.transformer("claude").unwrap().provider_name(), "anthropic");
+    let request = manager
+        .transform_request("openai-compatible", "google", &json!({
+            "model": "gpt-4",
+            "messages": create_test_messages()
+        }))
+        .unwrap();
+    assert!(request.get("contents").is_some());
+    
+    // A plugin can be registered under its own name without touching the manager
+    manager.register(
+        "my-gateway",
+        Box::new(OpenAITransformer::new()),
+        TransformerSource::Plugin { path: "/plugins/my-gateway.wasm".to_string() },
+    );
+    manager.register_alias("gateway", "my-gateway");
+    assert!(manager.is_provider_supported("gateway"));
+    assert!(manager.list_available_providers().contains(&"my-gateway".to_string()));
+    
+    let listed = manager.list_transformers();
+    let entry = listed.iter().find(|info| info.name == "my-gateway").unwrap();
+    assert_eq!(entry.aliases, vec!["gateway".to_string()]);
+    assert_eq!(entry.source, TransformerSource::Plugin { path: "/plugins/my-gateway.wasm".to_string() });
+    let openai = listed.iter().find(|info| info.name == "openai").unwrap();
+    assert_eq!(openai.source, TransformerSource::Builtin);
+    assert!(openai.aliases.contains(&"openai-compatible".to_string()));
+    
+    // An empty registry supports nothing, and dangling aliases do not resolve
+    let mut empty = TransformerManager::empty();
+    empty.register_alias("openai-compatible", "openai");
+    assert!(!empty.is_provider_supported("openai-compatible"));
+    assert!(empty.transform_request("openai", "anthropic", &json!({})).is_err());
+}
+
+/// Records its label in `provider_metadata["trace"]` so tests can observe hook order.
+struct TraceHook {
+    label: String,
+}
+
+impl TraceHook {
+    fn push(metadata: &mut Option<HashMap<String, Value>>, label: &str) {
+        let trace = metadata.get_or_insert_with(HashMap::new).entry("trace".to_string()).or_insert_with(|| json!([]));
+        trace.as_array_mut().unwrap().push(json!(label));
+    }
+}
+
+impl code_routic::transformers::chain::TransformerHook for TraceHook {
+    fn name(&self) -> &str {
+        "trace"
+    }
+
+    fn transform_request(&self, request: &mut code_routic::transformers::providers::provider_trait::ChatRequest) -> TransformerResult<()> {
+        Self::push(&mut request.provider_metadata, &self.label);
+        Ok(())
+    }
+
+    fn transform_response(&self, response: &mut code_routic::transformers::providers::provider_trait::ChatResponse) -> TransformerResult<()> {
+        Self::push(&mut response.provider_metadata, &self.label);
+        Ok(())
+    }
+
+    fn transform_stream_chunk(&self, chunk: &mut code_routic::transformers::providers::provider_trait::ChatStreamChunk) -> TransformerResult<()> {
+        Self::push(&mut chunk.provider_metadata, &self.label);
+        Ok(())
+    }
+}
+
+fn create_chain_manager() -> TransformerManager {
+    use code_routic::transformers::transformer_manager::TransformerSource;
+    
+    let mut manager = TransformerManager::new();
+    for name in ["trace", "trace-b"] {
+        manager.register_hook(
+            name,
+            Box::new(|options| {
+                let label = options
+                    .and_then(|options| options["label"].as_str())
+                    .unwrap_or("unlabelled")
+                    .to_string();
+                Ok(Box::new(TraceHook { label }))
+            }),
+            TransformerSource::Builtin,
+        );
+    }
+    manager
+}
+
+fn create_chain_provider(name: &str, transformer: Value) -> Provider {
+    Provider {
+        name: name.to_string(),
+        api_base_url: "https://example.com/v1/chat/completions".to_string(),
+        api_key: "key".to_string(),
+        models: vec!["model-a".to_string(), "model-b".to_string()],
+        transformer: Some(serde_json::from_value(transformer).unwrap()),
+        capabilities: HashMap::new(),
+    }
+}
+
+#[test]
+fn test_chain_runs_request_hooks_in_order_and_response_hooks_in_reverse() {
+    use code_routic::config::capabilities::ModelCapabilities;
+    
+    let manager = create_chain_manager();
+    let provider = create_chain_provider("deepseek", json!({
+        "use": ["openai", ["trace", {"label": "first"}], ["trace-b", {"label": "second"}]]
+    }));
+    let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+    assert_eq!(chain.provider().provider_name(), "openai");
+    assert_eq!(chain.hook_names(), vec!["trace", "trace"]);
+    
+    let anthropic_request = json!({
+        "model": "model-a",
+        "max_tokens": 100,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}]
+    });
+    let mut universal = manager.to_universal_request("anthropic", &anthropic_request).unwrap();
+    chain.apply_request(&mut universal).unwrap();
+    assert_eq!(universal.provider_metadata.unwrap()["trace"], json!(["first", "second"]));
+    
+    let (request, _) = manager
+        .transform_request_with_chain("anthropic", &chain, &anthropic_request, &ModelCapabilities::unrestricted())
+        .unwrap();
+    assert_eq!(request["messages"][0]["role"], "user");
+    
+    let openai_response = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "model-a",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello"}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+    });
+    let mut response = manager.to_universal_response("openai", &openai_response).unwrap();
+    chain.apply_response(&mut response).unwrap();
+    assert_eq!(response.provider_metadata.unwrap()["trace"], json!(["second", "first"]));
+    
+    let openai_chunk = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "model-a",
+        "choices": [{"index": 0, "delta": {"content": "He"}, "finish_reason": null}]
+    });
+    let mut chunk = manager.to_universal_stream_chunk("openai", &openai_chunk).unwrap();
+    chain.apply_stream_chunk(&mut chunk).unwrap();
+    assert_eq!(chunk.provider_metadata.unwrap()["trace"], json!(["second", "first"]));
+    
+    let client_response = manager
+        .transform_response_with_chain(&chain, "anthropic", &openai_response, &Default::default())
+        .unwrap();
+    assert_eq!(client_response["content"][0]["text"], "Hello");
+}
+
+#[test]
+fn test_chain_model_overrides_and_provider_options() {
+    let manager = create_chain_manager();
+    let provider = create_chain_provider("azure-east", json!({
+        "use": [["azure", {"api_version": "2024-06-01"}], ["trace", {"label": "provider"}]],
+        "model-b": {"use": [["trace", {"label": "model"}], ["trace-b", {"label": "extra"}]]}
+    }));
+    
+    // Models without their own list use the provider chain as-is
+    let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+    let mut request = manager
+        .to_universal_request("openai", &json!({"model": "model-a", "messages": create_test_messages()}))
+        .unwrap();
+    chain.apply_request(&mut request).unwrap();
+    assert_eq!(request.provider_metadata.as_ref().unwrap()["trace"], json!(["provider"]));
+    
+    // The configured Azure instance carries the options from the `use` entry
+    assert_eq!(chain.provider().provider_name(), "azure");
+    let upstream = chain.provider().build_upstream_request(&provider, &request).unwrap();
+    assert!(upstream.url.ends_with("api-version=2024-06-01"), "{}", upstream.url);
+    
+    // model-b replaces the provider's `trace` options and appends `trace-b`
+    let chain = manager.resolve_chain(&provider, "model-b").unwrap();
+    let mut request = manager
+        .to_universal_request("openai", &json!({"model": "model-b", "messages": create_test_messages()}))
+        .unwrap();
+    chain.apply_request(&mut request).unwrap();
+    assert_eq!(request.provider_metadata.unwrap()["trace"], json!(["model", "extra"]));
+}
+
+#[test]
+fn test_chain_resolution_fallbacks_and_errors() {
+    let manager = create_chain_manager();
+    
+    // Without a provider transformer in the chain, the provider's own name decides the format
+    let mut provider = create_chain_provider("gemini", json!({"use": ["trace"]}));
+    assert_eq!(manager.resolve_chain(&provider, "model-a").unwrap().provider().provider_name(), "gemini");
+    provider.transformer = None;
+    provider.name = "some-openai-compatible-host".to_string();
+    assert_eq!(manager.resolve_chain(&provider, "model-a").unwrap().provider().provider_name(), "openai");
+    
+    let provider = create_chain_provider("p", json!({"use": ["no-such-transformer"]}));
+    assert!(manager.resolve_chain(&provider, "model-a").is_err());
+    let provider = create_chain_provider("p", json!({"use": ["openai", "anthropic"]}));
+    assert!(manager.resolve_chain(&provider, "model-a").is_err());
+    let provider = create_chain_provider("p", json!({"use": [["openai", {"unexpected": true}]]}));
+    assert!(manager.resolve_chain(&provider, "model-a").is_err());
+    let provider = create_chain_provider("p", json!({"use": [42]}));
+    assert!(manager.resolve_chain(&provider, "model-a").is_err());
+    
+    // apply_transformer runs the chain end to end in the provider's own format
+    let provider = create_chain_provider("p", json!({"use": ["openai-compatible", "trace"]}));
+    let output = manager
+        .apply_transformer(&provider, "model-a", &json!({"model": "model-a", "messages": create_test_messages()}).to_string())
+        .unwrap();
+    let output: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(output["model"], "model-a");
+}
+
+fn transform_through_chain(use_entries: Value, target: &str, request: &Value) -> Value {
+    use code_routic::config::capabilities::ModelCapabilities;
+    
+    let manager = TransformerManager::new();
+    let provider = create_chain_provider(target, json!({"use": use_entries}));
+    let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+    manager
+        .transform_request_with_chain("anthropic", &chain, request, &ModelCapabilities::unrestricted())
+        .unwrap()
+        .0
+}
+
+fn create_anthropic_chain_request() -> Value {
+    json!({
+        "model": "model-a",
+        "max_tokens": 32000,
+        "temperature": 1.0,
+        "messages": [
+            {"role": "user", "content": [{"type": "text", "text": "Weather?", "cache_control": {"type": "ephemeral"}}]},
+            {"role": "assistant", "content": [{"type": "text", "text": "<think>Check the tool.</think>\n\nLet me look."}]},
+            {"role": "user", "content": [{"type": "text", "text": "Go on"}]}
+        ],
+        "tools": [
+            {"name": "get_weather", "description": "Weather", "input_schema": {"type": "object"}},
+            {"name": "get_time", "description": "Time", "input_schema": {"type": "object"}}
+        ]
+    })
+}
+
+#[test]
+fn test_maxtoken_and_sampling_hooks() {
+    let request = create_anthropic_chain_request();
+    
+    let output = transform_through_chain(json!([["maxtoken", {"max_tokens": 8192}]]), "openai", &request);
+    assert_eq!(output["max_tokens"], 8192);
+    
+    let output = transform_through_chain(json!([["sampling", {"temperature": 0.6, "top_p": 0.95}]]), "openai", &request);
+    assert_eq!(output["temperature"], 0.6);
+    assert_eq!(output["top_p"], 0.95);
+    
+    // Only the configured values are overridden
+    let output = transform_through_chain(json!([["sampling", {"top_p": 0.9}]]), "anthropic", &request);
+    assert_eq!(output["temperature"], 1.0);
+    assert_eq!(output["top_p"], 0.9);
+    
+    // Missing required options are configuration errors
+    let manager = TransformerManager::new();
+    for entry in [json!("maxtoken"), json!(["sampling", {}]), json!(["maxtoken", {"max_tokens": "many"}])] {
+        let provider = create_chain_provider("openai", json!({"use": [entry]}));
+        assert!(manager.resolve_chain(&provider, "model-a").is_err());
+    }
+}
+
+#[test]
+fn test_cleancache_hook() {
+    let request = create_anthropic_chain_request();
+    
+    let output = transform_through_chain(json!([]), "anthropic", &request);
+    assert_eq!(output["messages"][0]["content"][0]["cache_control"]["type"], "ephemeral");
+    
+    let output = transform_through_chain(json!(["cleancache"]), "anthropic", &request);
+    assert!(output["messages"][0]["content"][0].get("cache_control").is_none());
+}
+
+#[test]
+fn test_tooluse_hook() {
+    let request = create_anthropic_chain_request();
+    
+    let output = transform_through_chain(json!(["tooluse"]), "openai", &request);
+    assert_eq!(output["tool_choice"], "required");
+    
+    let output = transform_through_chain(json!([["tooluse", {"tool_name": "get_time"}]]), "openai", &request);
+    assert_eq!(output["tool_choice"]["function"]["name"], "get_time");
+    
+    // Requests without tools are left alone
+    let mut without_tools = request.clone();
+    without_tools.as_object_mut().unwrap().remove("tools");
+    let output = transform_through_chain(json!(["tooluse"]), "openai", &without_tools);
+    assert!(output["tool_choice"].is_null());
+}
+
+#[test]
+fn test_reasoning_hook() {
+    use code_routic::transformers::chain::TransformerHook;
+    use code_routic::transformers::hooks::ReasoningHook;
+    
+    let mut request = create_anthropic_chain_request();
+    request["thinking"] = json!({"type": "enabled", "budget_tokens": 20000});
+    
+    // A thinking budget becomes an effort level for OpenAI-style targets
+    let output = transform_through_chain(json!([]), "openai", &request);
+    assert!(output.get("reasoning_effort").is_none());
+    let output = transform_through_chain(json!(["reasoning"]), "openai", &request);
+    assert_eq!(output["reasoning_effort"], "high");
+    
+    // Forced options apply even when the client did not ask for thinking
+    let mut plain = request.clone();
+    plain.as_object_mut().unwrap().remove("thinking");
+    let output = transform_through_chain(json!([["reasoning", {"effort": "low"}]]), "anthropic", &plain);
+    assert_eq!(output["thinking"]["budget_tokens"], 2048);
+    
+    // Budgets stay below max_tokens, which is set when the request has none
+    let mut small = plain.clone();
+    small["max_tokens"] = json!(4000);
+    let output = transform_through_chain(json!([["reasoning", {"effort": "medium"}]]), "anthropic", &small);
+    assert_eq!((output["max_tokens"].as_u64(), output["thinking"]["budget_tokens"].as_u64()), (Some(4000), Some(2000)));
+    let output = transform_through_chain(json!([["reasoning", {"effort": "high"}]]), "anthropic", &plain);
+    assert_eq!(output["thinking"]["budget_tokens"], 24576);
+    let mut universal = TransformerManager::new().to_universal_request("anthropic", &plain).unwrap();
+    universal.max_tokens = None;
+    let hook = ReasoningHook::from_options(Some(&json!({"effort": "high"}))).unwrap();
+    hook.transform_request(&mut universal).unwrap();
+    assert_eq!(universal.max_tokens, Some(24576 + 4096));
+    
+    // enabled: false strips reasoning entirely
+    let output = transform_through_chain(json!([["reasoning", {"enabled": false}]]), "anthropic", &request);
+    assert!(output.get("thinking").is_none());
+    
+    assert!(ReasoningHook::from_options(Some(&json!({"effort": "extreme"}))).is_err());
+    let hook = ReasoningHook::from_options(None).unwrap();
+    assert_eq!(hook.name(), "reasoning");
+    assert_eq!(ReasoningHook::effort_for_budget(1024), "low");
+    assert_eq!(ReasoningHook::effort_for_budget(8192), "medium");
+}
+
+#[test]
+fn test_stripthinking_hook() {
+    use code_routic::transformers::chain::TransformerHook;
+    use code_routic::transformers::hooks::StripThinkingHook;
+    
+    // Thinking is removed from the history sent upstream
+    let request = create_anthropic_chain_request();
+    let output = transform_through_chain(json!(["stripthinking"]), "anthropic", &request);
+    assert_eq!(output["messages"][1]["content"][0]["text"], "Let me look.");
+    
+    // ...and from complete responses
+    let manager = TransformerManager::new();
+    let mut response = manager.to_universal_response("openai", &json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "deepseek-reasoner",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "<think>Hmm.</think>\nSunny."}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+    })).unwrap();
+    let hook = StripThinkingHook::new();
+    hook.transform_response(&mut response).unwrap();
+    let output = manager.from_universal_response("anthropic", &response).unwrap();
+    assert_eq!(output["content"][0]["text"], "Sunny.");
+    
+    // ...and from streams, where a span may cross chunk boundaries
+    let deltas = ["Be", "fore<thi", "nk>pondering", " more</thi", "nk>After <", "3"];
+    let streamed: Vec<String> = deltas
+        .iter()
+        .enumerate()
+        .map(|(index, delta)| {
+            let finish_reason = if index == deltas.len() - 1 { json!("stop") } else { Value::Null };
+            let mut chunk = manager.to_universal_stream_chunk("openai", &json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 1,
+                "model": "deepseek-reasoner",
+                "choices": [{"index": 0, "delta": {"content": delta}, "finish_reason": finish_reason}]
+            })).unwrap();
+            hook.transform_stream_chunk(&mut chunk).unwrap();
+            chunk.choices[0].delta.content.clone().unwrap()
+        })
+        .collect();
+    assert_eq!(streamed.concat(), "BeforeAfter <3");
+}
+
+#[test]
+fn test_same_format_request_round_trip_is_lossless() {
+    let manager = TransformerManager::new();
+    let anthropic = json!({
+        "model": "claude-sonnet-4",
+        "max_tokens": 1024,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}],
+        "system": [{"type": "text", "text": "Be brief"}],
+        "metadata": {"user_id": "user_1"},
+        "service_tier": "auto",
+        "stop_sequences": ["END"],
+        "container": "container_1",
+        "mcp_servers": [{"type": "url", "url": "https://mcp.example.com", "name": "example"}],
+        "stream": false
+    });
+    assert_eq!(manager.transform_request("anthropic", "anthropic", &anthropic).unwrap(), anthropic);
+
+    let openai = json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "Hello"}],
+        "stream": false,
+        "stop": ["END"],
+        "seed": 42,
+        "user": "user_1",
+        "service_tier": "flex"
+    });
+    assert_eq!(manager.transform_request("openai", "openai", &openai).unwrap(), openai);
+
+    // Wrappers share their inner wire format's fields
+    let output = manager.transform_request("openai", "openrouter", &openai).unwrap();
+    assert_eq!(output["seed"], 42);
+    assert_eq!(output["service_tier"], "flex");
+
+    let gemini = json!({
+        "contents": [{"role": "user", "parts": [{"text": "Hello"}]}],
+        "systemInstruction": {"parts": [{"text": "Be brief"}]},
+        "safetySettings": [{"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_NONE"}]
+    });
+    let output = manager.transform_request("gemini", "gemini", &gemini).unwrap();
+    assert_eq!(output["systemInstruction"], gemini["systemInstruction"]);
+    assert_eq!(output["safetySettings"], gemini["safetySettings"]);
+
+    let ollama = json!({
+        "model": "llama3",
+        "messages": [{"role": "user", "content": "Hello"}],
+        "stream": false,
+        "format": "json"
+    });
+    let output = manager.transform_request("ollama", "ollama", &ollama).unwrap();
+    assert_eq!(output["format"], "json");
+}
+
+#[test]
+fn test_same_format_response_round_trip_is_lossless() {
+    let manager = TransformerManager::new();
+    let anthropic = json!({
+        "id": "msg_1",
+        "type": "message",
+        "role": "assistant",
+        "content": [{"type": "text", "text": "Hi there"}],
+        "model": "claude-sonnet-4",
+        "stop_reason": "end_turn",
+        "stop_sequence": null,
+        "container": {"id": "container_1", "expires_at": "2026-01-01T00:00:00Z"},
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    });
+    assert_eq!(manager.transform_response("anthropic", "anthropic", &anthropic).unwrap(), anthropic);
+
+    let openai = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi there"}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15},
+        "system_fingerprint": "fp_1",
+        "service_tier": "default"
+    });
+    assert_eq!(manager.transform_response("openai", "openai", &openai).unwrap(), openai);
+
+    let bedrock = json!({
+        "output": {"message": {"role": "assistant", "content": [{"text": "Hi there"}]}},
+        "stopReason": "end_turn",
+        "usage": {"inputTokens": 10, "outputTokens": 5, "totalTokens": 15},
+        "metrics": {"latencyMs": 120}
+    });
+    let output = manager.transform_response("bedrock", "bedrock", &bedrock).unwrap();
+    assert_eq!(output["metrics"], bedrock["metrics"]);
+
+    // OpenRouter's cost metadata sits alongside the passthrough fields
+    let openrouter = json!({
+        "id": "gen-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "anthropic/claude-sonnet-4",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15, "cost": 0.001},
+        "provider": "Anthropic"
+    });
+    let universal = manager.to_universal_response("openrouter", &openrouter).unwrap();
+    let metadata = universal.provider_metadata.unwrap();
+    assert_eq!(metadata["cost"], 0.001);
+    assert_eq!(metadata["openai"]["provider"], "Anthropic");
+}
+
+#[test]
+fn test_unmodeled_fields_do_not_leak_across_formats() {
+    let manager = TransformerManager::new();
+    let anthropic = json!({
+        "model": "claude-sonnet-4",
+        "max_tokens": 1024,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}],
+        "service_tier": "auto",
+        "container": "container_1"
+    });
+    let output = manager.transform_request("anthropic", "openai", &anthropic).unwrap();
+    assert!(output.get("service_tier").is_none(), "{}", output);
+    assert!(output.get("container").is_none(), "{}", output);
+
+    let openai = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15},
+        "system_fingerprint": "fp_1"
+    });
+    let output = manager.transform_response("openai", "anthropic", &openai).unwrap();
+    assert!(output.get("system_fingerprint").is_none(), "{}", output);
+    assert_eq!(output["stop_reason"], "end_turn");
+}
+
+#[test]
+fn test_anthropic_beta_header_passes_through_same_format_only() {
+    use code_routic::config::capabilities::ModelCapabilities;
+
+    let manager = TransformerManager::new();
+    let request = json!({
+        "model": "claude-sonnet-4",
+        "max_tokens": 1024,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}]
+    });
+    let headers = HashMap::from([
+        ("anthropic-beta".to_string(), "context-1m-2025-08-07".to_string()),
+        ("user-agent".to_string(), "cli/1.0".to_string()),
+    ]);
+    let build = |name: &str| {
+        let provider = create_chain_provider(name, json!({"use": [name]}));
+        let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+        let (upstream, _) = manager
+            .build_upstream_request("anthropic", &chain, &provider, "model-a", &request, &headers, &ModelCapabilities::unrestricted())
+            .unwrap();
+        upstream
+    };
+
+    let upstream = build("anthropic");
+    assert_eq!(upstream.header("anthropic-beta"), Some("context-1m-2025-08-07"));
+    assert_eq!(upstream.header("x-api-key"), Some("key"));
+    assert_eq!(upstream.header("user-agent"), None);
+    assert!(upstream.body.get("anthropic_headers").is_none(), "{}", upstream.body);
+
+    let upstream = build("openai");
+    assert_eq!(upstream.header("anthropic-beta"), None);
+}
+
+#[test]
+fn test_unknown_finish_reason_has_no_anthropic_stop_reason() {
+    let manager = TransformerManager::new();
+    let openai = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "function_call_filter"}],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}
+    });
+    let output = manager.transform_response("openai", "anthropic", &openai).unwrap();
+    assert_eq!(output["stop_reason"], Value::Null);
+}
+
+#[test]
+fn test_cache_and_reasoning_usage_mapping() {
+    let manager = TransformerManager::new();
+
+    // Anthropic input_tokens excludes cached tokens; universal prompt_tokens includes them
+    let anthropic = json!({
+        "id": "msg_1",
+        "type": "message",
+        "role": "assistant",
+        "content": [{"type": "text", "text": "Hi"}],
+        "model": "claude-sonnet-4",
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 10, "output_tokens": 5, "cache_creation_input_tokens": 100, "cache_read_input_tokens": 2000}
+    });
+    let usage = manager.to_universal_response("anthropic", &anthropic).unwrap().usage;
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (2110, 5, 2115));
+    assert_eq!((usage.cache_read_tokens, usage.cache_creation_tokens), (Some(2000), Some(100)));
+    assert_eq!(manager.transform_response("anthropic", "anthropic", &anthropic).unwrap(), anthropic);
+
+    let openai = manager.transform_response("anthropic", "openai", &anthropic).unwrap();
+    assert_eq!(openai["usage"]["prompt_tokens"], 2110);
+    assert_eq!(openai["usage"]["prompt_tokens_details"]["cached_tokens"], 2000);
+
+    // OpenAI cached and reasoning tokens reach Anthropic clients
+    let openai = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "o3",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}],
+        "usage": {
+            "prompt_tokens": 1500,
+            "completion_tokens": 300,
+            "total_tokens": 1800,
+            "prompt_tokens_details": {"cached_tokens": 1024},
+            "completion_tokens_details": {"reasoning_tokens": 256}
+        }
+    });
+    let usage = manager.to_universal_response("openai", &openai).unwrap().usage;
+    assert_eq!((usage.cache_read_tokens, usage.reasoning_tokens), (Some(1024), Some(256)));
+    assert_eq!(manager.transform_response("openai", "openai", &openai).unwrap(), openai);
+    let anthropic = manager.transform_response("openai", "anthropic", &openai).unwrap();
+    assert_eq!(anthropic["usage"], json!({"input_tokens": 476, "output_tokens": 300, "cache_read_input_tokens": 1024}));
+
+    // Gemini thoughts are outside candidatesTokenCount but inside the universal completion
+    let gemini = json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": "Hi"}]}, "finishReason": "STOP", "index": 0}],
+        "usageMetadata": {
+            "promptTokenCount": 800,
+            "candidatesTokenCount": 50,
+            "totalTokenCount": 970,
+            "cachedContentTokenCount": 512,
+            "thoughtsTokenCount": 120
+        }
+    });
+    let usage = manager.to_universal_response("gemini", &gemini).unwrap().usage;
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.total_tokens), (800, 170, 970));
+    assert_eq!((usage.cache_read_tokens, usage.reasoning_tokens), (Some(512), Some(120)));
+    let output = manager.transform_response("gemini", "gemini", &gemini).unwrap();
+    assert_eq!(output["usageMetadata"], gemini["usageMetadata"]);
+    let anthropic = manager.transform_response("gemini", "anthropic", &gemini).unwrap();
+    assert_eq!(anthropic["usage"], json!({"input_tokens": 288, "output_tokens": 170, "cache_read_input_tokens": 512}));
+
+    // Providers that report no breakdown leave the fields out
+    let openai = manager.transform_response("anthropic", "openai", &json!({
+        "id": "msg_1",
+        "type": "message",
+        "role": "assistant",
+        "content": [{"type": "text", "text": "Hi"}],
+        "model": "claude-sonnet-4",
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    })).unwrap();
+    assert_eq!(openai["usage"], json!({"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15}));
+}
+
+#[test]
+fn test_stream_usage_mapping() {
+    let manager = TransformerManager::new();
+
+    // Streaming OpenAI requests ask for the final usage chunk unless the client chose otherwise
+    let request = json!({"model": "gpt-4o", "stream": true, "messages": [{"role": "user", "content": "Hi"}]});
+    let output = manager.transform_request("openai", "openai", &request).unwrap();
+    assert_eq!(output["stream_options"], json!({"include_usage": true}));
+    let request = json!({"model": "gpt-4o", "stream": true, "stream_options": {"include_usage": false}, "messages": [{"role": "user", "content": "Hi"}]});
+    let output = manager.transform_request("openai", "openai", &request).unwrap();
+    assert_eq!(output["stream_options"], json!({"include_usage": false}));
+
+    // OpenAI reports counts on a final chunk without choices
+    let openai = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [],
+        "usage": {"prompt_tokens": 1500, "completion_tokens": 300, "total_tokens": 1800, "prompt_tokens_details": {"cached_tokens": 1024}}
+    });
+    let usage = manager.to_universal_stream_chunk("openai", &openai).unwrap().usage.unwrap();
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.cache_read_tokens), (1500, 300, Some(1024)));
+    assert_eq!(manager.transform_stream_chunk("openai", "openai", &openai).unwrap(), openai);
+    let anthropic = manager.transform_stream_chunk("openai", "anthropic", &openai).unwrap();
+    assert_eq!(anthropic["type"], "message_delta");
+    assert_eq!(anthropic["usage"], json!({"input_tokens": 476, "output_tokens": 300, "cache_read_input_tokens": 1024}));
+    let gemini = manager.transform_stream_chunk("openai", "gemini", &openai).unwrap();
+    assert_eq!(gemini["usageMetadata"]["promptTokenCount"], 1500);
+
+    // Gemini sends usageMetadata alongside the candidates
+    let gemini = json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": "Hi"}]}, "finishReason": "STOP", "index": 0}],
+        "usageMetadata": {"promptTokenCount": 800, "candidatesTokenCount": 50, "totalTokenCount": 970, "thoughtsTokenCount": 120}
+    });
+    let usage = manager.to_universal_stream_chunk("gemini", &gemini).unwrap().usage.unwrap();
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens, usage.reasoning_tokens), (800, 170, Some(120)));
+
+    // Anthropic puts the prompt counts on message_start and the output count on message_delta
+    let message_start = json!({
+        "type": "message_start",
+        "message": {
+            "id": "msg_1", "type": "message", "role": "assistant", "content": [], "model": "claude-sonnet-4",
+            "usage": {"input_tokens": 10, "output_tokens": 1, "cache_read_input_tokens": 2000}
+        }
+    });
+    let usage = manager.to_universal_stream_chunk("anthropic", &message_start).unwrap().usage.unwrap();
+    assert_eq!((usage.prompt_tokens, usage.cache_read_tokens), (2010, Some(2000)));
+    let message_delta = json!({
+        "type": "message_delta",
+        "delta": {"stop_reason": "end_turn", "stop_sequence": null},
+        "usage": {"output_tokens": 42}
+    });
+    let chunk = manager.to_universal_stream_chunk("anthropic", &message_delta).unwrap();
+    assert_eq!(chunk.usage.unwrap().completion_tokens, 42);
+    assert_eq!(chunk.choices[0].finish_reason.as_deref(), Some("end_turn"));
+    let openai = manager.transform_stream_chunk("anthropic", "openai", &message_delta).unwrap();
+    assert_eq!(openai["usage"]["completion_tokens"], 42);
+}
+
+#[test]
+fn test_structured_output_mapping() {
+    use code_routic::config::capabilities::ModelCapabilities;
+
+    let manager = TransformerManager::new();
+    let schema = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "type": "object",
+        "properties": {"city": {"type": "string"}, "additionalProperties": {"type": "integer"}},
+        "required": ["city"],
+        "additionalProperties": false
+    });
+    let openai = json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "Where is the Eiffel Tower?"}],
+        "stream": false,
+        "reasoning_effort": "high",
+        "response_format": {
+            "type": "json_schema",
+            "json_schema": {"name": "location", "schema": schema, "strict": true}
+        }
+    });
+    assert_eq!(manager.transform_request("openai", "openai", &openai).unwrap(), openai);
+
+    // Anthropic has no native format: a single forced tool carries the schema
+    let anthropic = manager.transform_request("openai", "anthropic", &openai).unwrap();
+    assert_eq!(anthropic["tool_choice"], json!({"type": "tool", "name": "structured_output"}));
+    assert_eq!(anthropic["tools"][0]["name"], "structured_output");
+    assert_eq!(anthropic["tools"][0]["input_schema"], schema);
+    assert!(anthropic.get("thinking").is_none(), "{}", anthropic);
+
+    // The forced call comes back as the JSON text the client asked for
+    let provider = create_chain_provider("anthropic", json!({"use": ["anthropic"]}));
+    let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+    let (_, tool_names) = manager
+        .transform_request_with_chain("openai", &chain, &openai, &ModelCapabilities::unrestricted())
+        .unwrap();
+    let response = json!({
+        "id": "msg_1",
+        "type": "message",
+        "role": "assistant",
+        "content": [{"type": "tool_use", "id": "toolu_1", "name": "structured_output", "input": {"city": "Paris"}}],
+        "model": "claude-sonnet-4",
+        "stop_reason": "tool_use",
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    });
+    let output = manager.transform_response_with_chain(&chain, "openai", &response, &tool_names).unwrap();
+    let message = &output["choices"][0]["message"];
+    assert_eq!(message["content"], r#"{"city":"Paris"}"#);
+    assert!(message.get("tool_calls").is_none(), "{}", output);
+    assert_eq!(output["choices"][0]["finish_reason"], "stop");
+
+    // Streamed, the call's input arrives as text deltas
+    let events = [
+        json!({"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "structured_output", "input": {}}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "{\"city\": "}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "\"Paris\"}"}}),
+        json!({"type": "message_delta", "delta": {"stop_reason": "tool_use", "stop_sequence": null}, "usage": {"output_tokens": 5}}),
+    ];
+    let chunks: Vec<Value> = events
+        .iter()
+        .map(|event| manager.transform_stream_chunk_with_chain(&chain, "openai", event, &tool_names).unwrap())
+        .collect();
+    let text: String = chunks
+        .iter()
+        .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str())
+        .collect();
+    assert_eq!(text, r#"{"city": "Paris"}"#);
+    assert!(chunks.iter().all(|chunk| chunk["choices"][0]["delta"]["tool_calls"].is_null()), "{:?}", chunks);
+    assert_eq!(chunks[3]["choices"][0]["finish_reason"], "stop");
+
+    // Without response_format the same tool is an ordinary tool call
+    let plain = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]});
+    let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+    let (_, tool_names) = manager
+        .transform_request_with_chain("openai", &chain, &plain, &ModelCapabilities::unrestricted())
+        .unwrap();
+    let output = manager.transform_response_with_chain(&chain, "openai", &response, &tool_names).unwrap();
+    assert_eq!(output["choices"][0]["message"]["tool_calls"][0]["function"]["name"], "structured_output");
+    let universal = manager.to_universal_response("anthropic", &response).unwrap();
+    assert_eq!(universal.choices[0].finish_reason, "tool_use");
+
+    // Gemini gets the native fields, with keywords it rejects removed
+    let gemini = manager.transform_request("openai", "gemini", &openai).unwrap();
+    let config = &gemini["generationConfig"];
+    assert_eq!(config["responseMimeType"], "application/json");
+    assert_eq!(
+        config["responseSchema"],
+        json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}, "additionalProperties": {"type": "integer"}},
+            "required": ["city"]
+        })
+    );
+
+    let json_mode = json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "Hi"}],
+        "response_format": {"type": "json_object"}
+    });
+    let gemini = manager.transform_request("openai", "gemini", &json_mode).unwrap();
+    assert_eq!(gemini["generationConfig"]["responseMimeType"], "application/json");
+    assert!(gemini["generationConfig"].get("responseSchema").is_none());
+    let ollama = manager.transform_request("openai", "ollama", &json_mode).unwrap();
+    assert_eq!(ollama["format"], "json");
+    let anthropic = manager.transform_request("openai", "anthropic", &json_mode).unwrap();
+    assert_eq!(anthropic["tools"][0]["input_schema"], json!({"type": "object"}));
+
+    // Plain text needs nothing
+    let text = json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "Hi"}],
+        "response_format": {"type": "text"}
+    });
+    let anthropic = manager.transform_request("openai", "anthropic", &text).unwrap();
+    assert!(anthropic.get("tools").is_none() && anthropic.get("tool_choice").is_none(), "{}", anthropic);
+}
+
+#[test]
+fn test_tool_choice_mapping() {
+    let manager = TransformerManager::new();
+    let tools = json!([{"name": "get_weather", "description": "Get weather", "input_schema": {"type": "object"}}]);
+    let anthropic = |tool_choice: Value| {
+        json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 100,
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "Weather?"}]}],
+            "tools": tools,
+            "tool_choice": tool_choice
+        })
+    };
+
+    // (Anthropic, OpenAI, Gemini functionCallingConfig)
+    let cases = [
+        (json!({"type": "auto"}), json!("auto"), json!({"mode": "AUTO"})),
+        (json!({"type": "any"}), json!("required"), json!({"mode": "ANY"})),
+        (json!({"type": "none"}), json!("none"), json!({"mode": "NONE"})),
+        (
+            json!({"type": "tool", "name": "get_weather"}),
+            json!({"type": "function", "function": {"name": "get_weather"}}),
+            json!({"mode": "ANY", "allowedFunctionNames": ["get_weather"]}),
+        ),
+    ];
+    for (anthropic_choice, openai_choice, gemini_config) in cases {
+        let request = anthropic(anthropic_choice.clone());
+        let openai = manager.transform_request("anthropic", "openai", &request).unwrap();
+        assert_eq!(openai["tool_choice"], openai_choice);
+        let gemini = manager.transform_request("anthropic", "gemini", &request).unwrap();
+        assert_eq!(gemini["toolConfig"]["functionCallingConfig"], gemini_config);
+
+        // And back again
+        let back = manager.transform_request("openai", "anthropic", &openai).unwrap();
+        assert_eq!(back["tool_choice"]["type"], anthropic_choice["type"], "{}", openai_choice);
+        let back = manager.transform_request("gemini", "anthropic", &gemini).unwrap();
+        assert_eq!(back["tool_choice"], anthropic_choice, "{}", gemini_config);
+    }
+
+    // The parallel flag lives on tool_choice in Anthropic and top-level in OpenAI
+    let request = anthropic(json!({"type": "any", "disable_parallel_tool_use": true}));
+    let openai = manager.transform_request("anthropic", "openai", &request).unwrap();
+    assert_eq!((&openai["tool_choice"], &openai["parallel_tool_calls"]), (&json!("required"), &json!(false)));
+    let back = manager.transform_request("openai", "anthropic", &openai).unwrap();
+    assert_eq!(back["tool_choice"], json!({"type": "any", "disable_parallel_tool_use": true}));
+
+    // Without a tool choice Anthropic gets an explicit auto
+    let mut openai = openai;
+    openai.as_object_mut().unwrap().remove("tool_choice");
+    let back = manager.transform_request("openai", "anthropic", &openai).unwrap();
+    assert_eq!(back["tool_choice"], json!({"type": "auto", "disable_parallel_tool_use": true}));
+
+    // No tools, no flag
+    let openai = json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}], "parallel_tool_calls": false});
+    let back = manager.transform_request("openai", "anthropic", &openai).unwrap();
+    assert!(back.get("tool_choice").is_none(), "{}", back);
+    let universal = manager.to_universal_request("openai", &openai).unwrap();
+    assert_eq!(universal.parallel_tool_calls, Some(false));
+}
+
+#[test]
+fn test_anthropic_stream_blocks_through_chain() {
+    let manager = TransformerManager::new();
+    let provider = create_chain_provider("openai", json!({"use": ["openai"]}));
+    let chunk = |delta: Value, finish_reason: Option<&str>| {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1,
+            "model": "model-a",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+        })
+    };
+    let chunks = [
+        chunk(json!({"role": "assistant", "content": ""}), None),
+        chunk(json!({"content": "Let me check."}), None),
+        chunk(json!({"tool_calls": [{"index": 0, "id": "call_0", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}}]}), None),
+        chunk(json!({"tool_calls": [{"index": 1, "id": "call_1", "type": "function", "function": {"name": "get_time", "arguments": "{}"}}]}), None),
+        chunk(json!({}), Some("tool_calls")),
+        // OpenAI's usage arrives after the finish, on a chunk without choices
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1,
+            "model": "model-a",
+            "choices": [],
+            "usage": {"prompt_tokens": 20, "completion_tokens": 12, "total_tokens": 32}
+        }),
+    ];
+    let events_of = |value: Value| -> Vec<Value> {
+        match value {
+            Value::Array(events) => events,
+            event => vec![event],
+        }
+    };
+    let summary = |events: &[Value]| -> Vec<(String, Value)> {
+        events
+            .iter()
+            .map(|event| (event["type"].as_str().unwrap().to_string(), event["index"].clone()))
+            .collect()
+    };
+
+    let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+    let events: Vec<Value> = chunks
+        .iter()
+        .flat_map(|chunk| events_of(manager.transform_stream_chunk_with_chain(&chain, "anthropic", chunk, &Default::default()).unwrap()))
+        .collect();
+    // Tool calls are numbered after the text block, each block is stopped before the next, and
+    // the stop is sent once, with the usage that followed the finish
+    assert_eq!(summary(&events), vec![
+        ("message_start".to_string(), Value::Null),
+        ("content_block_start".to_string(), json!(0)),
+        ("content_block_delta".to_string(), json!(0)),
+        ("content_block_stop".to_string(), json!(0)),
+        ("content_block_start".to_string(), json!(1)),
+        ("content_block_delta".to_string(), json!(1)),
+        ("content_block_stop".to_string(), json!(1)),
+        ("content_block_start".to_string(), json!(2)),
+        ("content_block_delta".to_string(), json!(2)),
+        ("content_block_stop".to_string(), json!(2)),
+        ("message_delta".to_string(), Value::Null),
+        ("message_stop".to_string(), Value::Null),
+    ]);
+    assert_eq!(events[1]["content_block"], json!({"type": "text", "text": ""}));
+    assert_eq!(events[4]["content_block"], json!({"type": "tool_use", "id": "call_0", "name": "get_weather", "input": {}}));
+    assert_eq!(events[5]["delta"]["partial_json"], "{\"city\":\"Paris\"}");
+    assert_eq!(events[10]["delta"]["stop_reason"], "tool_use");
+    assert_eq!(events[10]["usage"], json!({"input_tokens": 20, "output_tokens": 12}));
+    assert_eq!(chain.finish_stream().unwrap(), json!([]));
+
+    // A stream cut off before its finish reason is closed by finish_stream
+    let chain = manager.resolve_chain(&provider, "model-a").unwrap();
+    for chunk in &chunks[..2] {
+        manager.transform_stream_chunk_with_chain(&chain, "anthropic", chunk, &Default::default()).unwrap();
+    }
+    let closing = events_of(chain.finish_stream().unwrap());
+    assert_eq!(summary(&closing), vec![
+        ("content_block_stop".to_string(), json!(0)),
+        ("message_delta".to_string(), Value::Null),
+        ("message_stop".to_string(), Value::Null),
+    ]);
+}
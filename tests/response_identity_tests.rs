@@ -0,0 +1,202 @@
+//! 响应标识测试模块
+//!
+//! 验证响应 id 改写为 `msg_` 格式、按 `RESPONSE_MODEL` 策略回显请求模型或报告上游模型，
+//! 以及 JSON 与 SSE 响应中的 `x-ccr-provider` / `x-ccr-model` 响应头。
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Method, Request, StatusCode};
+use code_routic::config::capabilities::ModelCapabilities;
+use code_routic::config::types::{Config, Provider, ResponseModel};
+use code_routic::server::response::{json_response, sse_response};
+use code_routic::server::server::ServerSetup;
+use code_routic::transformers::identity::{generate_message_id, message_id, ResponseIdentity, MODEL_HEADER, PROVIDER_HEADER};
+use code_routic::transformers::stream::encode_sse_event;
+use code_routic::transformers::TransformerManager;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tower::ServiceExt;
+
+fn create_provider() -> Provider {
+    Provider {
+        name: "deepseek".to_string(),
+        api_base_url: "https://api.deepseek.com/chat/completions".to_string(),
+        api_key: "key".to_string(),
+        models: vec!["deepseek-chat".to_string()],
+        transformer: Some(serde_json::from_value(json!({"use": ["openai"]})).unwrap()),
+        capabilities: HashMap::new(),
+    }
+}
+
+fn create_openai_response() -> Value {
+    json!({
+        "id": "chatcmpl-abc123",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "deepseek-chat",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+    })
+}
+
+fn create_openai_stream_chunk(delta: Value, finish_reason: Option<&str>) -> Value {
+    json!({
+        "id": "chatcmpl-abc123",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "deepseek-chat",
+        "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}]
+    })
+}
+
+fn is_message_id(id: &str) -> bool {
+    id.len() == 28 && id.starts_with("msg_") && id[4..].chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[test]
+fn test_message_ids() {
+    let id = generate_message_id();
+    assert!(is_message_id(&id), "{}", id);
+    assert_ne!(generate_message_id(), id);
+
+    // Upstream ids map to a stable msg_ id; msg_ ids are kept
+    assert!(is_message_id(&message_id("chatcmpl-abc123")));
+    assert_eq!(message_id("chatcmpl-abc123"), message_id("chatcmpl-abc123"));
+    assert_ne!(message_id("chatcmpl-abc123"), message_id("chatcmpl-abc124"));
+    assert_eq!(message_id("msg_01XFDUDYJgAACzvnptvVoYEL"), "msg_01XFDUDYJgAACzvnptvVoYEL");
+
+    // Even without an identity, Anthropic clients never see upstream ids
+    let manager = TransformerManager::new();
+    let output = manager.transform_response("openai", "anthropic", &create_openai_response()).unwrap();
+    assert_eq!(output["id"], message_id("chatcmpl-abc123"));
+    assert_eq!(output["model"], "deepseek-chat");
+}
+
+#[test]
+fn test_identity_policy_rewrites_model() {
+    let identity = ResponseIdentity::for_route("claude-sonnet-4", "deepseek,deepseek-chat", ResponseModel::Requested);
+    assert_eq!(identity.model(), "claude-sonnet-4");
+    assert_eq!(identity.provider(), "deepseek");
+    assert_eq!(identity.upstream_model(), "deepseek-chat");
+    assert_eq!(identity.headers(), [(PROVIDER_HEADER, "deepseek"), (MODEL_HEADER, "deepseek-chat")]);
+
+    let identity = ResponseIdentity::for_route("claude-sonnet-4", "deepseek,deepseek-chat", ResponseModel::Upstream);
+    assert_eq!(identity.model(), "deepseek-chat");
+
+    // Without a requested model there is nothing to echo
+    let identity = ResponseIdentity::for_route("", "deepseek,deepseek-chat", ResponseModel::Requested);
+    assert_eq!(identity.model(), "deepseek-chat");
+
+    let config: Config = serde_json::from_value(json!({
+        "RESPONSE_MODEL": "upstream",
+        "Providers": [],
+        "Router": {"default": "deepseek,deepseek-chat"}
+    }))
+    .unwrap();
+    assert_eq!(config.response_model, Some(ResponseModel::Upstream));
+    assert_eq!(Config::default().response_model.unwrap_or_default(), ResponseModel::Requested);
+}
+
+#[test]
+fn test_chain_identity_applies_to_json_and_stream() {
+    let manager = TransformerManager::new();
+    let provider = create_provider();
+    let identity = ResponseIdentity::for_route("claude-sonnet-4", "deepseek,deepseek-chat", ResponseModel::Requested);
+    let chain = manager
+        .resolve_chain(&provider, "deepseek-chat")
+        .unwrap()
+        .with_identity(identity.clone());
+    let (_, tool_names) = manager
+        .transform_request_with_chain(
+            "anthropic",
+            &chain,
+            &json!({
+                "model": "claude-sonnet-4",
+                "max_tokens": 100,
+                "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}]
+            }),
+            &ModelCapabilities::unrestricted(),
+        )
+        .unwrap();
+
+    let output = manager
+        .transform_response_with_chain(&chain, "anthropic", &create_openai_response(), &tool_names)
+        .unwrap();
+    assert_eq!(output["id"], identity.id());
+    assert_eq!(output["model"], "claude-sonnet-4");
+
+    // The opening chunk becomes message_start with the same id and model
+    let events: Vec<Value> = [
+        create_openai_stream_chunk(json!({"role": "assistant", "content": ""}), None),
+        create_openai_stream_chunk(json!({"content": "Hi"}), None),
+        create_openai_stream_chunk(json!({}), Some("stop")),
+    ]
+    .iter()
+    .map(|chunk| manager.transform_stream_chunk_with_chain(&chain, "anthropic", chunk, &tool_names).unwrap())
+    .collect();
+    assert_eq!(events[0]["type"], "message_start");
+    assert_eq!(events[0]["message"]["id"], identity.id());
+    assert_eq!(events[0]["message"]["model"], "claude-sonnet-4");
+    assert_eq!(events[1][0]["type"], "content_block_start");
+    assert_eq!(events[1][0]["content_block"], json!({"type": "text", "text": ""}));
+    assert_eq!(events[1][1]["delta"]["text"], "Hi");
+    assert_eq!(events[2], json!({"type": "content_block_stop", "index": 0}));
+    let closing = chain.finish_stream().unwrap();
+    assert_eq!(closing[0]["type"], "message_delta");
+    assert_eq!(closing[0]["delta"]["stop_reason"], "end_turn");
+    assert_eq!(closing[1]["type"], "message_stop");
+
+    // OpenAI clients get the same id and model in every chunk
+    let chunk = manager
+        .transform_stream_chunk_with_chain(&chain, "openai", &create_openai_stream_chunk(json!({"content": "Hi"}), None), &tool_names)
+        .unwrap();
+    assert_eq!(chunk["id"], identity.id());
+    assert_eq!(chunk["model"], "claude-sonnet-4");
+}
+
+#[tokio::test]
+async fn test_identity_headers_on_json_and_sse_responses() {
+    let identity = ResponseIdentity::for_route("claude-sonnet-4", "deepseek,deepseek-chat", ResponseModel::Requested);
+
+    let response = json_response(StatusCode::OK, &json!({"id": identity.id()}), &identity);
+    assert_eq!(response.headers()["content-type"], "application/json");
+    assert_eq!(response.headers()[PROVIDER_HEADER], "deepseek");
+    assert_eq!(response.headers()[MODEL_HEADER], "deepseek-chat");
+
+    let events = [json!({"type": "message_stop"}), json!({"id": "chunk"})];
+    let body: String = events.iter().map(encode_sse_event).collect();
+    let response = sse_response(Body::from(body), &identity);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    assert_eq!(response.headers()[PROVIDER_HEADER], "deepseek");
+    assert_eq!(response.headers()[MODEL_HEADER], "deepseek-chat");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(
+        String::from_utf8(body.to_vec()).unwrap(),
+        "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\ndata: {\"id\":\"chunk\"}\n\n"
+    );
+}
+
+#[tokio::test]
+async fn test_messages_endpoint_reports_route() {
+    let mut config = Config::default();
+    config.router.default = "deepseek,deepseek-chat".to_string();
+    let app = ServerSetup::create_server(config).await;
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/messages")
+        .header("content-type", "application/json")
+        .header("host", "127.0.0.1:3456")
+        .body(Body::from(
+            json!({"model": "claude-sonnet-4", "max_tokens": 100, "messages": [{"role": "user", "content": "Hi"}]}).to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[PROVIDER_HEADER], "deepseek");
+    assert_eq!(response.headers()[MODEL_HEADER], "deepseek-chat");
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert!(is_message_id(body["id"].as_str().unwrap()));
+    assert_eq!(body["model"], "claude-sonnet-4");
+}
@@ -5,8 +5,11 @@
 
 #[cfg(test)]
 mod router_tests {
-    use code_routic::config::types::{Config, Provider, RouterConfig};
-    use code_routic::router::route_logic::{RouteLogic, RouteRequest, RequestBody, SystemMessage, Tool};
+    use code_routic::config::capabilities::{CapabilityRegistry, ModelCapabilityOverrides};
+    use code_routic::config::types::{Config, Provider, RouteRule, RouterConfig};
+    use code_routic::router::route_logic::{Metadata, RouteLogic, RouteRequest, RouteSource, RequestBody, SystemMessage, Tool, Usage};
+    use code_routic::router::rules::{default_rules, glob_match};
+    use std::collections::HashMap;
     
     #[test]
     fn test_get_specified_provider_model() {
@@ -18,8 +21,10 @@ mod router_tests {
                 thinking: None,
                 tools: None,
                 metadata: None,
+                has_images: false,
             },
             session_id: None,
+            headers: HashMap::new(),
         };
         
         let result = RouteLogic::get_use_model(&req, 1000, &config, None);
@@ -36,8 +41,10 @@ mod router_tests {
                 thinking: None,
                 tools: None,
                 metadata: None,
+                has_images: false,
             },
             session_id: None,
+            headers: HashMap::new(),
         };
         
         let result = RouteLogic::get_use_model(&req, 1000, &config, None);
@@ -56,8 +63,10 @@ mod router_tests {
                 thinking: None,
                 tools: None,
                 metadata: None,
+                has_images: false,
             },
             session_id: None,
+            headers: HashMap::new(),
         };
         
         let result = RouteLogic::get_use_model(&req, 1000, &config, None);
@@ -76,8 +85,10 @@ mod router_tests {
                 thinking: Some(true),
                 tools: None,
                 metadata: None,
+                has_images: false,
             },
             session_id: None,
+            headers: HashMap::new(),
         };
         
         let result = RouteLogic::get_use_model(&req, 1000, &config, None);
@@ -97,8 +108,10 @@ mod router_tests {
                 thinking: None,
                 tools: None,
                 metadata: None,
+                has_images: false,
             },
             session_id: None,
+            headers: HashMap::new(),
         };
         
         let result = RouteLogic::get_use_model(&req, 15000, &config, None);
@@ -121,13 +134,20 @@ mod router_tests {
                 thinking: None,
                 tools: None,
                 metadata: None,
+                has_images: false,
             },
             session_id: None,
+            headers: HashMap::new(),
         };
         
         let config = create_test_config();
         let result = RouteLogic::get_use_model(&req, 1000, &config, None);
         assert_eq!(result, "openrouter,anthropic/claude-3-opus");
+
+        // Only the second system block can carry the marker
+        let mut req = req;
+        req.body.system.as_mut().unwrap().remove(0);
+        assert_eq!(RouteLogic::get_use_model(&req, 1000, &config, None), "openrouter,anthropic/claude-sonnet-4");
     }
     
     #[test]
@@ -148,14 +168,208 @@ mod router_tests {
                 thinking: None,
                 tools: Some(tools),
                 metadata: None,
+                has_images: false,
             },
             session_id: None,
+            headers: HashMap::new(),
         };
         
         let result = RouteLogic::get_use_model(&req, 1000, &config, None);
         assert_eq!(result, "openrouter,anthropic/claude-3-sonnet");
     }
     
+    #[test]
+    fn test_skip_target_without_tool_support() {
+        let mut config = create_test_config();
+        config.router.web_search = Some("openrouter,anthropic/claude-3-sonnet".to_string());
+        config.providers[0].capabilities.insert(
+            "anthropic/claude-3-sonnet".to_string(),
+            ModelCapabilityOverrides { tools: Some(false), ..Default::default() },
+        );
+        
+        let req = RouteRequest {
+            body: RequestBody {
+                model: Some("claude-3-haiku".to_string()),
+                system: None,
+                thinking: None,
+                tools: Some(vec![Tool { tool_type: Some("web_search_20250305".to_string()) }]),
+                metadata: None,
+                has_images: false,
+            },
+            session_id: None,
+            headers: HashMap::new(),
+        };
+        
+        // The web search target cannot take tools, so routing falls through to the default
+        let result = RouteLogic::get_use_model(&req, 1000, &config, None);
+        assert_eq!(result, "openrouter,anthropic/claude-sonnet-4");
+    }
+    
+    #[test]
+    fn test_skip_target_with_small_context_window() {
+        let mut config = create_test_config();
+        config.router.think = Some("openrouter,anthropic/claude-3-opus".to_string());
+        config.providers[0].capabilities.insert(
+            "anthropic/claude-3-opus".to_string(),
+            ModelCapabilityOverrides { context_window: Some(4000), ..Default::default() },
+        );
+        
+        let req = RouteRequest {
+            body: RequestBody {
+                model: Some("claude-3-haiku".to_string()),
+                system: None,
+                thinking: Some(true),
+                tools: None,
+                metadata: None,
+                has_images: false,
+            },
+            session_id: None,
+            headers: HashMap::new(),
+        };
+        
+        assert_eq!(RouteLogic::get_use_model(&req, 3000, &config, None), "openrouter,anthropic/claude-3-opus");
+        assert_eq!(RouteLogic::get_use_model(&req, 5000, &config, None), "openrouter,anthropic/claude-sonnet-4");
+    }
+    
+    #[test]
+    fn test_rules_from_config() {
+        let mut config = create_test_config();
+        config.router.think = Some("openrouter,anthropic/claude-3-opus".to_string());
+        config.router.rules = serde_json::from_value(serde_json::json!([
+            {"name": "team", "headers": {"X-Team": "infra*"}, "target": "openrouter,anthropic/claude-3-opus"},
+            {"name": "vision", "images": true, "target": "openrouter,anthropic/claude-3-sonnet"},
+            {"model": "claude-*-haiku*", "tokens_below": 500, "tools": false, "target": "openrouter,anthropic/claude-3-haiku"},
+            {"system": "^Project: (?<project>\\w+)", "target": "openrouter,anthropic/${project}"},
+            {"user_id": "*_session_abc", "tool_type": "web_search*", "target": "openrouter,anthropic/claude-3-sonnet"}
+        ]))
+        .unwrap();
+
+        let mut req = RouteRequest::from_anthropic_body(
+            &serde_json::json!({
+                "model": "claude-3-5-haiku-20241022",
+                "messages": [{"role": "user", "content": [{"type": "text", "text": "Hi"}]}],
+                "thinking": {"type": "enabled", "budget_tokens": 1024}
+            }),
+            HashMap::new(),
+        );
+        // Configured rules come before the default ones
+        assert_eq!(RouteLogic::get_use_model(&req, 100, &config, None), "openrouter,anthropic/claude-3-haiku");
+        assert_eq!(RouteLogic::get_use_model(&req, 1000, &config, None), "openrouter,anthropic/claude-3-opus");
+
+        req.headers.insert("x-team".to_string(), "infra-platform".to_string());
+        assert_eq!(RouteLogic::get_use_model(&req, 100, &config, None), "openrouter,anthropic/claude-3-opus");
+        req.headers.clear();
+
+        req.body.has_images = true;
+        assert_eq!(RouteLogic::get_use_model(&req, 100, &config, None), "openrouter,anthropic/claude-3-sonnet");
+        req.body.has_images = false;
+
+        // Capture groups of the system pattern expand into the target
+        req.body.thinking = None;
+        req.body.system = Some(vec![SystemMessage { text: Some("Project: mars".to_string()) }]);
+        assert_eq!(RouteLogic::get_use_model(&req, 1000, &config, None), "openrouter,anthropic/mars");
+
+        req.body.system = None;
+        req.body.tools = Some(vec![Tool { tool_type: Some("web_search_20250305".to_string()) }]);
+        req.body.metadata = Some(Metadata { user_id: Some("user_1_session_abc".to_string()) });
+        assert_eq!(RouteLogic::get_use_model(&req, 1000, &config, None), "openrouter,anthropic/claude-3-sonnet");
+        req.body.metadata = None;
+        assert_eq!(RouteLogic::get_use_model(&req, 1000, &config, None), "openrouter,anthropic/claude-sonnet-4");
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let mut config = create_test_config();
+        config.router.long_context = Some("openrouter,anthropic/claude-3-sonnet".to_string());
+        config.router.background = Some("openrouter,anthropic/claude-3-opus".to_string());
+        let rules = default_rules(&config.router);
+        let names: Vec<&str> = rules.iter().map(|rule| rule.label()).collect();
+        assert_eq!(names, ["long_context", "long_context", "subagent", "background"]);
+
+        // A long previous turn keeps the session on the long-context model
+        let req = RouteRequest::from_anthropic_body(&serde_json::json!({"model": "claude-3-haiku"}), HashMap::new());
+        let usage = Usage { input_tokens: 70000 };
+        assert_eq!(RouteLogic::get_use_model(&req, 30000, &config, Some(&usage)), "openrouter,anthropic/claude-3-sonnet");
+        assert_eq!(RouteLogic::get_use_model(&req, 10000, &config, Some(&usage)), "openrouter,anthropic/claude-sonnet-4");
+
+        assert!(glob_match("claude-3-5-haiku*", "claude-3-5-haiku-20241022"));
+        assert!(glob_match("gpt-?o", "gpt-4o"));
+        assert!(!glob_match("gpt-4.1", "gpt-411"));
+        assert!(!glob_match("claude", "claude-3"));
+
+        // Patterns are compiled when the config loads; an invalid regex fails the load
+        let invalid = serde_json::from_value::<Vec<RouteRule>>(serde_json::json!([{"system": "(", "target": "a,b"}]));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_route_decision() {
+        let mut config = create_test_config();
+        config.router.long_context = Some("openrouter,anthropic/claude-3-sonnet".to_string());
+        config.router.think = Some("openrouter,anthropic/claude-3-opus".to_string());
+        config.providers[0].capabilities.insert(
+            "anthropic/claude-3-opus".to_string(),
+            ModelCapabilityOverrides { context_window: Some(4000), ..Default::default() },
+        );
+        let mut req = RouteRequest::from_anthropic_body(
+            &serde_json::json!({"model": "claude-3-haiku", "thinking": {"type": "enabled"}}),
+            HashMap::new(),
+        );
+
+        let decision = RouteLogic::decide(&req, 70000, &config, None, &[]);
+        assert_eq!(decision.target, "openrouter,anthropic/claude-3-sonnet");
+        assert_eq!(decision.source, RouteSource::Rule);
+        assert_eq!(decision.matched.as_deref(), Some("long_context"));
+        assert_eq!(decision.thresholds.long_context, 60000);
+        assert_eq!(decision.thresholds.tokens_above, Some(60000));
+        assert_eq!(decision.reason(), "rule:long_context; tokens=70000; tokens_above=60000");
+
+        // The think rule matches but its target's context window is too small
+        let usage = Usage { input_tokens: 1000 };
+        let decision = RouteLogic::decide(&req, 5000, &config, Some(&usage), &[]);
+        assert_eq!(decision.source, RouteSource::Default);
+        assert_eq!(decision.target, "openrouter,anthropic/claude-sonnet-4");
+        assert_eq!(decision.last_input_tokens, Some(1000));
+        assert_eq!(decision.skipped.len(), 1);
+        assert_eq!(decision.skipped[0].rule, "think");
+        assert_eq!(decision.reason(), "default; tokens=5000");
+        let value = serde_json::to_value(&decision).unwrap();
+        assert_eq!(value["source"], "default");
+        assert_eq!(value["thresholds"], serde_json::json!({"long_context": 60000}));
+        assert_eq!(value["skipped"][0]["target"], "openrouter,anthropic/claude-3-opus");
+
+        req.body.model = Some("openrouter,anthropic/claude-3-haiku".to_string());
+        let decision = RouteLogic::decide(&req, 70000, &config, None, &[]);
+        assert_eq!(decision.source, RouteSource::Explicit);
+        assert_eq!(decision.reason(), "explicit; tokens=70000");
+    }
+    
+    #[test]
+    fn test_capability_registry_lookup() {
+        let mut config = create_test_config();
+        config.providers[0].capabilities.insert(
+            "anthropic/claude-3-haiku".to_string(),
+            ModelCapabilityOverrides { max_output_tokens: Some(2048), ..Default::default() },
+        );
+        let registry = CapabilityRegistry::from_config(&config);
+        
+        // Vendor prefixes and dotted versions resolve to the same built-in entry
+        let sonnet = registry.lookup("openrouter", "anthropic/claude-3.5-sonnet");
+        assert_eq!(sonnet, registry.lookup("anthropic", "claude-3-5-sonnet-20241022"));
+        assert_eq!(sonnet, registry.lookup("bedrock", "us.anthropic.claude-3-5-sonnet-20241022-v2:0"));
+        assert_eq!(sonnet.max_output_tokens, 8192);
+        assert!(sonnet.tools && sonnet.vision && !sonnet.thinking);
+        
+        let haiku = registry.lookup_route("openrouter,anthropic/claude-3-haiku");
+        assert_eq!(haiku.max_output_tokens, 2048);
+        assert_eq!(haiku.context_window, 200_000);
+        
+        // Unknown models are never restricted
+        let unknown = registry.lookup("ollama", "llama3.2");
+        assert!(unknown.tools && unknown.thinking);
+        assert_eq!(unknown.max_output_tokens, u32::MAX);
+    }
+    
     fn create_test_config() -> Config {
         Config {
             api_key: None,
@@ -167,6 +381,8 @@ mod router_tests {
             non_interactive_mode: Some(false),
             api_timeout_ms: Some(600000),
             custom_router_path: None,
+            response_model: None,
+            project_roots: vec![],
             providers: vec![
                 Provider {
                     name: "openrouter".to_string(),
@@ -178,6 +394,7 @@ mod router_tests {
                         "anthropic/claude-3-opus".to_string(),
                     ],
                     transformer: None,
+                    capabilities: std::collections::HashMap::new(),
                 }
             ],
             router: RouterConfig {
@@ -187,6 +404,7 @@ mod router_tests {
                 long_context: None,
                 long_context_threshold: Some(60000),
                 web_search: None,
+                rules: vec![],
             },
             transformers: None,
             extra: std::collections::HashMap::new(),
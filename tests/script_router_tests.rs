@@ -0,0 +1,175 @@
+//! 脚本路由器测试模块
+//!
+//! 验证 `CUSTOM_ROUTER_PATH` 指向的 Rhai 路由脚本：按请求、token 数、上一次用量与配置返回目标，
+//! 文件修改后重新加载，以及加载或运行出错时记录日志并回退到内置路由逻辑。
+
+use code_routic::config::types::Config;
+use code_routic::router::custom_router::CustomRouter;
+use code_routic::router::route_logic::{RouteLogic, RouteRequest, Usage};
+use code_routic::router::script_router::ScriptRouter;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const ROUTER_SCRIPT: &str = r#"
+    fn route(request, token_count, last_usage, config) {
+        if request.body.model == "claude-3-5-haiku" {
+            return config.Router["default"];
+        }
+        if token_count > 1000 || (last_usage != () && last_usage.input_tokens > 5000) {
+            return "gemini,gemini-2.5-pro";
+        }
+        if request.headers["x-team"] == "infra" {
+            return "deepseek,deepseek-chat";
+        }
+    }
+"#;
+
+fn write_script(path: &Path, source: &str, age: u64) {
+    std::fs::write(path, source).unwrap();
+    // Each version gets a distinct modification time, however fast the test runs
+    let modified = SystemTime::now() - Duration::from_secs(age);
+    std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+}
+
+fn create_route_request(model: &str) -> RouteRequest {
+    RouteRequest::from_anthropic_body(&json!({"model": model, "messages": []}), HashMap::new())
+}
+
+fn create_config() -> Config {
+    let mut config = Config::default();
+    config.router.default = "openrouter,anthropic/claude-sonnet-4".to_string();
+    config
+}
+
+#[test]
+fn test_script_routes_requests() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("router.rhai");
+    write_script(&path, ROUTER_SCRIPT, 10);
+    let router = ScriptRouter::load(&path);
+    let config = create_config();
+
+    let req = create_route_request("claude-sonnet-4");
+    assert_eq!(router.route(&req, 100, None, &config).unwrap(), None);
+    assert_eq!(router.route(&req, 2000, None, &config).unwrap().as_deref(), Some("gemini,gemini-2.5-pro"));
+    let usage = Usage { input_tokens: 6000 };
+    assert_eq!(router.route(&req, 100, Some(&usage), &config).unwrap().as_deref(), Some("gemini,gemini-2.5-pro"));
+    let haiku = create_route_request("claude-3-5-haiku");
+    assert_eq!(
+        router.route(&haiku, 100, None, &config).unwrap().as_deref(),
+        Some("openrouter,anthropic/claude-sonnet-4")
+    );
+
+    let mut req = create_route_request("claude-sonnet-4");
+    req.headers.insert("x-team".to_string(), "infra".to_string());
+    let routers: Vec<Arc<dyn CustomRouter>> = vec![Arc::new(router)];
+    assert_eq!(RouteLogic::get_use_model_with_routers(&req, 100, &config, None, &routers), "deepseek,deepseek-chat");
+
+    // The script is consulted through the config path as well
+    let mut config = create_config();
+    config.custom_router_path = Some(path.to_string_lossy().into_owned());
+    let router = ScriptRouter::from_config(&config).unwrap();
+    assert_eq!(router.path(), path);
+    assert!(ScriptRouter::from_config(&create_config()).is_none());
+}
+
+#[test]
+fn test_script_reloads_on_change() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("router.rhai");
+    write_script(&path, r#"fn route(request, token_count, last_usage, config) { "a,first" }"#, 30);
+    let router = ScriptRouter::load(&path);
+    let config = create_config();
+    let req = create_route_request("claude-sonnet-4");
+    assert_eq!(router.route(&req, 0, None, &config).unwrap().as_deref(), Some("a,first"));
+
+    write_script(&path, r#"fn route(request, token_count, last_usage, config) { "a,second" }"#, 20);
+    assert_eq!(router.route(&req, 0, None, &config).unwrap().as_deref(), Some("a,second"));
+
+    // A broken edit keeps the last working version
+    write_script(&path, "fn route(request, token_count, last_usage, config) { ", 10);
+    assert_eq!(router.route(&req, 0, None, &config).unwrap().as_deref(), Some("a,second"));
+
+    // So does deleting the file
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(router.route(&req, 0, None, &config).unwrap().as_deref(), Some("a,second"));
+}
+
+#[test]
+fn test_script_sees_config_without_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.rhai");
+    let script = r#"
+        fn route(request, token_count, last_usage, config) {
+            if "APIKEY" in config {
+                return "leak,config";
+            }
+            for provider in config.Providers {
+                if "api_key" in provider {
+                    return "leak,provider";
+                }
+            }
+            config.Providers[0].name + "," + config.Providers[0].models[0] + "," + config.Router["default"]
+        }
+    "#;
+    write_script(&path, script, 10);
+    let router = ScriptRouter::load(&path);
+    let mut config = create_config();
+    config.api_key = Some("secret".to_string());
+    config.providers = serde_json::from_value(json!([
+        {"name": "deepseek", "api_base_url": "https://api.deepseek.com/chat/completions", "api_key": "sk-1", "models": ["deepseek-chat"]}
+    ]))
+    .unwrap();
+
+    let req = create_route_request("claude-sonnet-4");
+    assert_eq!(
+        router.route(&req, 0, None, &config).unwrap().as_deref(),
+        Some("deepseek,deepseek-chat,openrouter,anthropic/claude-sonnet-4")
+    );
+    // A changed config replaces the cached view
+    config.router.default = "deepseek,deepseek-chat".to_string();
+    assert_eq!(
+        router.route(&req, 0, None, &config).unwrap().as_deref(),
+        Some("deepseek,deepseek-chat,deepseek,deepseek-chat")
+    );
+}
+
+#[test]
+fn test_script_errors_fall_through() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = create_config();
+    let req = create_route_request("claude-sonnet-4");
+
+    // Runtime errors and wrong return types are errors the router logic logs and skips
+    let path = dir.path().join("failing.rhai");
+    write_script(&path, r#"fn route(request, token_count, last_usage, config) { throw "boom" }"#, 10);
+    let failing = ScriptRouter::load(&path);
+    assert!(failing.route(&req, 0, None, &config).unwrap_err().to_string().contains("boom"));
+    let path = dir.path().join("number.rhai");
+    write_script(&path, "fn route(request, token_count, last_usage, config) { 42 }", 10);
+    let number = ScriptRouter::load(&path);
+    assert!(number.route(&req, 0, None, &config).is_err());
+
+    // Scripts that never load make no decision
+    let path = dir.path().join("no_route.rhai");
+    write_script(&path, "fn other() { 1 }", 10);
+    let no_route = ScriptRouter::load(&path);
+    assert_eq!(no_route.route(&req, 0, None, &config).unwrap(), None);
+    let missing = ScriptRouter::load(dir.path().join("missing.rhai"));
+    assert_eq!(missing.route(&req, 0, None, &config).unwrap(), None);
+
+    // Runaway scripts hit the operation limit
+    let path = dir.path().join("loop.rhai");
+    write_script(&path, "fn route(request, token_count, last_usage, config) { loop {} }", 10);
+    let looping = ScriptRouter::load(&path);
+
+    let routers: Vec<Arc<dyn CustomRouter>> =
+        vec![Arc::new(failing), Arc::new(number), Arc::new(no_route), Arc::new(missing), Arc::new(looping)];
+    assert_eq!(
+        RouteLogic::get_use_model_with_routers(&req, 0, &config, None, &routers),
+        "openrouter,anthropic/claude-sonnet-4"
+    );
+}
@@ -0,0 +1,210 @@
+//! 脚本转换器测试模块
+//!
+//! 在临时插件目录中写入 Rhai 脚本，验证脚本的加载、选项传递、
+//! 在 provider `use` 链中的执行顺序，以及沙箱的资源限制。
+
+use code_routic::config::capabilities::ModelCapabilities;
+use code_routic::config::types::{Provider, TransformerConfig};
+use code_routic::transformers::{
+    chain::TransformerHook,
+    script::{ScriptLimits, ScriptModule, ScriptTransformer},
+    TransformerError, TransformerManager,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const PREFIX_SCRIPT: &str = r#"
+fn name() { "prefix" }
+
+fn transform_request(request, options) {
+    request.temperature = options.temperature;
+    for i in 0..request.messages.len() {
+        let message = request.messages[i];
+        if message.role == "user" && type_of(message.content) == "string" {
+            request.messages[i].content = options.prefix + message.content;
+        }
+    }
+    request
+}
+
+fn transform_response(response, options) {
+    for i in 0..response.choices.len() {
+        response.choices[i].message.content += options.suffix;
+    }
+    response
+}
+"#;
+
+fn write_script(dir: &Path, file: &str, source: &str) {
+    std::fs::write(dir.join(file), source).unwrap();
+}
+
+fn create_provider(use_entries: Value) -> Provider {
+    Provider {
+        name: "deepseek".to_string(),
+        api_base_url: "https://api.deepseek.com/chat/completions".to_string(),
+        api_key: "key".to_string(),
+        models: vec!["deepseek-chat".to_string()],
+        transformer: Some(serde_json::from_value(json!({"use": use_entries})).unwrap()),
+        capabilities: HashMap::new(),
+    }
+}
+
+fn create_openai_response() -> Value {
+    json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "deepseek-chat",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Sunny"}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+    })
+}
+
+#[test]
+fn test_scripts_load_from_config_and_run_in_chains() {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(dir.path(), "prefix.rhai", PREFIX_SCRIPT);
+
+    let mut manager = TransformerManager::new();
+    let errors = manager.load_scripts(
+        &[TransformerConfig {
+            path: "prefix.rhai".to_string(),
+            options: Some(json!({"prefix": "[routed] ", "suffix": "!", "temperature": 0.2})),
+        }],
+        dir.path(),
+    );
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let listed = manager.list_transformers();
+    let entry = listed.iter().find(|info| info.name == "prefix").unwrap();
+    assert_eq!(
+        serde_json::to_value(&entry.source).unwrap()["path"],
+        dir.path().join("prefix.rhai").display().to_string()
+    );
+
+    // Options from Config.transformers apply when the use entry gives none
+    let provider = create_provider(json!(["openai", "prefix"]));
+    let chain = manager.resolve_chain(&provider, "deepseek-chat").unwrap();
+    let (request, tool_names) = manager
+        .transform_request_with_chain(
+            "openai",
+            &chain,
+            &json!({"model": "deepseek-chat", "messages": [{"role": "user", "content": "Weather?"}]}),
+            &ModelCapabilities::unrestricted(),
+        )
+        .unwrap();
+    assert_eq!(request["messages"][0]["content"], "[routed] Weather?");
+    assert_eq!(request["temperature"], 0.2);
+
+    let response = manager
+        .transform_response_with_chain(&chain, "openai", &create_openai_response(), &tool_names)
+        .unwrap();
+    assert_eq!(response["choices"][0]["message"]["content"], "Sunny!");
+
+    // Options on the use entry replace them
+    let provider = create_provider(json!(["openai", ["prefix", {"prefix": "> ", "suffix": "?", "temperature": 0.9}]]));
+    let chain = manager.resolve_chain(&provider, "deepseek-chat").unwrap();
+    let (request, _) = manager
+        .transform_request_with_chain(
+            "openai",
+            &chain,
+            &json!({"model": "deepseek-chat", "messages": [{"role": "user", "content": "Hi"}]}),
+            &ModelCapabilities::unrestricted(),
+        )
+        .unwrap();
+    assert_eq!(request["messages"][0]["content"], "> Hi");
+    assert_eq!(request["temperature"], 0.9);
+}
+
+#[test]
+fn test_script_load_failures_are_reported_and_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    write_script(dir.path(), "broken.rhai", "fn transform_request(request, options) { request.");
+    write_script(dir.path(), "empty.rhai", "let x = 1;");
+    write_script(dir.path(), "maxtoken.rhai", "fn transform_request(request, options) { request }");
+    write_script(dir.path(), "response_only.rhai", "fn transform_response(response, options) { }");
+
+    let mut manager = TransformerManager::new();
+    let configs: Vec<TransformerConfig> = ["broken.rhai", "empty.rhai", "missing.rhai", "maxtoken.rhai", "response_only.rhai"]
+        .iter()
+        .map(|path| TransformerConfig { path: path.to_string(), options: None })
+        .collect();
+    let errors = manager.load_scripts(&configs, dir.path());
+
+    // Syntax error, no transform functions, unreadable file, clash with the built-in maxtoken
+    assert_eq!(errors.len(), 4, "{:?}", errors);
+    assert!(errors.iter().all(|e| matches!(e, TransformerError::Configuration(_))));
+    assert!(manager.list_transformers().iter().any(|info| info.name == "response_only"));
+    assert!(!manager.list_transformers().iter().any(|info| info.name == "broken"));
+
+    // A script that only defines a response hook leaves requests alone
+    let provider = create_provider(json!(["openai", "response_only"]));
+    let chain = manager.resolve_chain(&provider, "deepseek-chat").unwrap();
+    let (request, _) = manager
+        .transform_request_with_chain(
+            "openai",
+            &chain,
+            &json!({"model": "deepseek-chat", "messages": [{"role": "user", "content": "Hi"}]}),
+            &ModelCapabilities::unrestricted(),
+        )
+        .unwrap();
+    assert_eq!(request["messages"][0]["content"], "Hi");
+}
+
+#[test]
+fn test_script_sandbox_limits() {
+    let manager = TransformerManager::new();
+    let mut request = manager
+        .to_universal_request("openai", &json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}]}))
+        .unwrap();
+
+    // Runaway loops hit the operation limit instead of hanging the server
+    let spin = ScriptModule::compile(
+        "fn transform_request(request, options) { loop { } }",
+        "spin",
+        "spin.rhai".into(),
+        None,
+        ScriptLimits { max_operations: 10_000, ..ScriptLimits::default() },
+    )
+    .unwrap();
+    let hook = ScriptTransformer::new(Arc::new(spin), None);
+    assert!(matches!(hook.transform_request(&mut request), Err(TransformerError::Script(_))));
+
+    // Unbounded growth hits the size limits
+    let grow = ScriptModule::compile(
+        r#"fn transform_request(request, options) { let s = "x"; loop { s += s; } }"#,
+        "grow",
+        "grow.rhai".into(),
+        None,
+        ScriptLimits { max_string_size: 1024, ..ScriptLimits::default() },
+    )
+    .unwrap();
+    let hook = ScriptTransformer::new(Arc::new(grow), None);
+    assert!(matches!(hook.transform_request(&mut request), Err(TransformerError::Script(_))));
+
+    // eval and module imports are not available
+    for source in [
+        r#"fn transform_request(request, options) { eval("1") }"#,
+        r#"import "os" as os; fn transform_request(request, options) { request }"#,
+    ] {
+        let result = ScriptModule::compile(source, "escape", "escape.rhai".into(), None, ScriptLimits::default())
+            .and_then(|module| ScriptTransformer::new(Arc::new(module), None).transform_request(&mut request));
+        assert!(result.is_err(), "{}", source);
+    }
+
+    // Returning a body of the wrong shape is an error, not a silent corruption
+    let bad = ScriptModule::compile(
+        "fn transform_request(request, options) { #{ model: 42 } }",
+        "bad",
+        "bad.rhai".into(),
+        None,
+        ScriptLimits::default(),
+    )
+    .unwrap();
+    let hook = ScriptTransformer::new(Arc::new(bad), None);
+    assert!(hook.transform_request(&mut request).is_err());
+    assert_eq!(request.model, "m");
+}
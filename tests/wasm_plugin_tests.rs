@@ -0,0 +1,329 @@
+//! WASM 插件测试模块
+//!
+//! 用 WAT 编写测试插件并写入临时插件目录，验证插件 ABI、转换链与路由逻辑的集成、
+//! fuel 与内存限制，以及默认不提供文件系统访问的 WASI 环境。
+
+use code_routic::config::capabilities::ModelCapabilities;
+use code_routic::config::types::{Config, Provider};
+use code_routic::plugins::{load_plugins_dir, WasmLimits, WasmPlugin, WasmRouter, WasmTransformer};
+use code_routic::router::custom_router::CustomRouter;
+use code_routic::router::route_logic::{RequestBody, RouteLogic, RouteRequest};
+use code_routic::transformers::{chain::TransformerHook, TransformerError, TransformerManager};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Offset of the constant output in the plugins below, clear of the allocator's heap.
+const DATA_OFFSET: u64 = 16;
+
+/// A module with the required `memory`/`alloc` exports plus `body`.
+fn plugin_wat(imports: &str, body: &str) -> String {
+    format!(
+        r#"(module
+            {imports}
+            (memory (export "memory") 1)
+            (global $heap (mut i32) (i32.const 4096))
+            (func (export "alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (local.get $ptr))
+            {body})"#
+    )
+}
+
+/// A data segment holding `output` and the packed pointer/length a function returns for it.
+fn constant_output(output: &str) -> (String, String) {
+    let data = format!(r#"(data (i32.const {}) "{}")"#, DATA_OFFSET, output.replace('"', "\\\""));
+    let packed = format!("(i64.const {})", (DATA_OFFSET << 32) | output.len() as u64);
+    (data, packed)
+}
+
+fn write_plugin(dir: &Path, file: &str, wat: &str) {
+    std::fs::write(dir.join(file), wat::parse_str(wat).unwrap()).unwrap();
+}
+
+fn create_route_request(model: &str) -> RouteRequest {
+    RouteRequest {
+        body: RequestBody {
+            model: Some(model.to_string()),
+            system: None,
+            thinking: None,
+            tools: None,
+            metadata: None,
+            has_images: false,
+        },
+        session_id: None,
+        headers: HashMap::new(),
+    }
+}
+
+fn create_config() -> Config {
+    let mut config = Config::default();
+    config.router.default = "openrouter,anthropic/claude-sonnet-4".to_string();
+    config
+}
+
+#[test]
+fn test_transform_plugin_runs_in_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    let (data, packed) = constant_output(
+        r#"{"model":"wasm-model","messages":[{"role":"user","content":"rewritten by wasm"}],"stream":false}"#,
+    );
+    write_plugin(
+        dir.path(),
+        "rewrite.wasm",
+        &plugin_wat(
+            "",
+            &format!(
+                r#"{data}
+                (func (export "transform_request") (param i32 i32) (result i64) {packed})
+                (func (export "transform_response") (param i32 i32) (result i64) (i64.const 0))"#
+            ),
+        ),
+    );
+
+    let loaded = load_plugins_dir(dir.path(), WasmLimits::default());
+    assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+    assert!(loaded.routers.is_empty());
+    let plugin = loaded.transformers[0].clone();
+    assert_eq!(plugin.name(), "rewrite");
+
+    let mut manager = TransformerManager::new();
+    manager
+        .register_plugin("rewrite", WasmTransformer::factory(plugin.clone()), plugin.path())
+        .unwrap();
+    let listed = manager.list_transformers();
+    let entry = listed.iter().find(|info| info.name == "rewrite").unwrap();
+    assert_eq!(serde_json::to_value(entry).unwrap()["source"]["type"], "plugin");
+
+    // A plugin may not shadow a registered transformer
+    assert!(manager
+        .register_plugin("maxtoken", WasmTransformer::factory(plugin.clone()), plugin.path())
+        .is_err());
+
+    let provider = Provider {
+        name: "deepseek".to_string(),
+        api_base_url: "https://api.deepseek.com/chat/completions".to_string(),
+        api_key: "key".to_string(),
+        models: vec!["deepseek-chat".to_string()],
+        transformer: Some(serde_json::from_value(json!({"use": ["openai", "rewrite"]})).unwrap()),
+        capabilities: HashMap::new(),
+    };
+    let chain = manager.resolve_chain(&provider, "deepseek-chat").unwrap();
+    let (request, tool_names) = manager
+        .transform_request_with_chain(
+            "openai",
+            &chain,
+            &json!({"model": "deepseek-chat", "messages": [{"role": "user", "content": "Hi"}]}),
+            &ModelCapabilities::unrestricted(),
+        )
+        .unwrap();
+    assert_eq!(request["model"], "wasm-model");
+    assert_eq!(request["messages"][0]["content"], "rewritten by wasm");
+
+    // Returning 0 leaves the response unchanged
+    let upstream = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "deepseek-chat",
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Sunny"}, "finish_reason": "stop"}],
+        "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+    });
+    let response = manager
+        .transform_response_with_chain(&chain, "openai", &upstream, &tool_names)
+        .unwrap();
+    assert_eq!(response["choices"][0]["message"]["content"], "Sunny");
+}
+
+#[test]
+fn test_router_plugin_decides_before_builtin_checks() {
+    let dir = tempfile::tempdir().unwrap();
+    let (data, packed) = constant_output(r#""ollama,qwen2.5-coder""#);
+    write_plugin(
+        dir.path(),
+        "pin.wasm",
+        &plugin_wat("", &format!(r#"{data} (func (export "route") (param i32 i32) (result i64) {packed})"#)),
+    );
+    let (data, packed) = constant_output("null");
+    write_plugin(
+        dir.path(),
+        "abstain.wasm",
+        &plugin_wat("", &format!(r#"{data} (func (export "route") (param i32 i32) (result i64) {packed})"#)),
+    );
+    write_plugin(
+        dir.path(),
+        "broken.wasm",
+        &plugin_wat("", r#"(func (export "route") (param i32 i32) (result i64) unreachable)"#),
+    );
+
+    let loaded = load_plugins_dir(dir.path(), WasmLimits::default());
+    assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+    let routers: Vec<Arc<dyn CustomRouter>> = loaded
+        .routers
+        .into_iter()
+        .map(|plugin| Arc::new(WasmRouter::new(plugin)) as Arc<dyn CustomRouter>)
+        .collect();
+    // Loaded in file-name order
+    let names: Vec<&str> = routers.iter().map(|router| router.name()).collect();
+    assert_eq!(names, vec!["abstain", "broken", "pin"]);
+
+    // The abstaining and failing routers are skipped; the third decides
+    let config = create_config();
+    let req = create_route_request("claude-sonnet-4");
+    let model = RouteLogic::get_use_model_with_routers(&req, 1000, &config, None, &routers);
+    assert_eq!(model, "ollama,qwen2.5-coder");
+
+    // An explicit "provider,model" request still wins over plugins
+    let req = create_route_request("openrouter,anthropic/claude-3-haiku");
+    let model = RouteLogic::get_use_model_with_routers(&req, 1000, &config, None, &routers);
+    assert_eq!(model, "openrouter,anthropic/claude-3-haiku");
+
+    // Without a decision the built-in logic applies
+    let model = RouteLogic::get_use_model_with_routers(&create_route_request("claude-sonnet-4"), 1000, &config, None, &routers[..2]);
+    assert_eq!(model, "openrouter,anthropic/claude-sonnet-4");
+}
+
+#[test]
+fn test_plugin_fuel_and_memory_limits() {
+    let spin = WasmPlugin::from_bytes(
+        "spin",
+        "spin.wasm".into(),
+        &wat::parse_str(plugin_wat(
+            "",
+            r#"(func (export "transform_request") (param i32 i32) (result i64) (loop $spin (br $spin)) (i64.const 0))"#,
+        ))
+        .unwrap(),
+        WasmLimits { fuel: 100_000, ..WasmLimits::default() },
+    )
+    .unwrap();
+    let manager = TransformerManager::new();
+    let mut request = manager
+        .to_universal_request("openai", &json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}]}))
+        .unwrap();
+    let hook = WasmTransformer::new(Arc::new(spin), None);
+    assert!(matches!(hook.transform_request(&mut request), Err(TransformerError::Plugin(_))));
+    assert_eq!(request.model, "m");
+
+    // 2 MiB of initial memory against a 1 MiB cap fails when the call instantiates
+    let wat = plugin_wat("", r#"(func (export "transform_request") (param i32 i32) (result i64) (i64.const 0))"#)
+        .replace(r#"(memory (export "memory") 1)"#, r#"(memory (export "memory") 32)"#);
+    let large = WasmPlugin::from_bytes(
+        "large",
+        "large.wasm".into(),
+        &wat::parse_str(wat).unwrap(),
+        WasmLimits { max_memory_bytes: 1024 * 1024, ..WasmLimits::default() },
+    )
+    .unwrap();
+    let hook = WasmTransformer::new(Arc::new(large), None);
+    assert!(matches!(hook.transform_request(&mut request), Err(TransformerError::Plugin(_))));
+}
+
+#[test]
+fn test_out_of_bounds_buffers_are_rejected() {
+    let manager = TransformerManager::new();
+    let mut request = manager
+        .to_universal_request("openai", &json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}]}))
+        .unwrap();
+
+    // A 4 GiB output length is refused before the host allocates it
+    let body = format!(
+        r#"(func (export "transform_request") (param i32 i32) (result i64) (i64.const {}))"#,
+        (DATA_OFFSET << 32) | 0xffff_ffff
+    );
+    let plugin = WasmPlugin::from_bytes(
+        "huge",
+        "huge.wasm".into(),
+        &wat::parse_str(plugin_wat("", &body)).unwrap(),
+        WasmLimits::default(),
+    )
+    .unwrap();
+    let hook = WasmTransformer::new(Arc::new(plugin), None);
+    assert!(matches!(hook.transform_request(&mut request), Err(TransformerError::Plugin(_))));
+
+    // random_get past the end of memory fails with EFAULT (21); anything else traps
+    let imports = r#"(import "wasi_snapshot_preview1" "random_get"
+        (func $random_get (param i32 i32) (result i32)))"#;
+    let body = r#"(func (export "transform_request") (param i32 i32) (result i64)
+        (if (i32.ne (call $random_get (i32.const 16) (i32.const -1)) (i32.const 21))
+            (then unreachable))
+        (if (i32.ne (call $random_get (i32.const 65530) (i32.const 16)) (i32.const 21))
+            (then unreachable))
+        (if (i32.ne (call $random_get (i32.const 16) (i32.const 16)) (i32.const 0))
+            (then unreachable))
+        (i64.const 0))"#;
+    let plugin = WasmPlugin::from_bytes(
+        "random",
+        "random.wasm".into(),
+        &wat::parse_str(plugin_wat(imports, body)).unwrap(),
+        WasmLimits::default(),
+    )
+    .unwrap();
+    let hook = WasmTransformer::new(Arc::new(plugin), None);
+    hook.transform_request(&mut request).unwrap();
+    assert_eq!(request.model, "m");
+}
+
+#[test]
+fn test_wasi_has_no_filesystem_access() {
+    // path_open must be refused with ENOTCAPABLE (76); anything else traps
+    let imports = r#"(import "wasi_snapshot_preview1" "path_open"
+        (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "fd_write"
+        (func $fd_write (param i32 i32 i32 i32) (result i32)))"#;
+    let body = r#"(func (export "transform_request") (param $ptr i32) (param $len i32) (result i64)
+        (if (i32.ne (call $path_open (i32.const 3) (i32.const 0) (local.get $ptr) (local.get $len)
+                (i32.const 0) (i64.const 0) (i64.const 0) (i32.const 0) (i32.const 0))
+            (i32.const 76))
+            (then unreachable))
+        ;; stdout is accepted and discarded
+        (i32.store (i32.const 0) (local.get $ptr))
+        (i32.store (i32.const 4) (local.get $len))
+        (if (i32.ne (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)) (i32.const 0))
+            (then unreachable))
+        ;; iovecs past the end of memory fail with EFAULT (21), a negative count with EINVAL (28)
+        (if (i32.ne (call $fd_write (i32.const 1) (i32.const 0x7ffffffc) (i32.const 2) (i32.const 8)) (i32.const 21))
+            (then unreachable))
+        (if (i32.ne (call $fd_write (i32.const 1) (i32.const -8) (i32.const 2) (i32.const 8)) (i32.const 21))
+            (then unreachable))
+        (if (i32.ne (call $fd_write (i32.const 1) (i32.const 0) (i32.const -1) (i32.const 8)) (i32.const 28))
+            (then unreachable))
+        (i64.const 0))"#;
+    let dir = tempfile::tempdir().unwrap();
+    write_plugin(dir.path(), "sandboxed.wasm", &plugin_wat(imports, body));
+    let plugin = WasmPlugin::load(dir.path().join("sandboxed.wasm"), WasmLimits::default()).unwrap();
+
+    let manager = TransformerManager::new();
+    let mut request = manager
+        .to_universal_request("openai", &json!({"model": "m", "messages": [{"role": "user", "content": "Hi"}]}))
+        .unwrap();
+    let hook = WasmTransformer::new(Arc::new(plugin), None);
+    hook.transform_request(&mut request).unwrap();
+    assert_eq!(request.model, "m");
+}
+
+#[test]
+fn test_invalid_plugins_are_reported_and_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    // No alloc export
+    write_plugin(
+        dir.path(),
+        "no_alloc.wasm",
+        r#"(module (memory (export "memory") 1) (func (export "route") (param i32 i32) (result i64) (i64.const 0)))"#,
+    );
+    // No plugin functions at all
+    write_plugin(dir.path(), "idle.wasm", &plugin_wat("", ""));
+    std::fs::write(dir.path().join("garbage.wasm"), b"not wasm").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), b"ignored").unwrap();
+
+    let loaded = load_plugins_dir(dir.path(), WasmLimits::default());
+    assert_eq!(loaded.errors.len(), 3, "{:?}", loaded.errors);
+    assert!(loaded.errors.iter().all(|e| matches!(e, TransformerError::Configuration(_))));
+    assert!(loaded.transformers.is_empty() && loaded.routers.is_empty());
+
+    // A missing plugins directory is not an error
+    let loaded = load_plugins_dir(&dir.path().join("missing"), WasmLimits::default());
+    assert!(loaded.errors.is_empty());
+}
@@ -0,0 +1,254 @@
+//! 网络搜索测试模块
+//!
+//! 验证 Anthropic `web_search` 服务端工具到 OpenAI `web_search_options`、OpenRouter web 插件、
+//! Gemini `google_search` 的转换，以及上游的引用注释与 grounding 元数据转换回 Anthropic
+//! `server_tool_use` / `web_search_tool_result` 块与带引用的文本块。
+
+use code_routic::transformers::TransformerManager;
+use serde_json::{json, Value};
+
+fn create_anthropic_request() -> Value {
+    json!({
+        "model": "claude-sonnet-4",
+        "max_tokens": 1024,
+        "messages": [{"role": "user", "content": [{"type": "text", "text": "What is the capital of France?"}]}],
+        "tools": [
+            {
+                "name": "Read",
+                "description": "Read a file",
+                "input_schema": {"type": "object", "properties": {"file_path": {"type": "string"}}}
+            },
+            {
+                "type": "web_search_20250305",
+                "name": "web_search",
+                "max_uses": 3,
+                "allowed_domains": ["wikipedia.org"],
+                "user_location": {"type": "approximate", "city": "Paris", "country": "FR"}
+            }
+        ]
+    })
+}
+
+fn create_anthropic_search_response() -> Value {
+    json!({
+        "id": "msg_01XFDUDYJgAACzvnptvVoYEL",
+        "type": "message",
+        "role": "assistant",
+        "content": [
+            {"type": "text", "text": "Let me search."},
+            {"type": "server_tool_use", "id": "srvtoolu_01", "name": "web_search", "input": {"query": "capital of France"}},
+            {
+                "type": "web_search_tool_result",
+                "tool_use_id": "srvtoolu_01",
+                "content": [{
+                    "type": "web_search_result",
+                    "url": "https://en.wikipedia.org/wiki/Paris",
+                    "title": "Paris - Wikipedia",
+                    "encrypted_content": "EqgfCioIARgB",
+                    "page_age": "2 days ago"
+                }]
+            },
+            {
+                "type": "text",
+                "text": "Paris is the capital of France",
+                "citations": [{
+                    "type": "web_search_result_location",
+                    "url": "https://en.wikipedia.org/wiki/Paris",
+                    "title": "Paris - Wikipedia",
+                    "encrypted_index": "Eo8BCioIAhgB",
+                    "cited_text": "Paris is the capital and largest city of France."
+                }]
+            },
+            {"type": "text", "text": "."}
+        ],
+        "model": "claude-sonnet-4",
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 100, "output_tokens": 20}
+    })
+}
+
+#[test]
+fn test_web_search_tool_translated_per_provider() {
+    let manager = TransformerManager::new();
+    let request = create_anthropic_request();
+
+    let openai = manager.transform_request("anthropic", "openai", &request).unwrap();
+    assert_eq!(openai["tools"].as_array().unwrap().len(), 1, "{}", openai);
+    assert_eq!(openai["tools"][0]["function"]["name"], "Read");
+    assert_eq!(
+        openai["web_search_options"],
+        json!({"user_location": {"type": "approximate", "approximate": {"city": "Paris", "country": "FR"}}})
+    );
+
+    let gemini = manager.transform_request("anthropic", "gemini", &request).unwrap();
+    assert_eq!(gemini["tools"][0]["function_declarations"][0]["name"], "Read");
+    assert_eq!(gemini["tools"][1], json!({"google_search": {}}));
+
+    let openrouter = manager.transform_request("anthropic", "openrouter", &request).unwrap();
+    assert_eq!(openrouter["plugins"], json!([{"id": "web"}]));
+    assert_eq!(openrouter["web_search_options"]["user_location"]["approximate"]["city"], "Paris");
+
+    // A server tool alone does not produce an empty function tool list
+    let mut search_only = request.clone();
+    search_only["tools"] = json!([request["tools"][1]]);
+    let openai = manager.transform_request("anthropic", "openai", &search_only).unwrap();
+    assert!(openai.get("tools").is_none(), "{}", openai);
+    let openrouter = manager.transform_request("anthropic", "openrouter", &search_only).unwrap();
+    assert_eq!(openrouter["plugins"], json!([{"id": "web"}]));
+
+    // Back to Anthropic the server tool is rebuilt with its settings
+    let anthropic = manager.transform_request("anthropic", "anthropic", &request).unwrap();
+    assert_eq!(anthropic["tools"], request["tools"]);
+    let anthropic = manager
+        .transform_request("gemini", "anthropic", &json!({
+            "contents": [{"role": "user", "parts": [{"text": "Hi"}]}],
+            "tools": [{"googleSearch": {}}]
+        }))
+        .unwrap();
+    assert_eq!(anthropic["tools"], json!([{"type": "web_search_20250305", "name": "web_search"}]));
+    let anthropic = manager
+        .transform_request("openrouter", "anthropic", &json!({
+            "model": "openai/gpt-4o:online",
+            "messages": [{"role": "user", "content": "Hi"}]
+        }))
+        .unwrap();
+    assert_eq!(anthropic["tools"][0]["name"], "web_search");
+}
+
+#[test]
+fn test_openai_citations_become_search_blocks() {
+    let manager = TransformerManager::new();
+    let response = json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "gpt-4o-search-preview",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": "The capital is Paris, per Wikipedia.",
+                "annotations": [{
+                    "type": "url_citation",
+                    "url_citation": {"start_index": 15, "end_index": 20, "url": "https://en.wikipedia.org/wiki/Paris", "title": "Paris"}
+                }]
+            },
+            "finish_reason": "stop"
+        }],
+        "usage": {"prompt_tokens": 10, "completion_tokens": 8, "total_tokens": 18}
+    });
+    assert_eq!(manager.transform_response("openai", "openai", &response).unwrap(), response);
+
+    let output = manager.transform_response("openai", "anthropic", &response).unwrap();
+    let content = output["content"].as_array().unwrap();
+    let types: Vec<&str> = content.iter().map(|block| block["type"].as_str().unwrap()).collect();
+    assert_eq!(types, ["server_tool_use", "web_search_tool_result", "text", "text", "text"]);
+    assert!(content[0]["id"].as_str().unwrap().starts_with("srvtoolu_"));
+    assert_eq!(content[0]["name"], "web_search");
+    assert_eq!(content[1]["tool_use_id"], content[0]["id"]);
+    assert_eq!(content[1]["content"][0]["url"], "https://en.wikipedia.org/wiki/Paris");
+    assert_eq!(content[1]["content"][0]["type"], "web_search_result");
+
+    assert_eq!(content[2], json!({"type": "text", "text": "The capital is "}));
+    assert_eq!(content[3]["text"], "Paris");
+    assert_eq!(content[3]["citations"][0]["type"], "web_search_result_location");
+    assert_eq!(content[3]["citations"][0]["url"], "https://en.wikipedia.org/wiki/Paris");
+    assert_eq!(content[3]["citations"][0]["cited_text"], "Paris");
+    assert_eq!(content[4], json!({"type": "text", "text": ", per Wikipedia."}));
+}
+
+#[test]
+fn test_gemini_grounding_becomes_search_blocks() {
+    let manager = TransformerManager::new();
+    // "Café" is 5 bytes but 4 characters: offsets are converted, not copied
+    let response = json!({
+        "candidates": [{
+            "content": {"role": "model", "parts": [{"text": "Café de Flore is in Paris."}]},
+            "finishReason": "STOP",
+            "index": 0,
+            "groundingMetadata": {
+                "webSearchQueries": ["cafe de flore location"],
+                "groundingChunks": [
+                    {"web": {"uri": "https://example.com/flore", "title": "example.com"}},
+                    {"web": {"uri": "https://example.org/paris", "title": "example.org"}}
+                ],
+                "groundingSupports": [{
+                    "segment": {"startIndex": 21, "endIndex": 26, "text": "Paris"},
+                    "groundingChunkIndices": [0, 1]
+                }]
+            }
+        }],
+        "usageMetadata": {"promptTokenCount": 10, "candidatesTokenCount": 8, "totalTokenCount": 18}
+    });
+    let gemini = manager.transform_response("gemini", "gemini", &response).unwrap();
+    assert_eq!(gemini["candidates"][0]["groundingMetadata"], response["candidates"][0]["groundingMetadata"]);
+
+    let universal = manager.to_universal_response("gemini", &response).unwrap();
+    let web_search = universal.choices[0].web_search.as_ref().unwrap();
+    assert_eq!(web_search.queries, ["cafe de flore location"]);
+    assert_eq!(web_search.sources.len(), 2);
+    assert_eq!((web_search.citations[0].start_index, web_search.citations[0].end_index), (20, 25));
+
+    let output = manager.transform_response("gemini", "anthropic", &response).unwrap();
+    let content = output["content"].as_array().unwrap();
+    assert_eq!(content[0]["input"], json!({"query": "cafe de flore location"}));
+    assert_eq!(content[1]["content"].as_array().unwrap().len(), 2);
+    assert_eq!(content[2]["text"], "Café de Flore is in ");
+    assert_eq!(content[3]["text"], "Paris");
+    assert_eq!(content[3]["citations"].as_array().unwrap().len(), 2);
+    assert_eq!(content[4]["text"], ".");
+
+    let openai = manager.transform_response("gemini", "openai", &response).unwrap();
+    let annotations = openai["choices"][0]["message"]["annotations"].as_array().unwrap();
+    assert_eq!(annotations.len(), 2);
+    assert_eq!(annotations[1]["url_citation"]["start_index"], 20);
+    assert_eq!(annotations[1]["url_citation"]["url"], "https://example.org/paris");
+}
+
+#[test]
+fn test_anthropic_search_results_round_trip_and_convert() {
+    let manager = TransformerManager::new();
+    let response = create_anthropic_search_response();
+
+    // Encrypted content and indexes survive, so the client can send them back
+    assert_eq!(manager.transform_response("anthropic", "anthropic", &response).unwrap(), response);
+
+    let openai = manager.transform_response("anthropic", "openai", &response).unwrap();
+    let message = &openai["choices"][0]["message"];
+    assert_eq!(message["content"], "Let me search.Paris is the capital of France.");
+    assert_eq!(
+        message["annotations"],
+        json!([{
+            "type": "url_citation",
+            "url_citation": {
+                "start_index": 14,
+                "end_index": 44,
+                "url": "https://en.wikipedia.org/wiki/Paris",
+                "title": "Paris - Wikipedia",
+                "content": "Paris is the capital and largest city of France."
+            }
+        }])
+    );
+
+    let gemini = manager.transform_response("anthropic", "gemini", &response).unwrap();
+    let grounding = &gemini["candidates"][0]["groundingMetadata"];
+    assert_eq!(grounding["webSearchQueries"], json!(["capital of France"]));
+    assert_eq!(grounding["groundingChunks"][0]["web"]["uri"], "https://en.wikipedia.org/wiki/Paris");
+    assert_eq!(grounding["groundingSupports"][0]["segment"]["text"], "Paris is the capital of France");
+
+    // Search blocks in the conversation history are accepted and only reach Anthropic
+    let request = json!({
+        "model": "claude-sonnet-4",
+        "max_tokens": 1024,
+        "messages": [
+            {"role": "user", "content": [{"type": "text", "text": "What is the capital of France?"}]},
+            {"role": "assistant", "content": response["content"]},
+            {"role": "user", "content": [{"type": "text", "text": "And of Spain?"}]}
+        ]
+    });
+    let anthropic = manager.transform_request("anthropic", "anthropic", &request).unwrap();
+    assert_eq!(anthropic["messages"][1]["content"][1], response["content"][1]);
+    assert_eq!(anthropic["messages"][1]["content"][2], response["content"][2]);
+    let openai = manager.transform_request("anthropic", "openai", &request).unwrap();
+    assert_eq!(openai["messages"][1]["content"], "Let me search.Paris is the capital of France.");
+}